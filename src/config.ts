@@ -1,3 +1,4 @@
+import type { DrawingPreset } from './webview/webview';
 import { configUtils } from 'vscode-extensions-json-generator/utils';
 
 export enum Backends {
@@ -104,6 +105,12 @@ export interface Config {
    */
   'useExperimentalDataTransfer': boolean;
 
+  /**
+   * @default []
+   * @description Named drawing options of the image viewer, applied from the toolbar or the context menu of the image. Options that are not set take their default value.
+   */
+  'viewerUi.drawingPresets': DrawingPreset[];
+
   /**
    * @default false
    * @description Invert mouse wheel zoom direction in the image viewer.
@@ -111,6 +118,63 @@ export interface Config {
    */
   'viewerUi.invertMouseWheelZoom': boolean;
 
+  /**
+   * @default {}
+   * @description Key bindings of the image viewer, by command name, e.g. { "nextImage": "j", "zoomIn": "ctrl+ArrowUp" }. An empty string unbinds a command. Press ? in the viewer to list the active shortcuts.
+   */
+  'viewerUi.keyBindings': { [command: string]: string };
+
+  /**
+   * @default 1024
+   * @description Memory, in megabytes, that the images loaded in the image viewer may take. Above it, the least recently shown images are unloaded, and loaded again when shown. Pinned and visible images are kept.
+   */
+  'viewerUi.memoryBudgetMB': number;
+
+  /**
+   * @default "auto"
+   * @description Notation of float pixel values in the image viewer. 'auto' uses whichever of fixed and scientific notation is shorter.
+   * @enum [ "auto", "fixed", "scientific" ]
+   */
+  'viewerUi.pixelValues.floatNotation': 'auto' | 'fixed' | 'scientific';
+
+  /**
+   * @default 7
+   * @description Precision of float pixel values in the image viewer: the maximum number of characters for 'auto' notation, the number of digits after the decimal point otherwise.
+   */
+  'viewerUi.pixelValues.precision': number;
+
+  /**
+   * @default "decimal"
+   * @description Base of integer pixel values in the image viewer.
+   * @enum [ "decimal", "hex", "binary" ]
+   */
+  'viewerUi.pixelValues.integerBase': 'decimal' | 'hex' | 'binary';
+
+  /**
+   * @default false
+   * @description Show the normalized value used for display next to the raw pixel value in the image viewer.
+   */
+  'viewerUi.pixelValues.showNormalized': boolean;
+
+  /**
+   * @default 1
+   * @description Number of images before and after the shown image, in the order of the image list, whose data the image viewer requests ahead of time. 0 disables prefetching images.
+   */
+  'viewerUi.prefetch.images': number;
+
+  /**
+   * @default 2
+   * @description Number of batch items before and after the shown batch item whose data the image viewer requests ahead of time. 0 disables prefetching batch items.
+   */
+  'viewerUi.prefetch.batchItems': number;
+
+  /**
+   * @default "expression"
+   * @description Remember the drawing options (coloring, clipping, invert, ...) of images across debug sessions and restore them when the same expression is shown again. 'expressionShapeAndType' only restores them when the shape and data type also match.
+   * @enum [ "off", "expression", "expressionShapeAndType" ]
+   */
+  'viewerUi.rememberDrawingOptions': 'off' | 'expression' | 'expressionShapeAndType';
+
   /**
    * @default false
    * @description Show supported types diagnostic information in the tree view (mainly for debugging).
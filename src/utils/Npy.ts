@@ -0,0 +1,29 @@
+const NPY_MAGIC = [0x93, ...Array.from('NUMPY', c => c.charCodeAt(0))];
+// magic, version (2 bytes) and header length (2 bytes)
+const NPY_PREAMBLE_LENGTH = NPY_MAGIC.length + 4;
+const NPY_ALIGNMENT = 64;
+
+/**
+ * Encodes a row-major uint8 array of shape (height, width) as a NumPy .npy file (format 1.0).
+ */
+export function encodeUint8Npy(
+  width: number,
+  height: number,
+  data: ArrayLike<number>,
+): Uint8Array {
+  const dict = `{'descr': '|u1', 'fortran_order': False, 'shape': (${height}, ${width}), }`;
+  // the header is padded with spaces and ends with a newline, so the data is aligned
+  const headerLength
+    = Math.ceil((NPY_PREAMBLE_LENGTH + dict.length + 1) / NPY_ALIGNMENT) * NPY_ALIGNMENT
+      - NPY_PREAMBLE_LENGTH;
+  const header = `${dict.padEnd(headerLength - 1, ' ')}\n`;
+
+  const bytes = new Uint8Array(NPY_PREAMBLE_LENGTH + headerLength + data.length);
+  bytes.set(NPY_MAGIC, 0);
+  bytes[NPY_MAGIC.length] = 1; // major version
+  bytes[NPY_MAGIC.length + 1] = 0; // minor version
+  new DataView(bytes.buffer).setUint16(NPY_MAGIC.length + 2, headerLength, true);
+  bytes.set(Array.from(header, c => c.charCodeAt(0)), NPY_PREAMBLE_LENGTH);
+  bytes.set(data, NPY_PREAMBLE_LENGTH + headerLength);
+  return bytes;
+}
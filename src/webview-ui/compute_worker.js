@@ -0,0 +1,20 @@
+// Runs the pixel computations of the webview off the UI thread, with the same wasm module.
+// Webviews cannot start workers from their resource URIs, so the webview starts this worker
+// from a blob that sets `self.baseUri` and imports this script.
+// eslint-disable-next-line no-undef
+__webpack_public_path__ = `${self.baseUri}/`;
+
+const webview = import('./pkg/webview');
+
+self.onmessage = async (event) => {
+  const { runComputeTask } = await webview;
+  try {
+    self.postMessage(runComputeTask(event.data));
+  }
+  catch (error) {
+    self.postMessage({
+      texture_id: event.data.task.texture_id,
+      error: String(error),
+    });
+  }
+};
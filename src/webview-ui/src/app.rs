@@ -3,9 +3,13 @@ use crate::application_state::app_state::ElementsStoreKey;
 use crate::application_state::app_state::GlobalDrawingOptions;
 use crate::application_state::app_state::ImageObject;
 use crate::application_state::app_state::StoreAction;
+use crate::application_state::app_state::UiAction;
 use crate::application_state::app_state::UpdateDrawingOptions;
+use crate::application_state::image_errors::ImageError;
 use crate::application_state::images::DrawingContext;
 use crate::application_state::images::ImageAvailability;
+use crate::application_state::thumbnails::Thumbnail;
+use crate::application_state::thumbnails::ThumbnailKey;
 use crate::coloring::Coloring;
 use crate::coloring::DrawingOptions;
 use crate::colormap;
@@ -22,12 +26,18 @@ use crate::components::context_menu_view::ContextMenu;
 use crate::components::main::Main;
 use crate::configurations;
 use crate::keyboard_event::KeyboardHandler;
+use crate::mouse_events::AnnotationHandler;
+use crate::mouse_events::LineProfileHandler;
 use crate::mouse_events::PanHandler;
 use crate::mouse_events::ShiftScrollHandler;
 use crate::mouse_events::ZoomHandler;
+use crate::mouse_events::ZoomSelectionHandler;
+use crate::rendering::renderer::request_render;
 use crate::rendering::renderer::Renderer;
+use crate::rendering::rendering_context::AnnotationData;
 use crate::rendering::rendering_context::ColorBarData;
 use crate::rendering::rendering_context::ImageViewData;
+use crate::rendering::rendering_context::LineProfileData;
 use crate::rendering::rendering_context::RenderingContext;
 use crate::rendering::rendering_context::ViewContext;
 use crate::vscode;
@@ -42,6 +52,7 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use stylist::yew::use_style;
 use wasm_bindgen::prelude::*;
+use web_sys::CanvasRenderingContext2d;
 use web_sys::HtmlCanvasElement;
 use web_sys::HtmlElement;
 use web_sys::WebGl2RenderingContext;
@@ -176,6 +187,109 @@ fn rendering_context() -> impl RenderingContext {
                 None
             }
         }
+
+        fn get_line_profile_data(&self, view_id: ViewId) -> Option<LineProfileData> {
+            let dispatch = Dispatch::<AppState>::global();
+            let state = dispatch.get();
+            let (line, cursor) = state.line_profiles.borrow().visible_line(view_id)?;
+            let image_size = view_context().get_image_size_for_view(view_id)?;
+            let html_element = state
+                .image_views
+                .borrow()
+                .get_node_ref(view_id)
+                .cast::<HtmlElement>()?;
+            let camera = state.view_cameras.borrow().get(view_id);
+
+            Some(LineProfileData {
+                html_element,
+                camera,
+                image_size,
+                line,
+                cursor: cursor.map(|t| line.point_at(t)),
+            })
+        }
+
+        fn get_annotation_data(&self, view_id: ViewId) -> Option<AnnotationData> {
+            let dispatch = Dispatch::<AppState>::global();
+            let state = dispatch.get();
+            let cv = state.image_views.borrow().get_currently_viewing(view_id)?;
+            let image_id = cv.id().clone();
+            let has_annotations = {
+                let annotations = state.annotations.borrow();
+                annotations.get(&image_id).is_some_and(|a| !a.is_empty())
+                    || annotations.draft(view_id).outline().is_some()
+            };
+            if !has_annotations {
+                return None;
+            }
+            let image_size = view_context().get_image_size_for_view(view_id)?;
+            let html_element = state
+                .image_views
+                .borrow()
+                .get_node_ref(view_id)
+                .cast::<HtmlElement>()?;
+            let camera = state.view_cameras.borrow().get(view_id);
+
+            Some(AnnotationData {
+                html_element,
+                camera,
+                image_size,
+                image_id,
+                annotations: state.annotations.clone(),
+            })
+        }
+
+        fn thumbnails_to_render(&self) -> Vec<(ViewableObjectId, ThumbnailKey)> {
+            let dispatch = Dispatch::<AppState>::global();
+            let state = dispatch.get();
+            let thumbnails = state.thumbnails.borrow();
+            thumbnails
+                .visible()
+                .filter_map(|image_id| {
+                    let ImageAvailability::Available(texture) = self.texture_by_id(image_id) else {
+                        return None;
+                    };
+                    let (drawing_options, global_drawing_options) =
+                        self.drawing_options(image_id, &DrawingContext::BaseImage);
+                    // skipped until loaded, it would keep the slots of a frame from the others
+                    let batch_item = drawing_options.batch_item.unwrap_or(0);
+                    let is_renderable = if state.software_rendering {
+                        texture.borrow().has_batch_item(batch_item)
+                    } else {
+                        texture.borrow().textures.contains_key(&batch_item)
+                    };
+                    if !is_renderable {
+                        return None;
+                    }
+                    let key = ThumbnailKey::new(
+                        texture.borrow().id,
+                        texture.borrow().computed_info.minmax_pending,
+                        drawing_options,
+                        &global_drawing_options,
+                    );
+                    (!thumbnails.is_up_to_date(image_id, &key)).then(|| (image_id.clone(), key))
+                })
+                .collect()
+        }
+
+        fn set_thumbnail(&self, image_id: ViewableObjectId, thumbnail: Thumbnail) {
+            Dispatch::<AppState>::global().reduce_mut(|state| {
+                state.thumbnails.borrow_mut().insert(image_id, thumbnail);
+            });
+        }
+
+        fn report_image_error(&self, image_id: &ViewableObjectId, error: ImageError) {
+            let dispatch = Dispatch::<AppState>::global();
+            if dispatch.get().image_errors.borrow().get(image_id) == Some(&error) {
+                return;
+            }
+            dispatch.reduce_mut(|state| {
+                state
+                    .image_errors
+                    .borrow_mut()
+                    .report(image_id.clone(), error);
+            });
+        }
     }
 
     RenderingContextImpl {}
@@ -191,12 +305,8 @@ fn view_context() -> impl ViewContext {
         }
 
         fn set_camera_for_view(&self, view_id: ViewId, camera: camera::Camera) {
-            let dispatch = Dispatch::<AppState>::global();
-            dispatch
-                .get()
-                .view_cameras
-                .borrow_mut()
-                .set(view_id, camera);
+            // through the store, so the zoom shown in the status bar follows
+            Dispatch::<AppState>::global().apply(UiAction::SetCamera(view_id, camera));
         }
 
         fn get_image_size_for_view(&self, view_id: ViewId) -> Option<Size> {
@@ -259,6 +369,15 @@ fn view_context() -> impl ViewContext {
     CameraContextImpl {}
 }
 
+fn enable_extensions(gl: &WebGl2RenderingContext) {
+    let _ = [
+        webgl_utils::WebGlExtension::OesTextureFloat,
+        webgl_utils::WebGlExtension::OesTextureFloatLinear,
+        webgl_utils::WebGlExtension::ExtColorBufferFloat,
+    ]
+    .map(|ext| webgl_utils::general::enable_extension(gl, ext).unwrap());
+}
+
 #[function_component]
 pub(crate) fn App() -> Html {
     VSCodeRequests::init(vscode::acquire_vscode_api());
@@ -317,8 +436,14 @@ pub(crate) fn App() -> Html {
 
             let zoom_listener = ZoomHandler::install(view_id, Rc::clone(&view_context_rc));
             let pan_listener = PanHandler::install(view_id, Rc::clone(&view_context_rc));
+            let line_profile_listener =
+                LineProfileHandler::install(view_id, Rc::clone(&view_context_rc));
+            let annotation_listener =
+                AnnotationHandler::install(view_id, Rc::clone(&view_context_rc));
             let batch_item_scroll_listener =
                 ShiftScrollHandler::install(view_id, Rc::clone(&view_context_rc));
+            let zoom_selection_listener =
+                ZoomSelectionHandler::install(view_id, Rc::clone(&view_context_rc));
 
             let keyboard_listener = KeyboardHandler::install(&canvas_ref);
 
@@ -326,7 +451,10 @@ pub(crate) fn App() -> Html {
                 drop(message_listener);
                 drop(zoom_listener);
                 drop(pan_listener);
+                drop(line_profile_listener);
+                drop(annotation_listener);
                 drop(batch_item_scroll_listener);
+                drop(zoom_selection_listener);
                 drop(keyboard_listener);
             }
         }
@@ -341,23 +469,20 @@ pub(crate) fn App() -> Html {
                 .cast::<HtmlCanvasElement>()
                 .expect("canvas_ref not attached to a canvas element");
 
-            let gl: WebGl2RenderingContext = canvas
+            let gl = canvas
                 .get_context("webgl2")
-                .unwrap()
-                .unwrap()
-                .dyn_into()
-                .unwrap();
-
-            let _ = [
-                webgl_utils::WebGlExtension::OesTextureFloat,
-                webgl_utils::WebGlExtension::OesTextureFloatLinear,
-                webgl_utils::WebGlExtension::ExtColorBufferFloat,
-            ]
-            .map(|ext| webgl_utils::general::enable_extension(&gl, ext).unwrap());
+                .ok()
+                .flatten()
+                .and_then(|context| context.dyn_into::<WebGl2RenderingContext>().ok());
+
+            if let Some(gl) = &gl {
+                enable_extensions(gl);
+            }
 
             let dispatch = Dispatch::<AppState>::global();
             dispatch.reduce_mut(|state| {
-                state.gl = Some(gl.clone());
+                state.gl = gl.clone();
+                state.software_rendering = gl.is_none();
             });
 
             dispatch.reduce_mut(|state| {
@@ -368,11 +493,60 @@ pub(crate) fn App() -> Html {
             });
 
             let rendering_context: Rc<dyn RenderingContext> = Rc::new(rendering_context());
-            renderer
-                .borrow_mut()
-                .setup_rendering(Rc::clone(&rendering_context));
+            // everything the views show is in the state
+            let state_subscription =
+                Dispatch::<AppState>::global().subscribe_silent(|_: Rc<AppState>| request_render());
+
+            let context_listeners = if let Some(gl) = gl {
+                renderer
+                    .borrow_mut()
+                    .setup_rendering(Rc::clone(&rendering_context));
+
+                // the context is lost on GPU resets or when too many contexts are open, preventing
+                // the default lets the browser restore it
+                let context_lost_listener = EventListener::new_with_options(
+                    &canvas,
+                    "webglcontextlost",
+                    EventListenerOptions::enable_prevent_default(),
+                    |event| {
+                        log::warn!("WebGL context lost");
+                        event.prevent_default();
+                    },
+                );
+                let context_restored_listener =
+                    EventListener::new(&canvas, "webglcontextrestored", {
+                        let renderer = Rc::clone(&renderer);
+                        move |_| {
+                            if gl.is_context_lost() {
+                                return;
+                            }
+                            log::info!("WebGL context restored");
+                            enable_extensions(&gl);
+                            Dispatch::<AppState>::global().apply(StoreAction::RestoreTextures);
+                            renderer
+                                .borrow_mut()
+                                .setup_rendering(Rc::clone(&rendering_context));
+                            webgl_utils::general::clear_errors(&gl);
+                        }
+                    });
+                Some((context_lost_listener, context_restored_listener))
+            } else {
+                log::warn!("WebGL2 is unavailable, drawing the images on the CPU");
+                let context: CanvasRenderingContext2d = canvas
+                    .get_context("2d")
+                    .unwrap()
+                    .expect("Could not get a 2D context")
+                    .dyn_into()
+                    .unwrap();
+                renderer
+                    .borrow_mut()
+                    .setup_software_rendering(rendering_context, context);
+                None
+            };
 
             move || {
+                drop(state_subscription);
+                drop(context_listeners);
                 dispatch.reduce_mut(|state| {
                     state.gl = None;
                 });
@@ -0,0 +1,255 @@
+use std::collections::HashMap;
+
+use glam::Vec2;
+
+use crate::common::{constants::all_views, ViewId, ViewableObjectId};
+
+/// Colors of the mask labels, starting from label 1. Label 0 is the background and is not drawn.
+/// Keep in sync with `LABEL_COLORS` in `shaders/mask.frag`.
+pub(crate) const LABEL_COLORS: [[u8; 3]; 8] = [
+    [230, 25, 75],
+    [60, 180, 75],
+    [255, 225, 25],
+    [0, 130, 200],
+    [245, 130, 48],
+    [145, 30, 180],
+    [70, 240, 240],
+    [240, 50, 230],
+];
+
+pub(crate) fn label_color(label: u8) -> [u8; 3] {
+    LABEL_COLORS[(label.max(1) as usize - 1) % LABEL_COLORS.len()]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AnnotationTool {
+    Brush,
+    Eraser,
+    Polygon,
+    Box,
+}
+
+impl AnnotationTool {
+    pub(crate) const ALL: [AnnotationTool; 4] = [
+        AnnotationTool::Brush,
+        AnnotationTool::Eraser,
+        AnnotationTool::Polygon,
+        AnnotationTool::Box,
+    ];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            AnnotationTool::Brush => "Brush",
+            AnnotationTool::Eraser => "Eraser",
+            AnnotationTool::Polygon => "Polygon",
+            AnnotationTool::Box => "Box",
+        }
+    }
+
+    pub(crate) fn icon(&self) -> &'static str {
+        match self {
+            AnnotationTool::Brush => "codicon codicon-edit",
+            AnnotationTool::Eraser => "codicon codicon-circle-slash",
+            AnnotationTool::Polygon => "codicon codicon-triangle-up",
+            AnnotationTool::Box => "codicon codicon-primitive-square",
+        }
+    }
+
+    pub(crate) fn paints_mask(&self) -> bool {
+        matches!(self, AnnotationTool::Brush | AnnotationTool::Eraser)
+    }
+}
+
+/// A shape in image pixel coordinates, like [`super::line_profile::Line`].
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Shape {
+    Polygon { label: u8, points: Vec<Vec2> },
+    Box { label: u8, start: Vec2, end: Vec2 },
+}
+
+impl Shape {
+    pub(crate) fn label(&self) -> u8 {
+        match self {
+            Shape::Polygon { label, .. } | Shape::Box { label, .. } => *label,
+        }
+    }
+
+    /// The closed outline of the shape.
+    pub(crate) fn outline(&self) -> Vec<Vec2> {
+        match self {
+            Shape::Polygon { points, .. } => points.clone(),
+            Shape::Box { start, end, .. } => vec![
+                *start,
+                Vec2::new(end.x, start.y),
+                *end,
+                Vec2::new(start.x, end.y),
+            ],
+        }
+    }
+}
+
+/// A label per image pixel, in row-major order.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LabelMask {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+    /// Incremented on every change, so the renderer knows when to upload the mask again.
+    pub revision: u32,
+}
+
+impl LabelMask {
+    pub(crate) fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            data: vec![0; (width * height) as usize],
+            revision: 0,
+        }
+    }
+
+    fn paint_disc(&mut self, center: Vec2, radius: f32, label: u8) {
+        let min = (center - radius).floor().max(Vec2::ZERO);
+        let max = (center + radius)
+            .ceil()
+            .min(Vec2::new(self.width as f32, self.height as f32));
+        for y in min.y as u32..max.y as u32 {
+            for x in min.x as u32..max.x as u32 {
+                let pixel_center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                if pixel_center.distance_squared(center) <= radius * radius {
+                    self.data[(y * self.width + x) as usize] = label;
+                }
+            }
+        }
+    }
+
+    /// Paints a stroke of discs from `from` to `to`. Erasing is painting label 0.
+    pub(crate) fn paint_stroke(&mut self, from: Vec2, to: Vec2, radius: f32, label: u8) {
+        // half a radius between discs keeps the stroke edges smooth
+        let step = (radius / 2.0).max(0.5);
+        let num_discs = (from.distance(to) / step).ceil() as usize + 1;
+        for i in 0..num_discs {
+            let t = if num_discs > 1 {
+                i as f32 / (num_discs - 1) as f32
+            } else {
+                0.0
+            };
+            self.paint_disc(from.lerp(to, t), radius, label);
+        }
+        self.revision = self.revision.wrapping_add(1);
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.data.iter().all(|&label| label == 0)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ImageAnnotations {
+    pub mask: Option<LabelMask>,
+    pub shapes: Vec<Shape>,
+}
+
+impl ImageAnnotations {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.shapes.is_empty() && self.mask.as_ref().map_or(true, LabelMask::is_empty)
+    }
+}
+
+/// The annotation currently being drawn on a view.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct AnnotationDraft {
+    /// Last position of the brush stroke, while the mouse button is held.
+    pub stroke: Option<Vec2>,
+    /// Vertices of the polygon placed so far.
+    pub polygon: Vec<Vec2>,
+    /// Corner where the box was started, while the mouse button is held.
+    pub box_start: Option<Vec2>,
+    /// Last mouse position on the image, used to preview the next polygon edge or the box.
+    pub cursor: Option<Vec2>,
+}
+
+impl AnnotationDraft {
+    /// The outline of the shape being drawn, and whether it is closed.
+    pub(crate) fn outline(&self) -> Option<(Vec<Vec2>, bool)> {
+        if let (Some(start), Some(cursor)) = (self.box_start, self.cursor) {
+            let shape = Shape::Box {
+                label: 0,
+                start,
+                end: cursor,
+            };
+            return Some((shape.outline(), true));
+        }
+        if self.polygon.is_empty() {
+            return None;
+        }
+        let mut points = self.polygon.clone();
+        points.extend(self.cursor);
+        Some((points, false))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Annotations {
+    pub tool: Option<AnnotationTool>,
+    pub label: u8,
+    /// Brush and eraser radius, in image pixels.
+    pub brush_radius: f32,
+    images: HashMap<ViewableObjectId, ImageAnnotations>,
+    drafts: HashMap<ViewId, AnnotationDraft>,
+}
+
+impl Annotations {
+    pub(crate) const MIN_BRUSH_RADIUS: f32 = 0.5;
+    pub(crate) const MAX_BRUSH_RADIUS: f32 = 64.0;
+    pub(crate) const MAX_LABEL: u8 = 255;
+
+    pub(crate) fn new() -> Self {
+        Self {
+            tool: None,
+            label: 1,
+            brush_radius: 3.0,
+            images: HashMap::new(),
+            drafts: all_views()
+                .into_iter()
+                .map(|v| (v, AnnotationDraft::default()))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn is_active(&self) -> bool {
+        self.tool.is_some()
+    }
+
+    pub(crate) fn get(&self, image_id: &ViewableObjectId) -> Option<&ImageAnnotations> {
+        self.images.get(image_id)
+    }
+
+    pub(crate) fn get_or_create(&mut self, image_id: ViewableObjectId) -> &mut ImageAnnotations {
+        self.images.entry(image_id).or_default()
+    }
+
+    pub(crate) fn clear(&mut self, image_id: &ViewableObjectId) {
+        self.images.remove(image_id);
+    }
+
+    pub(crate) fn draft(&self, view_id: ViewId) -> &AnnotationDraft {
+        self.drafts.get(&view_id).unwrap()
+    }
+
+    pub(crate) fn draft_mut(&mut self, view_id: ViewId) -> &mut AnnotationDraft {
+        self.drafts.get_mut(&view_id).unwrap()
+    }
+
+    pub(crate) fn reset_drafts(&mut self) {
+        self.drafts
+            .values_mut()
+            .for_each(|d| *d = Default::default());
+    }
+}
+
+impl Default for Annotations {
+    fn default() -> Self {
+        Self::new()
+    }
+}
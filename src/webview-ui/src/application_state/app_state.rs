@@ -1,22 +1,36 @@
+use super::annotations::{AnnotationTool, Annotations, LabelMask, Shape};
 use super::colormaps::{ColorMapRegistry, ColorMapTexturesCache};
-use super::images::{ImageCache, Images, ImagesDrawingOptions};
+use super::image_errors::{ImageError, ImageErrors};
+use super::image_list::{ImageListGrouping, ImageListOptions, ImageListSort};
+use super::images::{ImageAvailability, ImageCache, Images, ImagesDrawingOptions};
+use super::line_profile::{Interpolation, Line, LineProfiles};
+use super::link_groups::LinkGroups;
+use super::memory_budget::MemoryBudgetKeeper;
+use super::playback::ViewsPlayback;
 use super::sessions::Sessions;
+use super::thumbnails::Thumbnails;
+use super::undo_history::{Snapshot, UndoHistory};
+use super::value_history::ValueHistory;
 use super::views::ImageViews;
 use super::vscode_data_fetcher::ImagesFetcher;
 use crate::application_state::images::DrawingContext;
 use crate::application_state::views::Overlays;
 use crate::coloring::{Clip, Coloring, DrawingOptions};
-use crate::common::camera::ViewsCameras;
-use crate::common::texture_image::TextureImage;
+use crate::common::camera::{Camera, Orientation, ViewGeometry, ViewsCameras};
+use crate::common::drawing_preset::DrawingPreset;
+use crate::common::texture_image::{MemoryUsage, TextureImage};
 use crate::common::{
-    constants, AppMode, CurrentlyViewing, Image, ImageData, ImagePlaceholder, SessionId, ViewId,
-    ViewableObjectId,
+    constants, AppMode, ComputedInfo, CurrentlyViewing, Datatype, Image, ImageData, ImageInfo,
+    ImagePlaceholder, SessionId, Size, ValueVariableKind, ViewId, ViewableObjectId,
 };
+use crate::compute;
 use crate::configurations;
-use crate::vscode::state::HostExtensionStateUpdate;
+use crate::math_utils::image_calculations::image_difference_on_bytes;
+use crate::vscode::state::{HostExtensionState, HostExtensionStateUpdate};
 use crate::vscode::vscode_requests::VSCodeRequests;
 use anyhow::{anyhow, Result};
-use std::collections::HashMap;
+use glam::{UVec2, Vec2};
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use web_sys::WebGl2RenderingContext;
 use yew::NodeRef;
@@ -39,6 +53,36 @@ impl Default for GlobalDrawingOptions {
     }
 }
 
+#[derive(Clone, PartialEq)]
+pub(crate) struct PixelInspectorOptions {
+    pub enabled: bool,
+    /// Number of pixels on each side of the inspected neighborhood. Always odd, so the hovered
+    /// pixel is at the center.
+    pub size: u32,
+}
+
+impl PixelInspectorOptions {
+    pub(crate) const MIN_SIZE: u32 = 5;
+    pub(crate) const MAX_SIZE: u32 = 15;
+}
+
+impl Default for PixelInspectorOptions {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            size: 7,
+        }
+    }
+}
+
+/// The rectangle being dragged to zoom into, in CSS pixels relative to the view element.
+#[derive(Clone, PartialEq)]
+pub(crate) struct ZoomSelection {
+    pub view_id: ViewId,
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
 #[derive(Clone, PartialEq, Hash, Eq)]
 pub(crate) enum ElementsStoreKey {
     ColorBar,
@@ -47,19 +91,40 @@ pub(crate) enum ElementsStoreKey {
 #[derive(Clone, PartialEq)]
 pub(crate) struct AppState {
     pub gl: Option<WebGl2RenderingContext>,
+    /// WebGL2 is unavailable, the images are drawn on the CPU from their bytes.
+    pub software_rendering: bool,
 
     pub sessions: Mrc<Sessions>,
     pub images: Mrc<Images>,
     pub image_views: Mrc<ImageViews>,
     pub image_cache: Mrc<ImageCache>,
+    /// The last error of each image, shown on its list item and in the views.
+    pub image_errors: Mrc<ImageErrors>,
+    /// Filter, sorting and grouping of the image list in the sidebar.
+    pub image_list: ImageListOptions,
+    pub thumbnails: Mrc<Thumbnails>,
     pub drawing_options: Mrc<ImagesDrawingOptions>,
     pub global_drawing_options: GlobalDrawingOptions,
+    pub pixel_inspector: PixelInspectorOptions,
+    /// Whether the keyboard shortcuts overlay is shown.
+    pub shortcuts_visible: bool,
+    pub zoom_selection: Option<ZoomSelection>,
     pub overlays: Mrc<Overlays>,
+    pub link_groups: Mrc<LinkGroups>,
 
     pub color_map_registry: Mrc<ColorMapRegistry>,
     pub color_map_textures_cache: Mrc<ColorMapTexturesCache>,
 
     pub view_cameras: Mrc<ViewsCameras>,
+    pub undo_history: Mrc<UndoHistory>,
+
+    pub playback: Mrc<ViewsPlayback>,
+
+    pub value_history: Mrc<ValueHistory>,
+
+    pub line_profiles: Mrc<LineProfiles>,
+
+    pub annotations: Mrc<Annotations>,
 
     pub elements_refs_store: Mrc<HashMap<ElementsStoreKey, NodeRef>>,
 
@@ -72,16 +137,29 @@ impl Default for AppState {
     fn default() -> Self {
         Self {
             gl: None,
+            software_rendering: false,
             sessions: Default::default(),
             images: Default::default(),
             image_views: Default::default(),
             image_cache: Default::default(),
+            image_errors: Default::default(),
+            image_list: Default::default(),
+            thumbnails: Default::default(),
             drawing_options: Default::default(),
             global_drawing_options: Default::default(),
+            pixel_inspector: Default::default(),
+            shortcuts_visible: false,
+            zoom_selection: None,
             overlays: Default::default(),
+            link_groups: Default::default(),
             color_map_registry: Default::default(),
             color_map_textures_cache: Default::default(),
             view_cameras: Default::default(),
+            undo_history: Default::default(),
+            playback: Default::default(),
+            value_history: Default::default(),
+            line_profiles: Default::default(),
+            annotations: Default::default(),
             elements_refs_store: Default::default(),
             app_mode: AppMode::ImageList,
             configuration: configurations::Configuration::default(),
@@ -92,6 +170,7 @@ impl Default for AppState {
 impl Store for AppState {
     fn new(cx: &yewdux::Context) -> Self {
         init_listener(ImagesFetcher::default, cx);
+        init_listener(MemoryBudgetKeeper::default, cx);
         Default::default()
     }
 
@@ -108,6 +187,8 @@ impl AppState {
     }
 
     pub(crate) fn set_image_to_view(&mut self, image_id: ViewableObjectId, view_id: ViewId) {
+        self.playback.borrow_mut().stop(view_id);
+
         let is_batched = self
             .images
             .borrow()
@@ -129,6 +210,266 @@ impl AppState {
             .borrow()
             .send_event_to_view(view_id, "svifpd:changeimage");
     }
+
+    /// Memory of the loaded images and of the value history, counting shared data once.
+    pub(crate) fn memory_usage(&self) -> MemoryUsage {
+        let image_cache = self.image_cache.borrow();
+        image_cache.memory_usage()
+            + self
+                .value_history
+                .borrow()
+                .memory_usage(&image_cache.texture_ids())
+    }
+
+    /// Returns the current batch item index and the batch size of a batched image.
+    pub(crate) fn batch_item_position(&self, image_id: &ViewableObjectId) -> Option<(u32, u32)> {
+        let current_index = self
+            .drawing_options
+            .borrow()
+            .get(image_id, &DrawingContext::BaseImage)?
+            .batch_item?;
+        match self.images.borrow().get(image_id) {
+            Some(Image::Full(info)) => {
+                let batch_size = info.batch_info.as_ref().map_or(1, |info| info.batch_size);
+                Some((current_index, batch_size))
+            }
+            _ => None,
+        }
+    }
+
+    /// The sizes of the view and of the image it shows, once both are known.
+    pub(crate) fn view_geometry(&self, view_id: ViewId) -> Option<ViewGeometry> {
+        let element = self
+            .image_views
+            .borrow()
+            .get_node_ref(view_id)
+            .cast::<web_sys::HtmlElement>()?;
+        let image_id = self
+            .image_views
+            .borrow()
+            .get_currently_viewing(view_id)?
+            .id()
+            .clone();
+        let image_size = match self.image_cache.borrow().get(&image_id) {
+            ImageAvailability::Available(texture) => texture.borrow().image_size(),
+            _ => return None,
+        };
+        let element_size = Size {
+            width: element.client_width() as f32,
+            height: element.client_height() as f32,
+        };
+        if element_size.width <= 0.0 || element_size.height <= 0.0 {
+            return None;
+        }
+        Some(ViewGeometry {
+            element_size,
+            image_size,
+            device_pixel_ratio: gloo_utils::window().device_pixel_ratio() as f32,
+            orientation: self.view_cameras.borrow().get(view_id).orientation,
+        })
+    }
+
+    fn update_orientation(
+        &mut self,
+        view_id: ViewId,
+        update: impl FnOnce(Orientation) -> Orientation,
+    ) {
+        let mut view_cameras = self.view_cameras.borrow_mut();
+        let camera = view_cameras.get(view_id);
+        view_cameras.set(view_id, camera.with_orientation(update(camera.orientation)));
+    }
+
+    /// Records the camera of the view for undo, as a change of the image it shows.
+    fn record_camera(&self, view_id: ViewId) {
+        let Some(cv) = self.image_views.borrow().get_currently_viewing(view_id) else {
+            return;
+        };
+        let camera = self.view_cameras.borrow().get(view_id);
+        self.undo_history
+            .borrow_mut()
+            .record(cv.id().clone(), Snapshot::Camera(view_id, camera));
+    }
+
+    /// The current state of the image, of the same kind as `snapshot`.
+    fn snapshot_like(&self, image_id: &ViewableObjectId, snapshot: &Snapshot) -> Snapshot {
+        match snapshot {
+            Snapshot::DrawingOptions(_) => Snapshot::DrawingOptions(
+                self.drawing_options
+                    .borrow()
+                    .get(image_id, &DrawingContext::BaseImage)
+                    .cloned()
+                    .unwrap_or_default(),
+            ),
+            Snapshot::Camera(view_id, _) => {
+                Snapshot::Camera(*view_id, self.view_cameras.borrow().get(*view_id))
+            }
+        }
+    }
+
+    fn restore_snapshot(&self, image_id: ViewableObjectId, snapshot: Snapshot) {
+        match snapshot {
+            Snapshot::DrawingOptions(drawing_options) => {
+                // the linked images share the drawing options
+                let linked_images = self.link_groups.borrow().linked_images(&image_id);
+                for image_id in std::iter::once(image_id).chain(linked_images) {
+                    let drawing_options = DrawingOptions {
+                        // changes of the batch item are not recorded, keep the current one
+                        batch_item: self
+                            .drawing_options
+                            .borrow()
+                            .get(&image_id, &DrawingContext::BaseImage)
+                            .and_then(|d| d.batch_item),
+                        ..drawing_options.clone()
+                    };
+                    self.remember_drawing_options(&image_id, &drawing_options);
+                    self.drawing_options.borrow_mut().set(
+                        image_id,
+                        DrawingContext::BaseImage,
+                        drawing_options,
+                    );
+                }
+            }
+            Snapshot::Camera(view_id, camera) => {
+                self.view_cameras.borrow_mut().set(view_id, camera);
+            }
+        }
+    }
+
+    fn drawing_options_key(&self, image_id: &ViewableObjectId) -> Option<String> {
+        let images = self.images.borrow();
+        let image = images.get(image_id)?.minimal();
+        self.configuration
+            .remember_drawing_options
+            .key(image.expression, image.additional_info)
+    }
+
+    /// Persists the drawing options of the image in the webview state, so they are restored
+    /// when the same expression shows up in a later debug session.
+    fn remember_drawing_options(
+        &self,
+        image_id: &ViewableObjectId,
+        drawing_options: &DrawingOptions,
+    ) {
+        let Some(key) = self.drawing_options_key(image_id) else {
+            return;
+        };
+        let mut host_state = VSCodeRequests::get_state().unwrap_or_default();
+        // the batch item is specific to the data, it is not restored
+        let drawing_options = DrawingOptions {
+            batch_item: None,
+            ..drawing_options.clone()
+        };
+        host_state.set_remembered_drawing_options(
+            key,
+            (drawing_options != DrawingOptions::default()).then_some(drawing_options),
+        );
+        update_remembered_drawing_options(host_state);
+    }
+
+    /// Restores the remembered drawing options of an image that has none yet.
+    fn restore_drawing_options(&self, image_id: &ViewableObjectId) {
+        if self
+            .drawing_options
+            .borrow()
+            .get(image_id, &DrawingContext::BaseImage)
+            .is_some()
+        {
+            return;
+        }
+        let Some(key) = self.drawing_options_key(image_id) else {
+            return;
+        };
+        let Some(mut host_state) = VSCodeRequests::get_state() else {
+            return;
+        };
+        let remembered = host_state.remembered_drawing_options.get(&key).cloned();
+        if let Some(drawing_options) = remembered {
+            log::debug!("Restoring drawing options of {}", key);
+            host_state.touch_remembered_drawing_options(&key);
+            update_remembered_drawing_options(host_state);
+            self.drawing_options.borrow_mut().set(
+                image_id.clone(),
+                DrawingContext::BaseImage,
+                drawing_options,
+            );
+        }
+    }
+
+    fn send_event_to_views_of(&self, image_id: &ViewableObjectId, event: &str) {
+        let image_views = self.image_views.borrow();
+        image_views
+            .is_currently_viewing(image_id)
+            .into_iter()
+            .for_each(|view_id| image_views.send_event_to_view(view_id, event));
+    }
+
+    /// Replaces the data of the image with a snapshot from a previous step.
+    pub(crate) fn show_snapshot(
+        &mut self,
+        image_id: &ViewableObjectId,
+        step_index: u32,
+    ) -> Result<()> {
+        let snapshot = self
+            .value_history
+            .borrow()
+            .get(image_id, step_index)
+            .cloned()
+            .ok_or(anyhow!(
+                "No snapshot of step {} for {:?}",
+                step_index,
+                image_id
+            ))?;
+
+        let live = if self.value_history.borrow().is_viewing_snapshot(image_id) {
+            None
+        } else {
+            self.image_cache
+                .borrow()
+                .loaded_images()
+                .find(|(id, _)| *id == image_id)
+                .map(|(_, image)| image.clone())
+        };
+        self.value_history
+            .borrow_mut()
+            .start_viewing(image_id.clone(), step_index, live);
+        self.image_cache
+            .borrow_mut()
+            .set_texture(image_id, snapshot.texture);
+
+        self.send_event_to_views_of(image_id, "svifpd:changeimage");
+        Ok(())
+    }
+
+    /// Brings back the data of the current step, after viewing a snapshot.
+    pub(crate) fn show_live_data(&mut self, image_id: &ViewableObjectId) {
+        let Some(live) = self.value_history.borrow_mut().stop_viewing(image_id) else {
+            return;
+        };
+        match live {
+            Some(image) => self.image_cache.borrow_mut().set_texture(image_id, image),
+            // never loaded in this step, let the fetcher request it
+            None => self.image_cache.borrow_mut().remove(image_id),
+        }
+
+        self.send_event_to_views_of(image_id, "svifpd:changeimage");
+    }
+
+    pub(crate) fn set_batch_item(
+        &mut self,
+        view_id: ViewId,
+        image_id: ViewableObjectId,
+        index: u32,
+    ) {
+        self.drawing_options
+            .borrow_mut()
+            .get_mut_ref(image_id, DrawingContext::BaseImage)
+            .batch_item = Some(index);
+
+        // send event to view that the batch item has changed
+        self.image_views
+            .borrow()
+            .send_event_to_view(view_id, "svifpd:changeimage");
+    }
 }
 
 #[derive(PartialEq, Clone)]
@@ -139,8 +480,83 @@ pub(crate) enum UpdateDrawingOptions {
     Invert(bool),
     HighContrast(bool),
     IgnoreAlpha(bool),
+    NormalizePerBatchItem(bool),
     ClipMin(Option<f32>),
     ClipMax(Option<f32>),
+    /// Also sets the colormaps of the preset globally.
+    Preset(DrawingPreset),
+}
+
+impl UpdateDrawingOptions {
+    /// The drawing options after applying the update to `current_drawing_options`.
+    fn apply_to(
+        &self,
+        current_drawing_options: DrawingOptions,
+        drawing_context: DrawingContext,
+    ) -> DrawingOptions {
+        match self {
+            UpdateDrawingOptions::Full(drawing_options) => drawing_options.clone(),
+            UpdateDrawingOptions::Reset => DrawingOptions {
+                // keep the batch slice index
+                batch_item: current_drawing_options.batch_item,
+                ..DrawingOptions::default()
+            },
+            UpdateDrawingOptions::Coloring(
+                coloring @ (Coloring::Segmentation | Coloring::Edges),
+            ) => DrawingOptions {
+                coloring: *coloring,
+                zeros_as_transparent: if drawing_context == DrawingContext::BaseImage {
+                    current_drawing_options.zeros_as_transparent
+                } else {
+                    true
+                },
+                ..current_drawing_options
+            },
+            UpdateDrawingOptions::Coloring(c) => DrawingOptions {
+                coloring: *c,
+                ..current_drawing_options
+            },
+            UpdateDrawingOptions::Invert(i) => DrawingOptions {
+                invert: *i,
+                ..current_drawing_options
+            },
+            UpdateDrawingOptions::HighContrast(hc) => DrawingOptions {
+                high_contrast: *hc,
+                ..current_drawing_options
+            },
+            UpdateDrawingOptions::IgnoreAlpha(ia) => DrawingOptions {
+                ignore_alpha: *ia,
+                ..current_drawing_options
+            },
+            UpdateDrawingOptions::NormalizePerBatchItem(per_batch_item) => DrawingOptions {
+                normalize_per_batch_item: *per_batch_item,
+                ..current_drawing_options
+            },
+            UpdateDrawingOptions::ClipMin(min) => DrawingOptions {
+                clip: Clip {
+                    min: *min,
+                    ..current_drawing_options.clip
+                },
+                ..current_drawing_options
+            },
+            UpdateDrawingOptions::ClipMax(max) => DrawingOptions {
+                clip: Clip {
+                    max: *max,
+                    ..current_drawing_options.clip
+                },
+                ..current_drawing_options
+            },
+            UpdateDrawingOptions::Preset(preset) => {
+                preset.drawing_options(current_drawing_options.batch_item)
+            }
+        }
+    }
+
+    /// Whether the update also applies to the images linked with the updated image. The batch
+    /// item is kept per image.
+    fn is_shared_by_link_group(&self) -> bool {
+        !matches!(self, UpdateDrawingOptions::Full(_))
+    }
 }
 
 #[allow(dead_code)]
@@ -168,32 +584,57 @@ pub(crate) enum StoreAction {
     SetActiveSession(SessionId),
     SetImageToView(ViewableObjectId, ViewId),
     AddImageWithData(ViewableObjectId, ImageData),
+    /// The data received for the image could not be used.
+    RejectImageData(ViewableObjectId, ImageError),
     UpdateDrawingOptions(ViewableObjectId, DrawingContext, UpdateDrawingOptions),
     UpdateGlobalDrawingOptions(UpdateGlobalDrawingOptions),
     ReplaceData(Vec<ImageObject>),
     UpdateData(ImageObject),
     SetMode(AppMode),
     SetSessionNames(HashMap<SessionId, String>),
+    /// The WebGL context was restored after being lost, with none of the textures.
+    RestoreTextures,
 }
 
-fn add_session(sessions: &Mrc<Sessions>, session_id: SessionId) -> Result<()> {
+fn update_remembered_drawing_options(host_state: HostExtensionState) {
+    VSCodeRequests::update_state(
+        HostExtensionStateUpdate::default()
+            .remembered_drawing_options(host_state.remembered_drawing_options)
+            .remembered_drawing_options_order(host_state.remembered_drawing_options_order)
+            .clone(),
+    );
+}
+
+fn add_session(sessions: &Mrc<Sessions>, session_id: SessionId) {
     let mut sessions = sessions.borrow_mut();
     if !sessions.sessions.contains(&session_id) {
         sessions.sessions.push(session_id);
     }
-    Ok(())
 }
 
-fn handle_received_image(state: &AppState, image: ImageObject) -> Result<()> {
+/// Adds the received image, its error replaces the previous one in [`AppState::image_errors`].
+fn handle_received_image(state: &AppState, image: ImageObject) -> Result<(), ImageError> {
+    let image_id = image.image_id().clone();
+    let result = add_received_image(state, image);
+    let mut image_errors = state.image_errors.borrow_mut();
+    match &result {
+        Ok(()) => image_errors.clear(&image_id),
+        Err(e) => image_errors.report(image_id, e.clone()),
+    }
+    result
+}
+
+fn add_received_image(state: &AppState, image: ImageObject) -> Result<(), ImageError> {
     let image_id = image.image_id().clone();
 
-    add_session(&state.sessions, image_id.session_id().clone())?;
+    add_session(&state.sessions, image_id.session_id().clone());
 
     if let ImageObject::Placeholder(image_placeholder) = &image {
         state.images.borrow_mut().insert(
             image_id.clone(),
             Image::Placeholder(image_placeholder.clone()),
         );
+        state.restore_drawing_options(&image_id);
         return Ok(());
     }
 
@@ -208,9 +649,22 @@ fn handle_received_image(state: &AppState, image: ImageObject) -> Result<()> {
         .images
         .borrow_mut()
         .insert(image_id.clone(), Image::Full(image_info));
+    state.restore_drawing_options(&image_id);
 
     if let ImageObject::WithData(image_data) = image {
-        let tex_image = TextureImage::try_new(image_data, state.gl.as_ref().unwrap())?;
+        let gl = if state.software_rendering {
+            None
+        } else {
+            Some(
+                state
+                    .gl
+                    .as_ref()
+                    .ok_or_else(|| ImageError::Texture("WebGL is not initialized".to_string()))?,
+            )
+        };
+        let tex_image = TextureImage::try_new(image_data, gl)
+            .map_err(|e| ImageError::Texture(format!("{:#}", e)))?;
+        compute::submit(&image_id, &tex_image);
         log::debug!(
             "updating image cache: {:?} is_batched: {}, tex_image: {:?}",
             image_id,
@@ -218,7 +672,11 @@ fn handle_received_image(state: &AppState, image: ImageObject) -> Result<()> {
             tex_image
         );
         if is_batched {
-            state.image_cache.borrow_mut().update(&image_id, tex_image);
+            state
+                .image_cache
+                .borrow_mut()
+                .update(&image_id, tex_image)
+                .map_err(|e| ImageError::IncompatibleBatchItems(e.to_string()))?;
         } else {
             state
                 .image_cache
@@ -255,6 +713,33 @@ fn handle_received_image(state: &AppState, image: ImageObject) -> Result<()> {
     Ok(())
 }
 
+/// Creates the textures of the loaded images and the snapshots again from their bytes.
+fn restore_textures(state: &AppState) {
+    let Some(gl) = state.gl.as_ref() else {
+        return;
+    };
+    let image_cache = state.image_cache.borrow();
+    let value_history = state.value_history.borrow();
+    // the value history can hold copies of the textures of the cache
+    let mut restored = HashSet::new();
+    for texture in image_cache
+        .loaded_images()
+        .map(|(_, texture)| texture)
+        .chain(value_history.textures())
+    {
+        let mut texture = texture.borrow_mut();
+        if !restored.insert(texture.id) {
+            continue;
+        }
+        if let Err(e) = texture.restore_textures(gl) {
+            state.image_errors.borrow_mut().report(
+                texture.info.image_id.clone(),
+                ImageError::Texture(format!("{:#}", e)),
+            );
+        }
+    }
+}
+
 impl Reducer<AppState> for StoreAction {
     fn apply(self, mut app_state: Rc<AppState>) -> Rc<AppState> {
         let state = Rc::make_mut(&mut app_state);
@@ -277,87 +762,93 @@ impl Reducer<AppState> for StoreAction {
             }
             StoreAction::AddImageWithData(image_id, image_data) => {
                 log::debug!("AddImageWithData: {:?}", image_id);
+                // new data should never be merged into a snapshot
+                state.show_live_data(&image_id);
                 let image_object = ImageObject::WithData(image_data);
-                handle_received_image(state, image_object)
-                    .map_err(|e| {
-                        log::error!("Error handling image data: {:?}", e);
-                    })
-                    .ok();
+                // the error is kept in the image errors
+                handle_received_image(state, image_object).ok();
+            }
+            StoreAction::RejectImageData(image_id, error) => {
+                state.image_cache.borrow_mut().cancel_pending(&image_id);
+                state.image_errors.borrow_mut().report(image_id, error);
             }
             StoreAction::UpdateDrawingOptions(image_id, drawing_context, update) => {
+                // the renderer reports them again if they remain
+                state.image_errors.borrow_mut().clear_rendering_errors();
+                if let UpdateDrawingOptions::Preset(preset) = &update {
+                    preset.apply_colormaps(
+                        &mut state.global_drawing_options,
+                        &state.color_map_registry.borrow(),
+                    );
+                }
                 let current_drawing_options = state
                     .drawing_options
                     .borrow()
                     .get(&image_id, &drawing_context)
                     .cloned()
                     .unwrap_or_default();
-                let new_drawing_option = match update {
-                    UpdateDrawingOptions::Full(drawing_options) => drawing_options,
-                    UpdateDrawingOptions::Reset => DrawingOptions {
-                        // keep the batch slice index
-                        batch_item: current_drawing_options.batch_item,
-                        ..DrawingOptions::default()
-                    },
-                    UpdateDrawingOptions::Coloring(
-                        coloring @ (Coloring::Segmentation | Coloring::Edges),
-                    ) => DrawingOptions {
-                        coloring,
-                        zeros_as_transparent: if drawing_context == DrawingContext::BaseImage {
-                            current_drawing_options.zeros_as_transparent
-                        } else {
-                            true
-                        },
-                        ..current_drawing_options
-                    },
-                    UpdateDrawingOptions::Coloring(c) => DrawingOptions {
-                        coloring: c,
-                        ..current_drawing_options
-                    },
-                    UpdateDrawingOptions::Invert(i) => DrawingOptions {
-                        invert: i,
-                        ..current_drawing_options
-                    },
-                    UpdateDrawingOptions::HighContrast(hc) => DrawingOptions {
-                        high_contrast: hc,
-                        ..current_drawing_options
-                    },
-                    UpdateDrawingOptions::IgnoreAlpha(ia) => DrawingOptions {
-                        ignore_alpha: ia,
-                        ..current_drawing_options
-                    },
-                    UpdateDrawingOptions::ClipMin(min) => DrawingOptions {
-                        clip: Clip {
-                            min,
-                            ..current_drawing_options.clip
-                        },
-                        ..current_drawing_options
-                    },
-                    UpdateDrawingOptions::ClipMax(max) => DrawingOptions {
-                        clip: Clip {
-                            max,
-                            ..current_drawing_options.clip
-                        },
-                        ..current_drawing_options
-                    },
-                };
+                if drawing_context == DrawingContext::BaseImage {
+                    state.undo_history.borrow_mut().record(
+                        image_id.clone(),
+                        Snapshot::DrawingOptions(current_drawing_options.clone()),
+                    );
+                }
+                let new_drawing_option = update.apply_to(current_drawing_options, drawing_context);
                 VSCodeRequests::update_state(
                     HostExtensionStateUpdate::default()
                         .current_image_drawing_options(Some(new_drawing_option.clone()))
                         .clone(),
                 );
+                if drawing_context == DrawingContext::BaseImage {
+                    state.remember_drawing_options(&image_id, &new_drawing_option);
+                }
+                let linked_images = if drawing_context == DrawingContext::BaseImage
+                    && update.is_shared_by_link_group()
+                {
+                    state.link_groups.borrow().linked_images(&image_id)
+                } else {
+                    Vec::new()
+                };
                 state.drawing_options.borrow_mut().set(
                     image_id,
                     drawing_context,
                     new_drawing_option,
                 );
+                for linked_image_id in linked_images {
+                    let current_drawing_options = state
+                        .drawing_options
+                        .borrow()
+                        .get(&linked_image_id, &DrawingContext::BaseImage)
+                        .cloned()
+                        .unwrap_or_default();
+                    state.undo_history.borrow_mut().record(
+                        linked_image_id.clone(),
+                        Snapshot::DrawingOptions(current_drawing_options.clone()),
+                    );
+                    let drawing_options =
+                        update.apply_to(current_drawing_options, DrawingContext::BaseImage);
+                    state.remember_drawing_options(&linked_image_id, &drawing_options);
+                    state.drawing_options.borrow_mut().set(
+                        linked_image_id,
+                        DrawingContext::BaseImage,
+                        drawing_options,
+                    );
+                }
             }
             StoreAction::ReplaceData(replacement_images) => {
                 log::debug!("ReplaceData");
                 state.image_cache.borrow_mut().clear();
+                state.value_history.borrow_mut().stop_viewing_all();
+                constants::all_views().iter().for_each(|view_id| {
+                    state.playback.borrow_mut().stop(*view_id);
+                });
                 let previously_pinned = state.images.borrow().pinned().to_vec();
-                replacement_images
-                    .first()
-                    .inspect(|img| state.images.borrow_mut().clear(img.image_id().session_id()));
+                replacement_images.first().inspect(|img| {
+                    let session_id = img.image_id().session_id();
+                    state.images.borrow_mut().clear(session_id);
+                    state.image_errors.borrow_mut().clear_session(session_id);
+                    state.thumbnails.borrow_mut().clear_session(session_id);
+                });
 
                 let mut session = None;
                 let mut errors = Vec::new();
@@ -381,9 +872,11 @@ impl Reducer<AppState> for StoreAction {
             }
             StoreAction::UpdateGlobalDrawingOptions(opts) => match opts {
                 UpdateGlobalDrawingOptions::GlobalHeatmapColormap(name) => {
+                    state.image_errors.borrow_mut().clear_rendering_errors();
                     state.global_drawing_options.heatmap_colormap_name = name;
                 }
                 UpdateGlobalDrawingOptions::GlobalSegmentationColormap(name) => {
+                    state.image_errors.borrow_mut().clear_rendering_errors();
                     state.global_drawing_options.segmentation_colormap_name = name;
                 }
                 UpdateGlobalDrawingOptions::DisplayColorbar(display) => {
@@ -391,7 +884,9 @@ impl Reducer<AppState> for StoreAction {
                 }
             },
             StoreAction::UpdateData(image_object) => {
-                handle_received_image(state, image_object).unwrap();
+                // batch items of an image whose shape changed are rejected, the error is kept in
+                // the image errors
+                handle_received_image(state, image_object).ok();
             }
             StoreAction::SetMode(app_mode) => {
                 state.app_mode = app_mode;
@@ -413,6 +908,10 @@ impl Reducer<AppState> for StoreAction {
                     .session_name
                     .extend(session_names);
             }
+            StoreAction::RestoreTextures => {
+                state.color_map_textures_cache.borrow_mut().clear();
+                restore_textures(state);
+            }
         };
 
         app_state
@@ -426,12 +925,61 @@ pub(crate) enum UiAction {
     Unpin(ViewableObjectId),
     ViewShiftScroll(ViewId, CurrentlyViewing, f64),
     Home(ViewId),
+    /// Zooms by a factor around the center of the view.
+    Zoom(ViewId, f32),
+    SetCamera(ViewId, Camera),
+    FitWidth(ViewId),
+    /// Shows one image pixel per device pixel.
+    ActualSize(ViewId),
+    SetZoomPercent(ViewId, f32),
+    /// Centers the view on a pixel, in image pixel coordinates.
+    CenterOn(ViewId, Vec2),
+    /// Fits the rectangle between two corners, in image pixel coordinates, in the view.
+    ZoomToRect(ViewId, Vec2, Vec2),
+    SetZoomSelection(Option<ZoomSelection>),
+    /// Rotates the image in the view by quarter turns, clockwise.
+    Rotate(ViewId, i32),
+    FlipHorizontally(ViewId),
+    FlipVertically(ViewId),
+    ResetOrientation(ViewId),
+    TogglePixelInspector,
+    SetPixelInspectorSize(u32),
+    ToggleShortcuts,
+    HideShortcuts,
+    SetImageListFilter(String),
+    SetImageListSort(ImageListSort),
+    SetImageListGrouping(ImageListGrouping),
+}
+
+impl UiAction {
+    /// The view whose camera the action changes.
+    fn camera_view(&self) -> Option<ViewId> {
+        match self {
+            UiAction::Home(view_id)
+            | UiAction::Zoom(view_id, _)
+            | UiAction::SetCamera(view_id, _)
+            | UiAction::FitWidth(view_id)
+            | UiAction::ActualSize(view_id)
+            | UiAction::SetZoomPercent(view_id, _)
+            | UiAction::CenterOn(view_id, _)
+            | UiAction::ZoomToRect(view_id, _, _)
+            | UiAction::Rotate(view_id, _)
+            | UiAction::FlipHorizontally(view_id)
+            | UiAction::FlipVertically(view_id)
+            | UiAction::ResetOrientation(view_id) => Some(*view_id),
+            _ => None,
+        }
+    }
 }
 
 impl Reducer<AppState> for UiAction {
     fn apply(self, mut app_state: Rc<AppState>) -> Rc<AppState> {
         let state = Rc::make_mut(&mut app_state);
 
+        if let Some(view_id) = self.camera_view() {
+            state.record_camera(view_id);
+        }
+
         match self {
             UiAction::Next(view_id) => {
                 let next_image_id = state
@@ -492,39 +1040,116 @@ impl Reducer<AppState> for UiAction {
             UiAction::ViewShiftScroll(view_id, cv, amount) => {
                 let id = cv.id();
 
-                let current_drawing_options = state
-                    .drawing_options
-                    .borrow()
-                    .get(id, &DrawingContext::BaseImage)
-                    .cloned()
-                    .unwrap_or_default();
-                if let (Some(current_index), Some(Image::Full(info))) = (
-                    current_drawing_options.batch_item,
-                    state.images.borrow().get(id),
-                ) {
-                    let batch_size = info.batch_info.as_ref().map_or(1, |info| info.batch_size);
-
+                if let Some((current_index, batch_size)) = state.batch_item_position(id) {
                     let new_index = ((current_index as f64 + amount) as i32)
                         .clamp(0, batch_size as i32 - 1) as u32;
 
                     if new_index != current_index {
-                        state
-                            .drawing_options
-                            .borrow_mut()
-                            .get_mut_ref(id.clone(), DrawingContext::BaseImage)
-                            .batch_item = Some(new_index);
-
-                        // send event to view that the batch item has changed
-                        state
-                            .image_views
-                            .borrow()
-                            .send_event_to_view(view_id, "svifpd:changeimage");
+                        state.set_batch_item(view_id, id.clone(), new_index);
                     }
                 }
             }
             UiAction::Home(view_id) => {
                 state.view_cameras.borrow_mut().reset(view_id);
             }
+            UiAction::Zoom(view_id, factor) => {
+                if let Some(geometry) = state.view_geometry(view_id) {
+                    let mut view_cameras = state.view_cameras.borrow_mut();
+                    let camera = view_cameras
+                        .get(view_id)
+                        .zoomed_around_center(factor, &geometry);
+                    view_cameras.set(view_id, camera);
+                }
+            }
+            UiAction::SetCamera(view_id, camera) => {
+                state.view_cameras.borrow_mut().set(view_id, camera);
+            }
+            UiAction::FitWidth(view_id) => {
+                if let Some(geometry) = state.view_geometry(view_id) {
+                    state
+                        .view_cameras
+                        .borrow_mut()
+                        .set(view_id, Camera::fit_width(&geometry));
+                }
+            }
+            UiAction::ActualSize(view_id) => {
+                if let Some(geometry) = state.view_geometry(view_id) {
+                    let mut view_cameras = state.view_cameras.borrow_mut();
+                    let camera = view_cameras
+                        .get(view_id)
+                        .with_zoom_percent(100.0, &geometry);
+                    view_cameras.set(view_id, camera);
+                }
+            }
+            UiAction::SetZoomPercent(view_id, percent) => {
+                if let Some(geometry) = state.view_geometry(view_id) {
+                    let mut view_cameras = state.view_cameras.borrow_mut();
+                    let camera = view_cameras
+                        .get(view_id)
+                        .with_zoom_percent(percent, &geometry);
+                    view_cameras.set(view_id, camera);
+                }
+            }
+            UiAction::CenterOn(view_id, pixel) => {
+                if let Some(geometry) = state.view_geometry(view_id) {
+                    let mut view_cameras = state.view_cameras.borrow_mut();
+                    let camera = view_cameras
+                        .get(view_id)
+                        .centered_on_pixel(pixel, &geometry);
+                    view_cameras.set(view_id, camera);
+                }
+            }
+            UiAction::ZoomToRect(view_id, a, b) => {
+                if let Some(geometry) = state.view_geometry(view_id) {
+                    state
+                        .view_cameras
+                        .borrow_mut()
+                        .set(view_id, Camera::fit_rect(a, b, &geometry));
+                }
+            }
+            UiAction::SetZoomSelection(selection) => {
+                state.zoom_selection = selection;
+            }
+            UiAction::Rotate(view_id, quarter_turns) => {
+                state.update_orientation(view_id, |orientation| {
+                    orientation.rotated_clockwise(quarter_turns)
+                });
+            }
+            UiAction::FlipHorizontally(view_id) => {
+                state.update_orientation(view_id, |orientation| orientation.flipped_horizontally());
+            }
+            UiAction::FlipVertically(view_id) => {
+                state.update_orientation(view_id, |orientation| orientation.flipped_vertically());
+            }
+            UiAction::ResetOrientation(view_id) => {
+                state.update_orientation(view_id, |_| Orientation::default());
+            }
+            UiAction::TogglePixelInspector => {
+                state.pixel_inspector.enabled = !state.pixel_inspector.enabled;
+            }
+            UiAction::SetPixelInspectorSize(size) => {
+                let size = size.clamp(
+                    PixelInspectorOptions::MIN_SIZE,
+                    PixelInspectorOptions::MAX_SIZE,
+                );
+                // keep the hovered pixel at the center
+                state.pixel_inspector.size = size | 1;
+            }
+            UiAction::ToggleShortcuts => {
+                state.shortcuts_visible = !state.shortcuts_visible;
+            }
+            UiAction::HideShortcuts => {
+                state.shortcuts_visible = false;
+            }
+            UiAction::SetImageListFilter(filter) => {
+                state.image_list.filter = filter;
+            }
+            UiAction::SetImageListSort(sort) => {
+                state.image_list.sort = sort;
+            }
+            UiAction::SetImageListGrouping(grouping) => {
+                state.image_list.grouping = grouping;
+            }
         }
 
         app_state
@@ -633,3 +1258,599 @@ impl Reducer<AppState> for OverlayAction {
         app_state
     }
 }
+
+pub(crate) enum LinkAction {
+    /// Links the image with `other`, taking over the drawing options of `other`.
+    Link {
+        image_id: ViewableObjectId,
+        other: ViewableObjectId,
+    },
+    Unlink {
+        image_id: ViewableObjectId,
+    },
+}
+
+impl Reducer<AppState> for LinkAction {
+    fn apply(self, mut app_state: Rc<AppState>) -> Rc<AppState> {
+        let state = Rc::make_mut(&mut app_state);
+
+        match self {
+            LinkAction::Link { image_id, other } => {
+                state
+                    .link_groups
+                    .borrow_mut()
+                    .link(image_id.clone(), other.clone());
+
+                let shared_drawing_options = state
+                    .drawing_options
+                    .borrow()
+                    .get(&other, &DrawingContext::BaseImage)
+                    .cloned()
+                    .unwrap_or_default();
+                let batch_item = state
+                    .drawing_options
+                    .borrow()
+                    .get(&image_id, &DrawingContext::BaseImage)
+                    .and_then(|d| d.batch_item);
+                let drawing_options = DrawingOptions {
+                    batch_item,
+                    ..shared_drawing_options
+                };
+                state.remember_drawing_options(&image_id, &drawing_options);
+                state.drawing_options.borrow_mut().set(
+                    image_id,
+                    DrawingContext::BaseImage,
+                    drawing_options,
+                );
+            }
+            LinkAction::Unlink { image_id } => {
+                state.link_groups.borrow_mut().unlink(&image_id);
+            }
+        }
+
+        app_state
+    }
+}
+
+pub(crate) enum UndoAction {
+    /// Undoes the last drawing options or camera change of the image shown in the view.
+    Undo(ViewId),
+    Redo(ViewId),
+}
+
+impl Reducer<AppState> for UndoAction {
+    fn apply(self, mut app_state: Rc<AppState>) -> Rc<AppState> {
+        let state = Rc::make_mut(&mut app_state);
+
+        let view_id = match self {
+            UndoAction::Undo(view_id) | UndoAction::Redo(view_id) => view_id,
+        };
+        let Some(cv) = state.image_views.borrow().get_currently_viewing(view_id) else {
+            return app_state;
+        };
+        let image_id = cv.id().clone();
+        let current = |snapshot: &Snapshot| state.snapshot_like(&image_id, snapshot);
+        let snapshot = match self {
+            UndoAction::Undo(_) => state.undo_history.borrow_mut().undo(&image_id, current),
+            UndoAction::Redo(_) => state.undo_history.borrow_mut().redo(&image_id, current),
+        };
+        if let Some(snapshot) = snapshot {
+            state.restore_snapshot(image_id, snapshot);
+        }
+
+        app_state
+    }
+}
+
+pub(crate) enum PlaybackAction {
+    Play(ViewId),
+    Pause(ViewId),
+    SetFps(ViewId, f64),
+    SetLoop(ViewId, bool),
+    Seek(ViewId, u32),
+    Step(ViewId),
+}
+
+fn currently_viewed_batch(state: &AppState, view_id: ViewId) -> Option<ViewableObjectId> {
+    match state.image_views.borrow().get_currently_viewing(view_id)? {
+        CurrentlyViewing::BatchItem(image_id) => Some(image_id),
+        CurrentlyViewing::Image(_) => None,
+    }
+}
+
+fn playback_step(state: &mut AppState, view_id: ViewId) {
+    let Some((image_id, (current_index, batch_size))) = currently_viewed_batch(state, view_id)
+        .and_then(|image_id| {
+            let position = state.batch_item_position(&image_id)?;
+            Some((image_id, position))
+        })
+    else {
+        state.playback.borrow_mut().stop(view_id);
+        return;
+    };
+
+    let next_index = if current_index + 1 < batch_size {
+        current_index + 1
+    } else if state.playback.borrow().get(view_id).looping {
+        0
+    } else {
+        state.playback.borrow_mut().stop(view_id);
+        return;
+    };
+
+    // Hold the current frame until the next item arrives, instead of showing an empty view.
+    let next_is_loaded = match state.image_cache.borrow().get(&image_id) {
+        ImageAvailability::Available(image) | ImageAvailability::Pending(Some(image)) => {
+            image.borrow().has_batch_item(next_index)
+        }
+        _ => false,
+    };
+    if next_is_loaded {
+        state.set_batch_item(view_id, image_id, next_index);
+    }
+}
+
+impl Reducer<AppState> for PlaybackAction {
+    fn apply(self, mut app_state: Rc<AppState>) -> Rc<AppState> {
+        let state = Rc::make_mut(&mut app_state);
+
+        match self {
+            PlaybackAction::Play(view_id) => {
+                if let Some(image_id) = currently_viewed_batch(state, view_id) {
+                    let looping = state.playback.borrow().get(view_id).looping;
+                    // restart from the beginning when playing from the last item
+                    if let Some((current_index, batch_size)) = state.batch_item_position(&image_id)
+                    {
+                        if !looping && current_index + 1 >= batch_size {
+                            state.set_batch_item(view_id, image_id, 0);
+                        }
+                    }
+                    let mut playback = state.playback.borrow_mut();
+                    let playback = playback.get_mut(view_id);
+                    playback.playing = true;
+                    playback.requested_items.clear();
+                }
+            }
+            PlaybackAction::Pause(view_id) => {
+                state.playback.borrow_mut().stop(view_id);
+            }
+            PlaybackAction::SetFps(view_id, fps) => {
+                state.playback.borrow_mut().get_mut(view_id).fps = fps;
+            }
+            PlaybackAction::SetLoop(view_id, looping) => {
+                state.playback.borrow_mut().get_mut(view_id).looping = looping;
+            }
+            PlaybackAction::Seek(view_id, index) => {
+                if let Some(image_id) = currently_viewed_batch(state, view_id) {
+                    if let Some((current_index, batch_size)) = state.batch_item_position(&image_id)
+                    {
+                        let index = index.min(batch_size.saturating_sub(1));
+                        if index != current_index {
+                            state.set_batch_item(view_id, image_id, index);
+                        }
+                    }
+                }
+            }
+            PlaybackAction::Step(view_id) => playback_step(state, view_id),
+        }
+
+        app_state
+    }
+}
+
+/// Key in `ImageInfo::additional_info` that marks an image as a difference of two snapshots.
+const DIFF_INFO_KEY: &str = "diff of";
+
+pub(crate) enum HistoryAction {
+    /// The extension reports the frame of the session. A frame change ends the current step.
+    FrameChanged {
+        session_id: SessionId,
+        frame_id: u32,
+    },
+    /// Show a snapshot of the given step, or the live data when `None`.
+    ShowStep(ViewableObjectId, Option<u32>),
+    /// Show the difference between two steps (`None` is the live data) as a new image.
+    Diff {
+        view_id: ViewId,
+        image_id: ViewableObjectId,
+        steps: (Option<u32>, Option<u32>),
+    },
+}
+
+fn step_texture(
+    state: &AppState,
+    image_id: &ViewableObjectId,
+    step_index: Option<u32>,
+) -> Result<Mrc<TextureImage>> {
+    let value_history = state.value_history.borrow();
+    match step_index {
+        Some(index) => value_history
+            .get(image_id, index)
+            .map(|snapshot| snapshot.texture.clone())
+            .ok_or(anyhow!("No snapshot of step {} for {:?}", index, image_id)),
+        None if value_history.is_viewing_snapshot(image_id) => value_history
+            .live_data(image_id)
+            .ok_or(anyhow!("No data of the current step for {:?}", image_id)),
+        None => state
+            .image_cache
+            .borrow()
+            .loaded_images()
+            .find(|(id, _)| *id == image_id)
+            .map(|(_, image)| image.clone())
+            .ok_or(anyhow!("No data of the current step for {:?}", image_id)),
+    }
+}
+
+fn diff_steps(
+    state: &AppState,
+    image_id: &ViewableObjectId,
+    steps: (Option<u32>, Option<u32>),
+) -> Result<ViewableObjectId> {
+    let a = step_texture(state, image_id, steps.0)?;
+    let b = step_texture(state, image_id, steps.1)?;
+    let (a, b) = (a.borrow(), b.borrow());
+    let (info_a, info_b) = (&a.info, &b.info);
+
+    let layout = |info: &ImageInfo| {
+        (
+            info.width,
+            info.height,
+            info.channels,
+            info.datatype,
+            info.data_ordering,
+        )
+    };
+    if layout(info_a) != layout(info_b) {
+        return Err(anyhow!(
+            "Cannot diff {:?}: the shape or datatype changed between the steps",
+            image_id
+        ));
+    }
+
+    let batch_item = state
+        .drawing_options
+        .borrow()
+        .get(image_id, &DrawingContext::BaseImage)
+        .and_then(|d| d.batch_item)
+        .unwrap_or(0);
+    let (bytes_a, bytes_b) = a
+        .bytes
+        .get(&batch_item)
+        .zip(b.bytes.get(&batch_item))
+        .ok_or(anyhow!(
+            "Batch item {} is not loaded in both steps",
+            batch_item
+        ))?;
+
+    let bytes = image_difference_on_bytes(bytes_a, bytes_b, info_a.datatype)?;
+
+    let step_label = |step: Option<u32>| step.map_or("live".to_string(), |i| format!("step {}", i));
+    let expression = format!(
+        "{} [{} - {}]",
+        info_a.expression,
+        step_label(steps.0),
+        step_label(steps.1)
+    );
+    let diff_id = ViewableObjectId::new(image_id.session_id(), &expression);
+    let info = ImageInfo {
+        image_id: diff_id.clone(),
+        value_variable_kind: ValueVariableKind::Expression,
+        expression,
+        width: info_a.width,
+        height: info_a.height,
+        channels: info_a.channels,
+        datatype: Datatype::Float32,
+        batch_info: None,
+        data_ordering: info_a.data_ordering,
+        additional_info: HashMap::from([(DIFF_INFO_KEY.to_string(), info_a.expression.clone())]),
+    };
+
+    handle_received_image(
+        state,
+        ImageObject::WithData(ImageData {
+            info,
+            computed_info: ComputedInfo::pending(Datatype::Float32, info_a.channels),
+            bytes,
+        }),
+    )?;
+
+    Ok(diff_id)
+}
+
+impl Reducer<AppState> for HistoryAction {
+    fn apply(self, mut app_state: Rc<AppState>) -> Rc<AppState> {
+        let state = Rc::make_mut(&mut app_state);
+
+        match self {
+            HistoryAction::FrameChanged {
+                session_id,
+                frame_id,
+            } => {
+                let current = state.value_history.borrow().current_step(&session_id);
+                if current.frame_id.is_none() {
+                    state
+                        .value_history
+                        .borrow_mut()
+                        .set_frame(&session_id, frame_id);
+                } else if current.frame_id != Some(frame_id) {
+                    let images = {
+                        let value_history = state.value_history.borrow();
+                        state
+                            .image_cache
+                            .borrow()
+                            .loaded_images()
+                            .filter(|(id, _)| id.session_id() == &session_id)
+                            .filter_map(|(id, image)| {
+                                if value_history.is_viewing_snapshot(id) {
+                                    value_history.live_data(id)
+                                } else {
+                                    Some(image.clone())
+                                }
+                                .map(|image| (id.clone(), image))
+                            })
+                            .filter(|(_, image)| {
+                                !image
+                                    .borrow()
+                                    .info
+                                    .additional_info
+                                    .contains_key(DIFF_INFO_KEY)
+                            })
+                            .collect::<Vec<_>>()
+                    };
+                    log::debug!(
+                        "HistoryAction::FrameChanged: archiving {} images of {}",
+                        images.len(),
+                        current
+                    );
+                    state
+                        .value_history
+                        .borrow_mut()
+                        .archive(&session_id, frame_id, images);
+                }
+            }
+            HistoryAction::ShowStep(image_id, Some(step_index)) => {
+                if let Err(e) = state.show_snapshot(&image_id, step_index) {
+                    log::error!("HistoryAction::ShowStep: {}", e);
+                }
+            }
+            HistoryAction::ShowStep(image_id, None) => {
+                state.show_live_data(&image_id);
+            }
+            HistoryAction::Diff {
+                view_id,
+                image_id,
+                steps,
+            } => match diff_steps(state, &image_id, steps) {
+                Ok(diff_id) => state.set_image_to_view(diff_id, view_id),
+                Err(e) => log::error!("HistoryAction::Diff: {}", e),
+            },
+        }
+
+        app_state
+    }
+}
+
+pub(crate) enum LineProfileAction {
+    ToggleTool,
+    SetInterpolation(Interpolation),
+    StartLine(ViewId, Vec2),
+    MoveLineEnd(ViewId, Vec2),
+    FinishLine(ViewId),
+    SetCursor(ViewId, Option<f32>),
+    Clear(ViewId),
+}
+
+impl Reducer<AppState> for LineProfileAction {
+    fn apply(self, mut app_state: Rc<AppState>) -> Rc<AppState> {
+        let state = Rc::make_mut(&mut app_state);
+        let mut line_profiles = state.line_profiles.borrow_mut();
+
+        match self {
+            LineProfileAction::ToggleTool => {
+                line_profiles.enabled = !line_profiles.enabled;
+                if line_profiles.enabled {
+                    state.annotations.borrow_mut().tool = None;
+                }
+            }
+            LineProfileAction::SetInterpolation(interpolation) => {
+                line_profiles.interpolation = interpolation;
+            }
+            LineProfileAction::StartLine(view_id, position) => {
+                let profile = line_profiles.get_mut(view_id);
+                profile.line = Some(Line {
+                    start: position,
+                    end: position,
+                });
+                profile.is_drawing = true;
+                profile.cursor = None;
+            }
+            LineProfileAction::MoveLineEnd(view_id, position) => {
+                let profile = line_profiles.get_mut(view_id);
+                if profile.is_drawing {
+                    if let Some(line) = profile.line.as_mut() {
+                        line.end = position;
+                    }
+                }
+            }
+            LineProfileAction::FinishLine(view_id) => {
+                let profile = line_profiles.get_mut(view_id);
+                profile.is_drawing = false;
+                // a click without dragging does not make a line
+                if profile.line.is_some_and(|line| line.length() < 1.0) {
+                    profile.line = None;
+                }
+            }
+            LineProfileAction::SetCursor(view_id, cursor) => {
+                line_profiles.get_mut(view_id).cursor = cursor;
+            }
+            LineProfileAction::Clear(view_id) => {
+                *line_profiles.get_mut(view_id) = Default::default();
+            }
+        }
+        drop(line_profiles);
+
+        app_state
+    }
+}
+
+/// Distance from the first vertex, in image pixels, within which a click closes the polygon.
+const POLYGON_CLOSE_DISTANCE: f32 = 2.0;
+
+/// The image shown in the view, with its size in pixels.
+fn annotated_image(state: &AppState, view_id: ViewId) -> Option<(ViewableObjectId, UVec2)> {
+    let image_id = state
+        .image_views
+        .borrow()
+        .get_currently_viewing(view_id)?
+        .id()
+        .clone();
+    match state.image_cache.borrow().get(&image_id) {
+        ImageAvailability::Available(texture) => {
+            let size = texture.borrow().image_size();
+            Some((image_id, UVec2::new(size.width as u32, size.height as u32)))
+        }
+        _ => None,
+    }
+}
+
+pub(crate) enum AnnotationAction {
+    /// Selects a tool, or deselects it if it is already selected.
+    ToggleTool(AnnotationTool),
+    SetLabel(u8),
+    SetBrushRadius(f32),
+    Press(ViewId, Vec2),
+    Move(ViewId, Option<Vec2>),
+    Release(ViewId),
+    FinishPolygon(ViewId),
+    CancelDraft(ViewId),
+    Clear(ViewableObjectId),
+}
+
+impl Reducer<AppState> for AnnotationAction {
+    fn apply(self, mut app_state: Rc<AppState>) -> Rc<AppState> {
+        let state = Rc::make_mut(&mut app_state);
+        let target = match self {
+            AnnotationAction::Press(view_id, _)
+            | AnnotationAction::Move(view_id, _)
+            | AnnotationAction::Release(view_id)
+            | AnnotationAction::FinishPolygon(view_id) => annotated_image(state, view_id),
+            _ => None,
+        };
+        let mut annotations = state.annotations.borrow_mut();
+        let tool = annotations.tool;
+        let label = annotations.label;
+        let brush_radius = annotations.brush_radius;
+
+        match self {
+            AnnotationAction::ToggleTool(selected) => {
+                annotations.tool = (tool != Some(selected)).then_some(selected);
+                annotations.reset_drafts();
+                if annotations.is_active() {
+                    state.line_profiles.borrow_mut().enabled = false;
+                }
+            }
+            AnnotationAction::SetLabel(label) => {
+                annotations.label = label.max(1);
+            }
+            AnnotationAction::SetBrushRadius(radius) => {
+                annotations.brush_radius =
+                    radius.clamp(Annotations::MIN_BRUSH_RADIUS, Annotations::MAX_BRUSH_RADIUS);
+            }
+            AnnotationAction::Press(view_id, position) => {
+                if let (Some(tool), Some((image_id, size))) = (tool, target) {
+                    match tool {
+                        AnnotationTool::Brush | AnnotationTool::Eraser => {
+                            let paint_label = if tool == AnnotationTool::Brush {
+                                label
+                            } else {
+                                0
+                            };
+                            let mask = &mut annotations.get_or_create(image_id).mask;
+                            // the image may have changed shape since the mask was created
+                            if mask
+                                .as_ref()
+                                .is_some_and(|mask| (mask.width, mask.height) != (size.x, size.y))
+                            {
+                                *mask = None;
+                            }
+                            mask.get_or_insert_with(|| LabelMask::new(size.x, size.y))
+                                .paint_stroke(position, position, brush_radius, paint_label);
+                            annotations.draft_mut(view_id).stroke = Some(position);
+                        }
+                        AnnotationTool::Polygon => {
+                            let draft = annotations.draft_mut(view_id);
+                            // clicking next to the first vertex closes the polygon
+                            let closes = draft.polygon.len() >= 3
+                                && draft.polygon[0].distance(position) < POLYGON_CLOSE_DISTANCE;
+                            if closes {
+                                let points = std::mem::take(&mut draft.polygon);
+                                annotations
+                                    .get_or_create(image_id)
+                                    .shapes
+                                    .push(Shape::Polygon { label, points });
+                            } else {
+                                draft.polygon.push(position);
+                            }
+                        }
+                        AnnotationTool::Box => {
+                            annotations.draft_mut(view_id).box_start = Some(position);
+                        }
+                    }
+                }
+            }
+            AnnotationAction::Move(view_id, position) => {
+                let draft = annotations.draft_mut(view_id);
+                draft.cursor = position;
+                if let (Some(from), Some(to)) = (draft.stroke, position) {
+                    draft.stroke = Some(to);
+                    if let (Some(tool), Some((image_id, _))) = (tool, target) {
+                        let paint_label = if tool == AnnotationTool::Brush {
+                            label
+                        } else {
+                            0
+                        };
+                        if let Some(mask) = annotations.get_or_create(image_id).mask.as_mut() {
+                            mask.paint_stroke(from, to, brush_radius, paint_label);
+                        }
+                    }
+                }
+            }
+            AnnotationAction::Release(view_id) => {
+                let draft = annotations.draft_mut(view_id);
+                draft.stroke = None;
+                let corners = draft.box_start.take().zip(draft.cursor);
+                if let (Some((start, end)), Some((image_id, _))) = (corners, target) {
+                    let (min, max) = (start.min(end), start.max(end));
+                    // a click without dragging does not make a box
+                    if (max - min).min_element() >= 1.0 {
+                        annotations.get_or_create(image_id).shapes.push(Shape::Box {
+                            label,
+                            start: min,
+                            end: max,
+                        });
+                    }
+                }
+            }
+            AnnotationAction::FinishPolygon(view_id) => {
+                let mut points = std::mem::take(&mut annotations.draft_mut(view_id).polygon);
+                // the clicks of a double click add the same vertex twice
+                points.dedup();
+                if let Some((image_id, _)) = target.filter(|_| points.len() >= 3) {
+                    annotations
+                        .get_or_create(image_id)
+                        .shapes
+                        .push(Shape::Polygon { label, points });
+                }
+            }
+            AnnotationAction::CancelDraft(view_id) => {
+                *annotations.draft_mut(view_id) = Default::default();
+            }
+            AnnotationAction::Clear(image_id) => {
+                annotations.clear(&image_id);
+            }
+        }
+        drop(annotations);
+
+        app_state
+    }
+}
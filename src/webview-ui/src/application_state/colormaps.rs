@@ -28,6 +28,10 @@ impl ColorMapTexturesCache {
         self.0.insert(name.clone(), Rc::new(tex));
         Ok(self.0.get(&name).unwrap().clone())
     }
+
+    pub(crate) fn clear(&mut self) {
+        self.0.clear();
+    }
 }
 
 impl Default for ColorMapTexturesCache {
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::{
+    common::{SessionId, ViewableObjectId},
+    vscode::vscode_requests::VSCodeRequests,
+};
+
+/// An error of a single image, shown on its list item and in the views displaying it.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub(crate) enum ImageError {
+    #[error("Invalid image data: {0}")]
+    InvalidData(String),
+    #[error("Could not create the textures: {0}")]
+    Texture(String),
+    #[error("{0}")]
+    IncompatibleBatchItems(String),
+    #[error("Could not use the colormap: {0}")]
+    Colormap(String),
+}
+
+impl ImageError {
+    /// Rendering errors can be gone once the drawing options change.
+    fn is_rendering_error(&self) -> bool {
+        matches!(self, ImageError::Colormap(_))
+    }
+}
+
+/// The last error of each image.
+#[derive(Debug, Default)]
+pub(crate) struct ImageErrors {
+    errors: HashMap<ViewableObjectId, ImageError>,
+    /// The last error sent to the extension, kept when rendering errors are cleared so the same
+    /// error found again is not sent again.
+    reported: HashMap<ViewableObjectId, ImageError>,
+}
+
+impl ImageErrors {
+    pub(crate) fn get(&self, image_id: &ViewableObjectId) -> Option<&ImageError> {
+        self.errors.get(image_id)
+    }
+
+    /// Keeps the error of the image. A new error is also sent to the extension's log.
+    pub(crate) fn report(&mut self, image_id: ViewableObjectId, error: ImageError) {
+        if self.errors.get(&image_id) == Some(&error) {
+            return;
+        }
+        if self.reported.get(&image_id) != Some(&error) {
+            log::error!("{:?}: {}", image_id, error);
+            VSCodeRequests::report_error(image_id.clone(), error.to_string());
+            self.reported.insert(image_id.clone(), error.clone());
+        }
+        self.errors.insert(image_id, error);
+    }
+
+    pub(crate) fn clear(&mut self, image_id: &ViewableObjectId) {
+        self.errors.remove(image_id);
+        self.reported.remove(image_id);
+    }
+
+    pub(crate) fn clear_session(&mut self, session_id: &SessionId) {
+        self.errors
+            .retain(|image_id, _| image_id.session_id() != session_id);
+        self.reported
+            .retain(|image_id, _| image_id.session_id() != session_id);
+    }
+
+    pub(crate) fn clear_rendering_errors(&mut self) {
+        self.errors.retain(|_, error| !error.is_rendering_error());
+    }
+}
@@ -0,0 +1,186 @@
+use std::cmp::{Ordering, Reverse};
+
+use crate::common::{Image, ViewableObjectId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum ImageListSort {
+    /// Pinned images first, then in the order they were received.
+    #[default]
+    Default,
+    Name,
+    Size,
+    Type,
+}
+
+impl ImageListSort {
+    pub(crate) const ALL: [ImageListSort; 4] = [
+        ImageListSort::Default,
+        ImageListSort::Name,
+        ImageListSort::Size,
+        ImageListSort::Type,
+    ];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            ImageListSort::Default => "Default order",
+            ImageListSort::Name => "Name",
+            ImageListSort::Size => "Size",
+            ImageListSort::Type => "Type",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum ImageListGrouping {
+    /// Images of the active session, not grouped.
+    #[default]
+    None,
+    /// Images of all sessions, grouped by session.
+    Session,
+    /// Grouped by the expression before the first index or attribute, e.g. `outputs` for
+    /// `outputs["mask"]`.
+    ExpressionPrefix,
+}
+
+impl ImageListGrouping {
+    pub(crate) const ALL: [ImageListGrouping; 3] = [
+        ImageListGrouping::None,
+        ImageListGrouping::Session,
+        ImageListGrouping::ExpressionPrefix,
+    ];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            ImageListGrouping::None => "No grouping",
+            ImageListGrouping::Session => "Session",
+            ImageListGrouping::ExpressionPrefix => "Expression prefix",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub(crate) struct ImageListOptions {
+    pub filter: String,
+    pub sort: ImageListSort,
+    pub grouping: ImageListGrouping,
+}
+
+/// Whether the expression, shape, dtype or any additional info of the image contains the
+/// filter, ignoring case.
+pub(crate) fn matches_filter(image: &Image, filter: &str) -> bool {
+    let filter = filter.trim().to_lowercase();
+    if filter.is_empty() {
+        return true;
+    }
+    let minimal = image.minimal();
+    let mut texts = vec![minimal.expression.clone()];
+    texts.extend(minimal.additional_info.values().cloned());
+    if let Image::Full(info) = image {
+        texts.push(format!("{}x{}", info.width, info.height));
+        texts.push(format!("{:?}", info.datatype));
+    }
+    texts
+        .iter()
+        .any(|text| text.to_lowercase().contains(&filter))
+}
+
+/// Number of values of the image, from its shape when only the placeholder is known.
+fn image_size(image: &Image) -> Option<u64> {
+    match image {
+        Image::Full(info) => {
+            let batch_size = info.batch_info.as_ref().map_or(1, |b| b.batch_size);
+            Some(info.width as u64 * info.height as u64 * info.channels as u64 * batch_size as u64)
+        }
+        Image::Placeholder(placeholder) => {
+            let shape = placeholder.additional_info.get("shape")?;
+            shape
+                .split(|c: char| !c.is_ascii_digit())
+                .filter(|dim| !dim.is_empty())
+                .map(|dim| dim.parse::<u64>().ok())
+                .product()
+        }
+    }
+}
+
+fn image_type(image: &Image) -> Option<String> {
+    match image.minimal().additional_info.get("dtype") {
+        Some(dtype) => Some(dtype.clone()),
+        None => match image {
+            Image::Full(info) => Some(format!("{:?}", info.datatype).to_lowercase()),
+            Image::Placeholder(_) => None,
+        },
+    }
+}
+
+fn expression_prefix(expression: &str) -> &str {
+    let end = expression.find(['[', '.', '(']).unwrap_or(expression.len());
+    expression[..end].trim()
+}
+
+fn compare(a: &Image, b: &Image, sort: ImageListSort) -> Ordering {
+    // images without the sorted property come last
+    fn by<T: Ord>(a: Option<T>, b: Option<T>) -> Ordering {
+        match (a, b) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+    }
+    match sort {
+        ImageListSort::Default => Ordering::Equal,
+        ImageListSort::Name => a
+            .minimal()
+            .expression
+            .to_lowercase()
+            .cmp(&b.minimal().expression.to_lowercase()),
+        // largest first
+        ImageListSort::Size => by(image_size(a).map(Reverse), image_size(b).map(Reverse)),
+        ImageListSort::Type => by(image_type(a), image_type(b)),
+    }
+}
+
+/// A group of the image list, with its title if the list is grouped.
+pub(crate) struct ImageListGroup<'a> {
+    pub title: Option<String>,
+    pub images: Vec<(&'a ViewableObjectId, &'a Image)>,
+}
+
+/// Filters, sorts and groups the images, given in the default order. Pinned images stay first
+/// in their group. `session_title` names the group of a session.
+pub(crate) fn arrange_images<'a>(
+    images: impl Iterator<Item = (&'a ViewableObjectId, &'a Image)>,
+    options: &ImageListOptions,
+    is_pinned: impl Fn(&ViewableObjectId) -> bool,
+    session_title: impl Fn(&ViewableObjectId) -> String,
+) -> Vec<ImageListGroup<'a>> {
+    let mut images = images
+        .filter(|(_, image)| matches_filter(image, &options.filter))
+        .collect::<Vec<_>>();
+    // stable, so equal images keep the default order
+    images.sort_by(|(id_a, a), (id_b, b)| {
+        is_pinned(id_b)
+            .cmp(&is_pinned(id_a))
+            .then_with(|| compare(a, b, options.sort))
+    });
+
+    let group_title = |id: &ViewableObjectId, image: &Image| match options.grouping {
+        ImageListGrouping::None => None,
+        ImageListGrouping::Session => Some(session_title(id)),
+        ImageListGrouping::ExpressionPrefix => {
+            Some(expression_prefix(image.minimal().expression).to_string())
+        }
+    };
+    let mut groups: Vec<ImageListGroup> = Vec::new();
+    for (id, image) in images {
+        let title = group_title(id, image);
+        match groups.iter_mut().find(|group| group.title == title) {
+            Some(group) => group.images.push((id, image)),
+            None => groups.push(ImageListGroup {
+                title,
+                images: vec![(id, image)],
+            }),
+        }
+    }
+    groups
+}
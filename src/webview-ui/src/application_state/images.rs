@@ -3,9 +3,15 @@ use yewdux::mrc::Mrc;
 
 use crate::{
     coloring::DrawingOptions,
-    common::{texture_image::TextureImage, Image, SessionId, ViewableObjectId},
+    common::{
+        texture_image::{MemoryUsage, TextureImage},
+        Image, SessionId, ViewableObjectId,
+    },
+};
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
 };
-use std::collections::HashMap;
 
 #[derive(Clone, Debug)]
 pub(crate) enum ImageAvailability {
@@ -37,32 +43,40 @@ impl ImageAvailability {
 }
 
 #[derive(Default)]
-pub(crate) struct ImageCache(HashMap<ViewableObjectId, ImageAvailability>);
+pub(crate) struct ImageCache {
+    images: HashMap<ViewableObjectId, ImageAvailability>,
+    /// When each batch item of the loaded images was last loaded or shown, on a logical clock.
+    last_used: RefCell<HashMap<(ViewableObjectId, u32), u64>>,
+    clock: Cell<u64>,
+    /// Batch items requested ahead of being shown, and not shown since. They are evicted first.
+    prefetched: RefCell<HashSet<(ViewableObjectId, u32)>>,
+}
 
 impl ImageCache {
     pub(crate) fn get(&self, id: &ViewableObjectId) -> ImageAvailability {
-        self.0
+        self.images
             .get(id)
             .cloned()
             .unwrap_or(ImageAvailability::NotAvailable)
     }
 
     pub(crate) fn set_pending(&mut self, id: &ViewableObjectId) {
-        if let Some(ImageAvailability::Pending(_)) = self.0.get(id) {
+        if let Some(ImageAvailability::Pending(_)) = self.images.get(id) {
             // do nothing
-        } else if let Some(ImageAvailability::Available(current)) = self.0.remove(id) {
-            self.0
+        } else if let Some(ImageAvailability::Available(current)) = self.images.remove(id) {
+            self.images
                 .insert(id.clone(), ImageAvailability::Pending(Some(current)));
         } else {
-            self.0.insert(id.clone(), ImageAvailability::Pending(None));
+            self.images
+                .insert(id.clone(), ImageAvailability::Pending(None));
         }
     }
 
     pub(crate) fn try_set_available(&mut self, id: &ViewableObjectId) -> Result<()> {
-        if let Some(ImageAvailability::Available(_)) = self.0.get(id) {
+        if let Some(ImageAvailability::Available(_)) = self.images.get(id) {
             Ok(())
-        } else if let Some(ImageAvailability::Pending(Some(image))) = self.0.remove(id) {
-            self.0
+        } else if let Some(ImageAvailability::Pending(Some(image))) = self.images.remove(id) {
+            self.images
                 .insert(id.clone(), ImageAvailability::Available(image));
             Ok(())
         } else {
@@ -70,24 +84,195 @@ impl ImageCache {
         }
     }
 
+    /// The requested data won't arrive, the image keeps the data it had before the request.
+    pub(crate) fn cancel_pending(&mut self, id: &ViewableObjectId) {
+        match self.images.remove(id) {
+            Some(ImageAvailability::Pending(Some(image))) => {
+                self.images
+                    .insert(id.clone(), ImageAvailability::Available(image));
+            }
+            Some(ImageAvailability::Pending(None)) | None => {}
+            Some(availability) => {
+                self.images.insert(id.clone(), availability);
+            }
+        }
+    }
+
     pub(crate) fn set_image(&mut self, id: &ViewableObjectId, image: TextureImage) {
-        self.0
+        self.touch_all(id, &image);
+        self.images
             .insert(id.clone(), ImageAvailability::Available(Mrc::new(image)));
     }
 
-    pub(crate) fn update(&mut self, id: &ViewableObjectId, image: TextureImage) {
-        match self.0.remove_entry(id) {
+    /// Adds the batch items of `image` to the image in the cache. Batch items of an image with
+    /// a different shape are rejected.
+    pub(crate) fn update(&mut self, id: &ViewableObjectId, image: TextureImage) -> Result<()> {
+        match self.images.remove_entry(id) {
             Some((id, ImageAvailability::Available(current)))
             | Some((id, ImageAvailability::Pending(Some(current)))) => {
-                current.with_mut(|img| img.update(image));
-                self.0.insert(id, ImageAvailability::Available(current));
+                self.touch_all(&id, &image);
+                let result = current.with_mut(|img| img.update(image));
+                self.images
+                    .insert(id, ImageAvailability::Available(current));
+                result
+            }
+            _ => {
+                self.set_image(id, image);
+                Ok(())
             }
-            _ => self.set_image(id, image),
         }
     }
 
+    pub(crate) fn set_texture(&mut self, id: &ViewableObjectId, image: Mrc<TextureImage>) {
+        self.touch_all(id, &image.borrow());
+        self.images
+            .insert(id.clone(), ImageAvailability::Available(image));
+    }
+
+    pub(crate) fn remove(&mut self, id: &ViewableObjectId) {
+        self.images.remove(id);
+        self.last_used
+            .borrow_mut()
+            .retain(|(image_id, _), _| image_id != id);
+    }
+
+    /// Images that have data, including pending images that hold data from a previous request.
+    pub(crate) fn loaded_images(
+        &self,
+    ) -> impl Iterator<Item = (&ViewableObjectId, &Mrc<TextureImage>)> {
+        self.images
+            .iter()
+            .filter_map(|(id, availability)| match availability {
+                ImageAvailability::Available(image) | ImageAvailability::Pending(Some(image)) => {
+                    Some((id, image))
+                }
+                _ => None,
+            })
+    }
+
     pub(crate) fn clear(&mut self) {
-        self.0.clear();
+        self.images.clear();
+        self.last_used.borrow_mut().clear();
+        self.prefetched.borrow_mut().clear();
+    }
+
+    fn record_use(&self, id: &ViewableObjectId, batch_item: u32) {
+        let time = self.clock.get() + 1;
+        self.clock.set(time);
+        self.last_used
+            .borrow_mut()
+            .insert((id.clone(), batch_item), time);
+    }
+
+    fn touch_all(&self, id: &ViewableObjectId, image: &TextureImage) {
+        image
+            .batch_items()
+            .for_each(|batch_item| self.record_use(id, batch_item));
+    }
+
+    /// Marks the batch item as shown now, so it is evicted after the items shown before it.
+    pub(crate) fn touch(&self, id: &ViewableObjectId, batch_item: u32) {
+        self.record_use(id, batch_item);
+        self.prefetched
+            .borrow_mut()
+            .remove(&(id.clone(), batch_item));
+    }
+
+    /// Records that the batch item was requested before being shown. It stays marked until it
+    /// is shown, so an evicted item is not prefetched again.
+    pub(crate) fn mark_prefetched(&self, id: &ViewableObjectId, batch_item: u32) {
+        self.prefetched
+            .borrow_mut()
+            .insert((id.clone(), batch_item));
+    }
+
+    pub(crate) fn is_prefetched(&self, id: &ViewableObjectId, batch_item: u32) -> bool {
+        self.prefetched.borrow().contains(&(id.clone(), batch_item))
+    }
+
+    /// Ids of the loaded [`TextureImage`]s, which other holders of the same data do not count.
+    pub(crate) fn texture_ids(&self) -> HashSet<u32> {
+        self.loaded_images()
+            .map(|(_, image)| image.borrow().id)
+            .collect()
+    }
+
+    pub(crate) fn memory_usage(&self) -> MemoryUsage {
+        self.loaded_images()
+            .map(|(_, image)| image.borrow().memory_usage())
+            .sum()
+    }
+
+    /// The batch items to remove so that the loaded images fit in `budget` bytes: first the
+    /// prefetched items that were never shown, then the least recently used ones. Pending images
+    /// and the spared items are never evicted, so the result may not be enough to fit in the
+    /// budget.
+    pub(crate) fn items_to_evict(
+        &self,
+        budget: usize,
+        is_spared: impl Fn(&ViewableObjectId, u32) -> bool,
+    ) -> Vec<(ViewableObjectId, u32)> {
+        let mut usage = self.memory_usage().total();
+        if usage <= budget {
+            return Vec::new();
+        }
+
+        let last_used = self.last_used.borrow();
+        let prefetched = self.prefetched.borrow();
+        let mut candidates = self
+            .images
+            .iter()
+            .filter_map(|(id, availability)| match availability {
+                ImageAvailability::Available(image) => Some((id, image.borrow())),
+                _ => None,
+            })
+            .flat_map(|(id, image)| {
+                image
+                    .batch_items()
+                    .filter(|batch_item| !is_spared(id, *batch_item))
+                    .map(|batch_item| {
+                        let key = (id.clone(), batch_item);
+                        let order = (
+                            !prefetched.contains(&key),
+                            last_used.get(&key).copied().unwrap_or(0),
+                        );
+                        let bytes = image.batch_item_memory_usage(batch_item).total();
+                        (key, order, bytes)
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(_, order, _)| *order);
+
+        let mut evicted = Vec::new();
+        for (key, _, bytes) in candidates {
+            if usage <= budget {
+                break;
+            }
+            usage = usage.saturating_sub(bytes);
+            evicted.push(key);
+        }
+        evicted
+    }
+
+    /// Frees the batch item. An image left without data is removed, and is fetched again when
+    /// it is shown. Returns whether the image was removed.
+    pub(crate) fn remove_batch_item(&mut self, id: &ViewableObjectId, batch_item: u32) -> bool {
+        let Some(ImageAvailability::Available(image)) = self.images.get(id) else {
+            return false;
+        };
+        let is_empty = {
+            let mut image = image.borrow_mut();
+            image.remove_batch_item(batch_item);
+            image.bytes.is_empty()
+        };
+        self.last_used
+            .borrow_mut()
+            .remove(&(id.clone(), batch_item));
+        if is_empty {
+            self.remove(id);
+        }
+        is_empty
     }
 }
 
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+
+use glam::{UVec2, Vec2, Vec4};
+
+use crate::common::{constants::all_views, pixel_value::PixelValue, ImageInfo, ViewId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Interpolation {
+    #[default]
+    Nearest,
+    Bilinear,
+}
+
+impl Interpolation {
+    pub(crate) const ALL: [Interpolation; 2] = [Interpolation::Nearest, Interpolation::Bilinear];
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Interpolation::Nearest => "Nearest",
+            Interpolation::Bilinear => "Bilinear",
+        }
+    }
+}
+
+/// A segment in image pixel coordinates. Pixel (x, y) covers [x, x + 1) × [y, y + 1).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Line {
+    pub start: Vec2,
+    pub end: Vec2,
+}
+
+impl Line {
+    pub(crate) fn length(&self) -> f32 {
+        self.start.distance(self.end)
+    }
+
+    pub(crate) fn point_at(&self, t: f32) -> Vec2 {
+        self.start.lerp(self.end, t)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ProfileSample {
+    /// Distance from the start of the line, in pixels.
+    pub distance: f32,
+    pub position: Vec2,
+    pub values: Vec4,
+}
+
+fn pixel_rgba(info: &ImageInfo, bytes: &[u8], x: u32, y: u32) -> Vec4 {
+    Vec4::from(PixelValue::from_image_info(info, bytes, &UVec2::new(x, y)).as_rgba_f32())
+}
+
+fn sample_nearest(info: &ImageInfo, bytes: &[u8], position: Vec2) -> Vec4 {
+    let x = (position.x.floor().max(0.0) as u32).min(info.width - 1);
+    let y = (position.y.floor().max(0.0) as u32).min(info.height - 1);
+    pixel_rgba(info, bytes, x, y)
+}
+
+fn sample_bilinear(info: &ImageInfo, bytes: &[u8], position: Vec2) -> Vec4 {
+    // interpolate between pixel centers
+    let max = Vec2::new((info.width - 1) as f32, (info.height - 1) as f32);
+    let p = (position - 0.5).clamp(Vec2::ZERO, max);
+    let p0 = p.floor();
+    let frac = p - p0;
+    let (x0, y0) = (p0.x as u32, p0.y as u32);
+    let (x1, y1) = ((x0 + 1).min(info.width - 1), (y0 + 1).min(info.height - 1));
+
+    let top = pixel_rgba(info, bytes, x0, y0).lerp(pixel_rgba(info, bytes, x1, y0), frac.x);
+    let bottom = pixel_rgba(info, bytes, x0, y1).lerp(pixel_rgba(info, bytes, x1, y1), frac.x);
+    top.lerp(bottom, frac.y)
+}
+
+/// Samples the image along the line, one sample per pixel of length.
+pub(crate) fn sample_line(
+    info: &ImageInfo,
+    bytes: &[u8],
+    line: &Line,
+    interpolation: Interpolation,
+) -> Vec<ProfileSample> {
+    if info.width == 0 || info.height == 0 {
+        return vec![];
+    }
+    let length = line.length();
+    let num_samples = length.ceil() as usize + 1;
+    (0..num_samples)
+        .map(|i| {
+            let t = if num_samples > 1 {
+                i as f32 / (num_samples - 1) as f32
+            } else {
+                0.0
+            };
+            let position = line.point_at(t);
+            let values = match interpolation {
+                Interpolation::Nearest => sample_nearest(info, bytes, position),
+                Interpolation::Bilinear => sample_bilinear(info, bytes, position),
+            };
+            ProfileSample {
+                distance: t * length,
+                position,
+                values,
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ViewLineProfile {
+    pub line: Option<Line>,
+    pub is_drawing: bool,
+    /// Position of the plot cursor along the line, in [0, 1].
+    pub cursor: Option<f32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LineProfiles {
+    pub enabled: bool,
+    pub interpolation: Interpolation,
+    views: HashMap<ViewId, ViewLineProfile>,
+}
+
+impl LineProfiles {
+    pub(crate) fn new() -> Self {
+        Self {
+            enabled: false,
+            interpolation: Interpolation::default(),
+            views: all_views()
+                .into_iter()
+                .map(|v| (v, ViewLineProfile::default()))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn get(&self, view_id: ViewId) -> &ViewLineProfile {
+        self.views.get(&view_id).unwrap()
+    }
+
+    pub(crate) fn get_mut(&mut self, view_id: ViewId) -> &mut ViewLineProfile {
+        self.views.get_mut(&view_id).unwrap()
+    }
+
+    /// The line to draw on the view, if the tool is enabled.
+    pub(crate) fn visible_line(&self, view_id: ViewId) -> Option<(Line, Option<f32>)> {
+        if !self.enabled {
+            return None;
+        }
+        let profile = self.get(view_id);
+        profile.line.map(|line| (line, profile.cursor))
+    }
+}
+
+impl Default for LineProfiles {
+    fn default() -> Self {
+        Self::new()
+    }
+}
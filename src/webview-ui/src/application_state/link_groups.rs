@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use crate::common::ViewableObjectId;
+
+/// Groups of images that share their drawing options: changing the coloring, invert or clip
+/// of one image of a group changes it for every image of the group.
+#[derive(Debug, Default)]
+pub(crate) struct LinkGroups {
+    groups: HashMap<ViewableObjectId, u32>,
+    next_group: u32,
+}
+
+impl LinkGroups {
+    pub(crate) fn group_of(&self, image_id: &ViewableObjectId) -> Option<u32> {
+        self.groups.get(image_id).copied()
+    }
+
+    /// The other images of the group of the image.
+    pub(crate) fn linked_images(&self, image_id: &ViewableObjectId) -> Vec<ViewableObjectId> {
+        let Some(group) = self.group_of(image_id) else {
+            return Vec::new();
+        };
+        self.groups
+            .iter()
+            .filter(|(id, g)| **g == group && *id != image_id)
+            .map(|(id, _)| id.clone())
+            .collect()
+    }
+
+    /// Number of images in the group of the image, 0 if it is not linked.
+    pub(crate) fn group_size(&self, image_id: &ViewableObjectId) -> usize {
+        match self.group_of(image_id) {
+            Some(group) => self.groups.values().filter(|g| **g == group).count(),
+            None => 0,
+        }
+    }
+
+    /// Adds `image_id` to the group of `other`, creating the group if `other` is not linked yet.
+    pub(crate) fn link(&mut self, image_id: ViewableObjectId, other: ViewableObjectId) {
+        if image_id == other {
+            return;
+        }
+        self.unlink(&image_id);
+        let group = match self.group_of(&other) {
+            Some(group) => group,
+            None => {
+                let group = self.next_group;
+                self.next_group += 1;
+                self.groups.insert(other, group);
+                group
+            }
+        };
+        self.groups.insert(image_id, group);
+    }
+
+    /// Removes the image from its group. A group left with a single image is dissolved.
+    pub(crate) fn unlink(&mut self, image_id: &ViewableObjectId) {
+        let Some(group) = self.groups.remove(image_id) else {
+            return;
+        };
+        let remaining = self
+            .groups
+            .iter()
+            .filter(|(_, g)| **g == group)
+            .map(|(id, _)| id.clone())
+            .collect::<Vec<_>>();
+        if let [last] = remaining.as_slice() {
+            self.groups.remove(last);
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::{collections::HashSet, rc::Rc};
+
+use wasm_bindgen::{prelude::Closure, JsValue};
+use yewdux::{Dispatch, Listener};
+
+use crate::{
+    bindings::lodash,
+    common::{constants, CurrentlyViewing, ViewableObjectId},
+};
+
+use super::app_state::AppState;
+
+/// Batch items shown by the visible views, with their overlays, and the items about to be shown
+/// by the views that are playing.
+fn displayed_items(state: &AppState) -> HashSet<(ViewableObjectId, u32)> {
+    let mut items = HashSet::new();
+    for view_id in state.image_views.borrow().visible_views() {
+        let Some(cv) = state.image_views.borrow().get_currently_viewing(view_id) else {
+            continue;
+        };
+        let mut batch_items = vec![0];
+        if let CurrentlyViewing::BatchItem(image_id) = &cv {
+            if let Some((current_index, batch_size)) = state.batch_item_position(image_id) {
+                batch_items.push(current_index);
+                if state.playback.borrow().get(view_id).playing {
+                    batch_items.extend(
+                        (1..=constants::PLAYBACK_PREFETCH_AHEAD)
+                            .map(|offset| (current_index + offset) % batch_size.max(1)),
+                    );
+                }
+            }
+        }
+
+        let overlay_id = state
+            .overlays
+            .borrow()
+            .get_image_overlay(view_id, cv.id())
+            .map(|overlay| overlay.id.clone());
+        for image_id in std::iter::once(cv.id().clone()).chain(overlay_id) {
+            items.extend(
+                batch_items
+                    .iter()
+                    .map(|batch_item| (image_id.clone(), *batch_item)),
+            );
+        }
+    }
+    items
+}
+
+/// Unloads the least recently used images when the loaded images and the value history take
+/// more memory than the configured budget. The oldest snapshots of the history are dropped first.
+/// Pinned images, snapshots being viewed and what the visible views show are kept.
+pub(crate) struct MemoryBudgetKeeper {
+    debounced_enforce_budget: lodash::Debounced,
+}
+
+impl Default for MemoryBudgetKeeper {
+    fn default() -> Self {
+        Self {
+            debounced_enforce_budget: lodash::debounce_closure(
+                Closure::wrap(Box::new(move |_: JsValue| Self::enforce_budget())),
+                constants::TIMES.memory_budget_debounce,
+                lodash::DebouncedOptionsBuilder::default()
+                    .max_wait(constants::TIMES.memory_budget_max_wait)
+                    .build()
+                    .unwrap(),
+            ),
+        }
+    }
+}
+
+impl MemoryBudgetKeeper {
+    /// Snapshots cannot be fetched again, but the oldest steps are the least likely to be looked
+    /// at, and an unbounded history would take all the budget.
+    fn drop_oldest_snapshots(dispatch: &Dispatch<AppState>, budget: usize) {
+        let state = dispatch.get();
+        let excess = state.memory_usage().total().saturating_sub(budget);
+        if excess == 0 {
+            return;
+        }
+        let cached = state.image_cache.borrow().texture_ids();
+        let to_drop = state
+            .value_history
+            .borrow()
+            .snapshots_to_drop(excess, &cached);
+        if to_drop.is_empty() {
+            return;
+        }
+
+        log::debug!("MemoryBudgetKeeper: dropping snapshots {:?}", to_drop);
+        dispatch.reduce_mut(|state| {
+            state.value_history.borrow_mut().drop_snapshots(&to_drop);
+        });
+    }
+
+    fn enforce_budget() {
+        let dispatch = Dispatch::<AppState>::global();
+        let budget = dispatch.get().configuration.memory_budget_bytes();
+        Self::drop_oldest_snapshots(&dispatch, budget);
+        let state = dispatch.get();
+
+        let displayed = displayed_items(&state);
+        let image_cache = state.image_cache.borrow();
+        for (image_id, batch_item) in &displayed {
+            image_cache.touch(image_id, *batch_item);
+        }
+
+        // what the kept snapshots take is not available to the loaded images
+        let history_usage = state
+            .value_history
+            .borrow()
+            .memory_usage(&image_cache.texture_ids())
+            .total();
+        let to_evict = image_cache.items_to_evict(
+            budget.saturating_sub(history_usage),
+            |image_id, batch_item| {
+                state.images.borrow().is_pinned(image_id)
+                    || state.value_history.borrow().is_viewing_snapshot(image_id)
+                    || displayed.contains(&(image_id.clone(), batch_item))
+            },
+        );
+        drop(image_cache);
+        if to_evict.is_empty() {
+            return;
+        }
+
+        log::debug!(
+            "MemoryBudgetKeeper: evicting {:?} to fit in {} MB",
+            to_evict,
+            state.configuration.memory_budget_mb
+        );
+        dispatch.reduce_mut(|state| {
+            let mut image_cache = state.image_cache.borrow_mut();
+            for (image_id, batch_item) in &to_evict {
+                if image_cache.remove_batch_item(image_id, *batch_item) {
+                    state.thumbnails.borrow_mut().remove(image_id);
+                }
+            }
+        });
+    }
+}
+
+impl Listener for MemoryBudgetKeeper {
+    type Store = AppState;
+
+    fn on_change(&self, _cx: &yewdux::Context, _state: Rc<Self::Store>) {
+        self.debounced_enforce_budget
+            .call1(&JsValue::NULL, &JsValue::UNDEFINED)
+            .expect("debounced_enforce_budget call failed");
+    }
+}
@@ -1,6 +1,16 @@
+pub(crate) mod annotations;
 pub(crate) mod app_state;
 pub(crate) mod colormaps;
+pub(crate) mod image_errors;
+pub(crate) mod image_list;
 pub(crate) mod images;
+pub(crate) mod line_profile;
+pub(crate) mod link_groups;
+pub(crate) mod memory_budget;
+pub(crate) mod playback;
 pub(crate) mod sessions;
+pub(crate) mod thumbnails;
+pub(crate) mod undo_history;
+pub(crate) mod value_history;
 pub(crate) mod views;
 pub(crate) mod vscode_data_fetcher;
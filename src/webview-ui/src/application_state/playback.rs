@@ -0,0 +1,69 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::common::{constants::all_views, ViewId};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Playback {
+    pub playing: bool,
+    pub fps: f64,
+    pub looping: bool,
+    /// Batch items that were already requested ahead of the playback position.
+    pub requested_items: HashSet<u32>,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Self {
+            playing: false,
+            fps: 10.0,
+            looping: true,
+            requested_items: HashSet::new(),
+        }
+    }
+}
+
+impl Playback {
+    pub(crate) const MIN_FPS: f64 = 0.5;
+    pub(crate) const MAX_FPS: f64 = 60.0;
+
+    pub(crate) fn interval_millis(&self) -> u32 {
+        (1000.0 / self.fps.clamp(Self::MIN_FPS, Self::MAX_FPS)) as u32
+    }
+}
+
+pub(crate) struct ViewsPlayback(HashMap<ViewId, Playback>);
+
+impl ViewsPlayback {
+    pub(crate) fn new() -> Self {
+        Self(
+            all_views()
+                .into_iter()
+                .map(|v| (v, Playback::default()))
+                .collect(),
+        )
+    }
+
+    pub(crate) fn get(&self, view_id: ViewId) -> &Playback {
+        self.0.get(&view_id).unwrap()
+    }
+
+    pub(crate) fn get_mut(&mut self, view_id: ViewId) -> &mut Playback {
+        self.0.get_mut(&view_id).unwrap()
+    }
+
+    pub(crate) fn is_any_playing(&self) -> bool {
+        self.0.values().any(|p| p.playing)
+    }
+
+    pub(crate) fn stop(&mut self, view_id: ViewId) {
+        let playback = self.get_mut(view_id);
+        playback.playing = false;
+        playback.requested_items.clear();
+    }
+}
+
+impl Default for ViewsPlayback {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,98 @@
+use std::{
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
+
+use crate::{
+    application_state::app_state::GlobalDrawingOptions,
+    coloring::{Coloring, DrawingOptions},
+    common::{SessionId, ViewableObjectId},
+};
+
+/// Width and height of the thumbnails, in pixels. Images are fitted inside.
+pub(crate) const THUMBNAIL_SIZE: u32 = 48;
+
+/// What a thumbnail was rendered from. The thumbnail is rendered again when it changes.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ThumbnailKey {
+    /// [`TextureImage::id`](crate::common::texture_image::TextureImage) of the rendered data.
+    pub texture_id: u32,
+    /// The thumbnail is first rendered with the range of the datatype.
+    pub minmax_pending: bool,
+    pub drawing_options: DrawingOptions,
+    pub colormap_name: Option<String>,
+}
+
+impl ThumbnailKey {
+    pub(crate) fn new(
+        texture_id: u32,
+        minmax_pending: bool,
+        drawing_options: DrawingOptions,
+        global_drawing_options: &GlobalDrawingOptions,
+    ) -> Self {
+        let colormap_name = match drawing_options.coloring {
+            Coloring::Heatmap => Some(global_drawing_options.heatmap_colormap_name.clone()),
+            Coloring::Segmentation | Coloring::Edges => {
+                Some(global_drawing_options.segmentation_colormap_name.clone())
+            }
+            _ => None,
+        };
+        Self {
+            texture_id,
+            minmax_pending,
+            drawing_options,
+            colormap_name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Thumbnail {
+    pub key: ThumbnailKey,
+    /// RGBA pixels of a `THUMBNAIL_SIZE` square, top row first.
+    pub pixels: Rc<Vec<u8>>,
+}
+
+/// Thumbnails of the image list. Only images whose list item is visible get one.
+#[derive(Debug, Default)]
+pub(crate) struct Thumbnails {
+    thumbnails: HashMap<ViewableObjectId, Thumbnail>,
+    visible: HashSet<ViewableObjectId>,
+}
+
+impl Thumbnails {
+    pub(crate) fn get(&self, image_id: &ViewableObjectId) -> Option<&Thumbnail> {
+        self.thumbnails.get(image_id)
+    }
+
+    pub(crate) fn insert(&mut self, image_id: ViewableObjectId, thumbnail: Thumbnail) {
+        self.thumbnails.insert(image_id, thumbnail);
+    }
+
+    pub(crate) fn remove(&mut self, image_id: &ViewableObjectId) {
+        self.thumbnails.remove(image_id);
+    }
+
+    pub(crate) fn clear_session(&mut self, session_id: &SessionId) {
+        self.thumbnails
+            .retain(|image_id, _| image_id.session_id() != session_id);
+    }
+
+    pub(crate) fn set_visible(&mut self, image_id: ViewableObjectId, visible: bool) {
+        if visible {
+            self.visible.insert(image_id);
+        } else {
+            self.visible.remove(&image_id);
+        }
+    }
+
+    pub(crate) fn visible(&self) -> impl Iterator<Item = &ViewableObjectId> {
+        self.visible.iter()
+    }
+
+    pub(crate) fn is_up_to_date(&self, image_id: &ViewableObjectId, key: &ThumbnailKey) -> bool {
+        self.thumbnails
+            .get(image_id)
+            .is_some_and(|thumbnail| thumbnail.key == *key)
+    }
+}
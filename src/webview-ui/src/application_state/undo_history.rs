@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+
+use crate::{
+    coloring::DrawingOptions,
+    common::{camera::Camera, ViewId, ViewableObjectId},
+};
+
+/// Maximum number of changes that can be undone for an image.
+const MAX_UNDO_STEPS: usize = 100;
+/// Changes of the same kind closer than this, in milliseconds, are undone together, so a drag
+/// of the clip handles or a wheel zoom is a single step.
+const MERGE_INTERVAL_MS: f64 = 500.0;
+
+/// The state of an image before a change, restored on undo.
+#[derive(Clone)]
+pub(crate) enum Snapshot {
+    DrawingOptions(DrawingOptions),
+    /// The camera of the view the image was shown in.
+    Camera(ViewId, Camera),
+}
+
+impl Snapshot {
+    fn same_kind(&self, other: &Snapshot) -> bool {
+        match (self, other) {
+            (Snapshot::DrawingOptions(_), Snapshot::DrawingOptions(_)) => true,
+            (Snapshot::Camera(a, _), Snapshot::Camera(b, _)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+struct UndoEntry {
+    snapshot: Snapshot,
+    /// When the last change merged into this entry happened.
+    time: f64,
+}
+
+#[derive(Default)]
+struct ImageHistory {
+    undo: Vec<UndoEntry>,
+    redo: Vec<Snapshot>,
+}
+
+/// Undo and redo stacks of drawing options and camera changes, for each image.
+#[derive(Default)]
+pub(crate) struct UndoHistory {
+    histories: HashMap<ViewableObjectId, ImageHistory>,
+}
+
+impl UndoHistory {
+    /// Records the state of the image before a change. Clears what could be redone.
+    pub(crate) fn record(&mut self, image_id: ViewableObjectId, snapshot: Snapshot) {
+        let now = js_sys::Date::now();
+        let history = self.histories.entry(image_id).or_default();
+        history.redo.clear();
+        if let Some(last) = history.undo.last_mut() {
+            if last.snapshot.same_kind(&snapshot) && now - last.time < MERGE_INTERVAL_MS {
+                last.time = now;
+                return;
+            }
+        }
+        history.undo.push(UndoEntry {
+            snapshot,
+            time: now,
+        });
+        if history.undo.len() > MAX_UNDO_STEPS {
+            history.undo.remove(0);
+        }
+    }
+
+    /// Pops the last change of the image. `current` gives the state the change led to, which
+    /// is kept for redo. Returns the state to restore.
+    pub(crate) fn undo(
+        &mut self,
+        image_id: &ViewableObjectId,
+        current: impl FnOnce(&Snapshot) -> Snapshot,
+    ) -> Option<Snapshot> {
+        let history = self.histories.get_mut(image_id)?;
+        let entry = history.undo.pop()?;
+        history.redo.push(current(&entry.snapshot));
+        Some(entry.snapshot)
+    }
+
+    /// Pops the last undone change of the image. `current` gives the state before it is redone,
+    /// which is kept for undo. Returns the state to restore.
+    pub(crate) fn redo(
+        &mut self,
+        image_id: &ViewableObjectId,
+        current: impl FnOnce(&Snapshot) -> Snapshot,
+    ) -> Option<Snapshot> {
+        let history = self.histories.get_mut(image_id)?;
+        let snapshot = history.redo.pop()?;
+        history.undo.push(UndoEntry {
+            snapshot: current(&snapshot),
+            // never merged with the next change
+            time: f64::NEG_INFINITY,
+        });
+        Some(snapshot)
+    }
+}
@@ -0,0 +1,201 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use yewdux::mrc::Mrc;
+
+use crate::common::{
+    texture_image::{MemoryUsage, TextureImage},
+    SessionId, ViewableObjectId,
+};
+
+/// Maximum number of snapshots kept for each expression.
+pub(crate) const MAX_SNAPSHOTS_PER_EXPRESSION: usize = 16;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Step {
+    pub index: u32,
+    pub frame_id: Option<u32>,
+}
+
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.frame_id {
+            Some(frame_id) => write!(f, "step {} (frame {})", self.index, frame_id),
+            None => write!(f, "step {}", self.index),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct Snapshot {
+    pub step: Step,
+    pub texture: Mrc<TextureImage>,
+}
+
+/// Data of expressions from previous debugger steps.
+///
+/// A step ends when the extension reports a new frame for the session. The images that were
+/// loaded during the step are archived as its snapshots.
+#[derive(Default)]
+pub(crate) struct ValueHistory {
+    current: HashMap<SessionId, Step>,
+    snapshots: HashMap<ViewableObjectId, VecDeque<Snapshot>>,
+    /// Images that currently display a snapshot, with the live data they replaced.
+    viewing: HashMap<ViewableObjectId, (u32, Option<Mrc<TextureImage>>)>,
+}
+
+impl ValueHistory {
+    pub(crate) fn current_step(&self, session_id: &SessionId) -> Step {
+        self.current.get(session_id).copied().unwrap_or_default()
+    }
+
+    /// Archives the given images under the current step of the session, and starts a new step
+    /// for the given frame.
+    pub(crate) fn archive(
+        &mut self,
+        session_id: &SessionId,
+        next_frame_id: u32,
+        images: impl IntoIterator<Item = (ViewableObjectId, Mrc<TextureImage>)>,
+    ) {
+        let step = self.current_step(session_id);
+        for (image_id, texture) in images {
+            let snapshots = self.snapshots.entry(image_id).or_default();
+            snapshots.push_back(Snapshot { step, texture });
+            while snapshots.len() > MAX_SNAPSHOTS_PER_EXPRESSION {
+                snapshots.pop_front();
+            }
+        }
+        self.current.insert(
+            session_id.clone(),
+            Step {
+                index: step.index + 1,
+                frame_id: Some(next_frame_id),
+            },
+        );
+    }
+
+    pub(crate) fn set_frame(&mut self, session_id: &SessionId, frame_id: u32) {
+        self.current.entry(session_id.clone()).or_default().frame_id = Some(frame_id);
+    }
+
+    pub(crate) fn snapshots(
+        &self,
+        image_id: &ViewableObjectId,
+    ) -> impl DoubleEndedIterator<Item = &Snapshot> {
+        self.snapshots.get(image_id).into_iter().flatten()
+    }
+
+    /// The data held by the snapshots, and the live data replaced by the snapshots being viewed.
+    pub(crate) fn textures(&self) -> impl Iterator<Item = &Mrc<TextureImage>> {
+        self.snapshots
+            .values()
+            .flatten()
+            .map(|snapshot| &snapshot.texture)
+            .chain(self.viewing.values().filter_map(|(_, live)| live.as_ref()))
+    }
+
+    /// Memory of the data held by the history, without the textures whose id is in `counted`.
+    pub(crate) fn memory_usage(&self, counted: &HashSet<u32>) -> MemoryUsage {
+        let mut seen = counted.clone();
+        self.textures()
+            .filter_map(|texture| {
+                let texture = texture.borrow();
+                seen.insert(texture.id).then(|| texture.memory_usage())
+            })
+            .sum()
+    }
+
+    /// The oldest snapshots to drop to free `bytes`, as image ids and step indices. Snapshots
+    /// being viewed are kept, and dropping data that is also in `counted` frees nothing.
+    pub(crate) fn snapshots_to_drop(
+        &self,
+        bytes: usize,
+        counted: &HashSet<u32>,
+    ) -> Vec<(ViewableObjectId, u32)> {
+        let mut candidates = self
+            .snapshots
+            .iter()
+            .flat_map(|(image_id, snapshots)| {
+                snapshots
+                    .iter()
+                    .filter(move |snapshot| {
+                        self.viewing_step(image_id) != Some(snapshot.step.index)
+                    })
+                    .map(move |snapshot| (image_id, snapshot))
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(_, snapshot)| snapshot.step.index);
+
+        let mut freed = 0;
+        let mut dropped = Vec::new();
+        for (image_id, snapshot) in candidates {
+            if freed >= bytes {
+                break;
+            }
+            let texture = snapshot.texture.borrow();
+            if !counted.contains(&texture.id) {
+                freed += texture.memory_usage().total();
+            }
+            dropped.push((image_id.clone(), snapshot.step.index));
+        }
+        dropped
+    }
+
+    pub(crate) fn drop_snapshots(&mut self, dropped: &[(ViewableObjectId, u32)]) {
+        for (image_id, step_index) in dropped {
+            if let Some(snapshots) = self.snapshots.get_mut(image_id) {
+                snapshots.retain(|snapshot| snapshot.step.index != *step_index);
+                if snapshots.is_empty() {
+                    self.snapshots.remove(image_id);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn get(&self, image_id: &ViewableObjectId, step_index: u32) -> Option<&Snapshot> {
+        self.snapshots(image_id)
+            .find(|s| s.step.index == step_index)
+    }
+
+    pub(crate) fn viewing_step(&self, image_id: &ViewableObjectId) -> Option<u32> {
+        self.viewing
+            .get(image_id)
+            .map(|(step_index, _)| *step_index)
+    }
+
+    pub(crate) fn is_viewing_snapshot(&self, image_id: &ViewableObjectId) -> bool {
+        self.viewing.contains_key(image_id)
+    }
+
+    /// The live data of an image that currently displays a snapshot.
+    pub(crate) fn live_data(&self, image_id: &ViewableObjectId) -> Option<Mrc<TextureImage>> {
+        self.viewing
+            .get(image_id)
+            .and_then(|(_, live)| live.clone())
+    }
+
+    /// Marks the image as displaying a snapshot. The live data is kept only the first time, since
+    /// later calls replace one snapshot with another.
+    pub(crate) fn start_viewing(
+        &mut self,
+        image_id: ViewableObjectId,
+        step_index: u32,
+        live: Option<Mrc<TextureImage>>,
+    ) {
+        self.viewing
+            .entry(image_id)
+            .and_modify(|(current_step, _)| *current_step = step_index)
+            .or_insert((step_index, live));
+    }
+
+    /// Returns the live data that was replaced by a snapshot, if the image was displaying one.
+    pub(crate) fn stop_viewing(
+        &mut self,
+        image_id: &ViewableObjectId,
+    ) -> Option<Option<Mrc<TextureImage>>> {
+        self.viewing.remove(image_id).map(|(_, live)| live)
+    }
+
+    pub(crate) fn stop_viewing_all(&mut self) {
+        self.viewing.clear();
+    }
+}
@@ -7,7 +7,7 @@ use yewdux::{Dispatch, Listener};
 use anyhow::Result;
 
 use crate::{
-    application_state::images::{DrawingContext, ImageAvailability}, bindings::lodash, common::{constants, ViewableObjectId},
+    application_state::images::{DrawingContext, ImageAvailability}, bindings::lodash, common::{constants, CurrentlyViewing, ViewableObjectId},
     configurations::AutoUpdateImages, vscode::vscode_requests::VSCodeRequests,
 };
 
@@ -15,6 +15,8 @@ use super::app_state::AppState;
 
 pub(crate) struct ImagesFetcher {
     debounced_fetch_missing_images: lodash::Debounced,
+    debounced_prefetch_playback_items: lodash::Debounced,
+    debounced_prefetch_neighbors: lodash::Debounced,
 }
 
 impl Default for ImagesFetcher {
@@ -28,6 +30,25 @@ impl Default for ImagesFetcher {
                 constants::TIMES.data_fetcher_debounce,
                 Default::default(),
             ),
+            debounced_prefetch_playback_items: lodash::debounce_closure(
+                Closure::wrap(Box::new(move |_: JsValue| {
+                    let _ = Self::prefetch_playback_items()
+                        .map_err(|e| log::error!("ImagesFetcher: {}", e));
+                })),
+                constants::TIMES.playback_prefetch_debounce,
+                lodash::DebouncedOptionsBuilder::default()
+                    .max_wait(constants::TIMES.playback_prefetch_max_wait)
+                    .build()
+                    .unwrap(),
+            ),
+            debounced_prefetch_neighbors: lodash::debounce_closure(
+                Closure::wrap(Box::new(move |_: JsValue| {
+                    let _ =
+                        Self::prefetch_neighbors().map_err(|e| log::error!("ImagesFetcher: {}", e));
+                })),
+                constants::TIMES.prefetch_debounce,
+                Default::default(),
+            ),
         }
     }
 }
@@ -46,6 +67,10 @@ impl ImagesFetcher {
             .collect::<Vec<_>>();
 
         for cv in currently_viewing_objects {
+            if state.value_history.borrow().is_viewing_snapshot(cv.id()) {
+                // snapshots are never updated
+                continue;
+            }
             match cv {
                 crate::common::CurrentlyViewing::Image(image_id) => {
                     log::debug!(
@@ -101,6 +126,8 @@ impl ImagesFetcher {
                                 expression,
                                 current_index,
                                 None,
+                                None,
+                                None,
                             );
                         }
                     } else if let ImageAvailability::Pending(Some(image))
@@ -115,7 +142,7 @@ impl ImagesFetcher {
                             .unwrap_or_default();
 
                         if let Some(item) = current_drawing_options.batch_item {
-                            let has_item = image.borrow().textures.contains_key(&item);
+                            let has_item = image.borrow().has_batch_item(item);
                             if has_item {
                                 log::debug!(
                                     "ImagesFetcher::on_change: batch item {} already in cache. changing to ImageAvailability::Available",
@@ -143,8 +170,7 @@ impl ImagesFetcher {
                                     .minimal()
                                     .expression
                                     .clone();
-                                let currently_holding =
-                                    image.borrow().textures.keys().copied().collect_vec();
+                                let currently_holding = image.borrow().batch_items().collect_vec();
                                 log::debug!(
                                     "ImagesFetcher::on_change: {:?}[{}] not in cache (has {:?})",
                                     image_id,
@@ -156,6 +182,8 @@ impl ImagesFetcher {
                                     expression,
                                     item,
                                     Some(currently_holding),
+                                    None,
+                                    None,
                                 );
                             }
                         }
@@ -191,6 +219,236 @@ impl ImagesFetcher {
         Ok(())
     }
 
+    /// Requests the batch items that are about to be shown by views that are playing.
+    fn prefetch_playback_items() -> Result<()> {
+        let dispatch = Dispatch::<AppState>::global();
+        let state = dispatch.get();
+
+        let visible_views = state.image_views.borrow().visible_views();
+        for view_id in visible_views {
+            let playback = state.playback.borrow().get(view_id).clone();
+            if !playback.playing {
+                continue;
+            }
+            let Some(CurrentlyViewing::BatchItem(image_id)) =
+                state.image_views.borrow().get_currently_viewing(view_id)
+            else {
+                continue;
+            };
+            if state.value_history.borrow().is_viewing_snapshot(&image_id) {
+                continue;
+            }
+            // the first item is fetched by fetch_missing_images
+            let image = match state.image_cache.borrow().get(&image_id) {
+                ImageAvailability::Available(image) | ImageAvailability::Pending(Some(image)) => {
+                    image
+                }
+                _ => continue,
+            };
+            let Some((current_index, batch_size)) = state.batch_item_position(&image_id) else {
+                continue;
+            };
+
+            let ahead = constants::PLAYBACK_PREFETCH_AHEAD.min(batch_size.saturating_sub(1));
+            let missing_item = (1..=ahead)
+                .map(|offset| (current_index + offset) % batch_size)
+                .filter(|index| playback.looping || *index > current_index)
+                .find(|index| {
+                    !image.borrow().has_batch_item(*index)
+                        && !playback.requested_items.contains(index)
+                });
+
+            if let Some(item) = missing_item {
+                let Some(image_info) = state.images.borrow().get(&image_id).cloned() else {
+                    continue;
+                };
+                let currently_holding = image.borrow().batch_items().collect_vec();
+                log::debug!(
+                    "ImagesFetcher::prefetch_playback_items: {:?}[{}..={}]",
+                    image_id,
+                    item,
+                    item + ahead
+                );
+                VSCodeRequests::request_batch_item_data(
+                    image_id.clone(),
+                    image_info.minimal().expression.clone(),
+                    item,
+                    Some(currently_holding),
+                    Some(ahead),
+                    Some(batch_size),
+                );
+                let requested = item..=(item + ahead).min(batch_size - 1);
+                dispatch.reduce_mut(|s| {
+                    s.playback
+                        .borrow_mut()
+                        .get_mut(view_id)
+                        .requested_items
+                        .extend(requested);
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Requests the images around the shown ones in the image list, and the batch items around
+    /// the shown batch items. Waits until the shown images are loaded, so they are requested
+    /// first.
+    fn prefetch_neighbors() -> Result<()> {
+        let dispatch = Dispatch::<AppState>::global();
+        let state = dispatch.get();
+        let prefetch = state.configuration.prefetch.clone();
+
+        let currently_viewing_objects = state
+            .image_views
+            .borrow()
+            .visible_views()
+            .iter()
+            .filter_map(|view_id| state.image_views.borrow().get_currently_viewing(*view_id))
+            .collect::<Vec<_>>();
+        let any_pending = currently_viewing_objects.iter().any(|cv| {
+            matches!(
+                state.image_cache.borrow().get(cv.id()),
+                ImageAvailability::Pending(_)
+            )
+        });
+        if any_pending {
+            return Ok(());
+        }
+        // prefetched data would be evicted right away
+        let budget = state.configuration.memory_budget_bytes();
+        if state.memory_usage().total() >= budget {
+            return Ok(());
+        }
+
+        for cv in &currently_viewing_objects {
+            if let CurrentlyViewing::BatchItem(image_id) = cv {
+                if !state.value_history.borrow().is_viewing_snapshot(image_id) {
+                    Self::prefetch_batch_items(&state, image_id, prefetch.batch_items);
+                }
+            }
+        }
+
+        let neighbors = {
+            let images = state.images.borrow();
+            let mut neighbors = Vec::new();
+            for cv in &currently_viewing_objects {
+                let (mut next, mut previous) = (Some(cv.id()), Some(cv.id()));
+                for _ in 0..prefetch.images {
+                    next = next.and_then(|id| images.next_image_id(id));
+                    previous = previous.and_then(|id| images.previous_image_id(id));
+                    neighbors.extend(next.into_iter().chain(previous).cloned());
+                }
+            }
+            neighbors
+        };
+
+        for image_id in neighbors.into_iter().unique() {
+            if currently_viewing_objects
+                .iter()
+                .any(|cv| cv.id() == &image_id)
+                || state.image_cache.borrow().get(&image_id) != ImageAvailability::NotAvailable
+            {
+                continue;
+            }
+            let Some(image) = state.images.borrow().get(&image_id).cloned() else {
+                continue;
+            };
+            let batch_item = image.minimal().is_batched.then(|| {
+                state
+                    .drawing_options
+                    .borrow()
+                    .get(&image_id, &DrawingContext::BaseImage)
+                    .and_then(|d| d.batch_item)
+                    .unwrap_or(0)
+            });
+            if state
+                .image_cache
+                .borrow()
+                .is_prefetched(&image_id, batch_item.unwrap_or(0))
+            {
+                continue;
+            }
+
+            log::debug!("ImagesFetcher::prefetch_neighbors: {:?}", image_id);
+            let expression = image.minimal().expression.clone();
+            match batch_item {
+                Some(batch_item) => VSCodeRequests::request_batch_item_data(
+                    image_id.clone(),
+                    expression,
+                    batch_item,
+                    None,
+                    None,
+                    None,
+                ),
+                None => VSCodeRequests::request_image_data(image_id.clone(), expression),
+            };
+            state
+                .image_cache
+                .borrow()
+                .mark_prefetched(&image_id, batch_item.unwrap_or(0));
+            dispatch.reduce_mut(|s| {
+                s.image_cache.borrow_mut().set_pending(&image_id);
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Requests the missing batch items up to `around` items before and after the shown one, in
+    /// a single request.
+    fn prefetch_batch_items(state: &AppState, image_id: &ViewableObjectId, around: u32) {
+        if around == 0 {
+            return;
+        }
+        let ImageAvailability::Available(image) = state.image_cache.borrow().get(image_id) else {
+            return;
+        };
+        let Some((current_index, batch_size)) = state.batch_item_position(image_id) else {
+            return;
+        };
+
+        let image_cache = state.image_cache.borrow();
+        let last_index = (current_index + around).min(batch_size.saturating_sub(1));
+        let missing = (current_index.saturating_sub(around)..=last_index)
+            .filter(|index| {
+                !image.borrow().has_batch_item(*index)
+                    && !image_cache.is_prefetched(image_id, *index)
+            })
+            .collect_vec();
+        let (Some(&first), Some(&last)) = (missing.first(), missing.last()) else {
+            return;
+        };
+        let Some(expression) = state
+            .images
+            .borrow()
+            .get(image_id)
+            .map(|image| image.minimal().expression.clone())
+        else {
+            return;
+        };
+
+        log::debug!(
+            "ImagesFetcher::prefetch_batch_items: {:?}[{}..={}]",
+            image_id,
+            first,
+            last
+        );
+        // the items from `batch_item - 1` to `batch_item + prefetch_ahead` are sent
+        let currently_holding = image.borrow().batch_items().collect_vec();
+        VSCodeRequests::request_batch_item_data(
+            image_id.clone(),
+            expression,
+            first + 1,
+            Some(currently_holding),
+            Some(last.saturating_sub(first + 1)),
+            Some(batch_size),
+        );
+        for index in missing {
+            image_cache.mark_prefetched(image_id, index);
+        }
+    }
+
     pub(crate) fn force_fetch_missing_images() -> Result<()> {
         // Force fetch regardless of autoUpdate configuration
         Self::fetch_missing_images()
@@ -226,6 +484,15 @@ impl Listener for ImagesFetcher {
             self.debounced_fetch_missing_images
                 .call1(&JsValue::NULL, &JsValue::UNDEFINED)
                 .expect("debounced_fetch_missing_images call failed");
+            self.debounced_prefetch_neighbors
+                .call1(&JsValue::NULL, &JsValue::UNDEFINED)
+                .expect("debounced_prefetch_neighbors call failed");
+        }
+
+        if state.playback.borrow().is_any_playing() {
+            self.debounced_prefetch_playback_items
+                .call1(&JsValue::NULL, &JsValue::UNDEFINED)
+                .expect("debounced_prefetch_playback_items call failed");
         }
     }
 }
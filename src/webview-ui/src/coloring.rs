@@ -36,6 +36,10 @@ pub(crate) struct DrawingOptions {
     pub clip: Clip,
     pub zeros_as_transparent: bool,
     pub global_alpha: f32,
+    /// Stretch the values of a batch item with its own min and max, instead of those of all the
+    /// batch items.
+    #[serde(default)]
+    pub normalize_per_batch_item: bool,
 }
 
 impl Default for DrawingOptions {
@@ -49,6 +53,7 @@ impl Default for DrawingOptions {
             clip: Clip::default(),
             zeros_as_transparent: false,
             global_alpha: 1.0,
+            normalize_per_batch_item: false,
         }
     }
 }
@@ -145,7 +150,7 @@ const fn only_max_alpha(datatype: Datatype) -> Vec4 {
 fn stretch_values_matrix(
     image_info: &ImageInfo,
     image_computed_info: &ComputedInfo,
-    clip: &Clip,
+    drawing_options: &DrawingOptions,
 ) -> (Mat4, Vec4) {
     // x = MAX * (x - min) / (max - min) 
     // x = MAX * x / (max - min) - MAX * min / (max - min)
@@ -155,9 +160,14 @@ fn stretch_values_matrix(
     let channels = image_info.channels;
     let dt_max = max_by_datatype(datatype);
 
+    let clip = &drawing_options.clip;
+    let (min, max) = image_computed_info.range(
+        drawing_options.batch_item.unwrap_or(0),
+        drawing_options.normalize_per_batch_item,
+    );
     let calc_normalizer = |c| {
-        let min = clip.min.unwrap_or(*image_computed_info.min.get::<f32>(c));
-        let max = clip.max.unwrap_or(*image_computed_info.max.get::<f32>(c));
+        let min = clip.min.unwrap_or(*min.get::<f32>(c));
+        let max = clip.max.unwrap_or(*max.get::<f32>(c));
         let denom = max - min;
 
         (dt_max / denom, -dt_max * min / denom)
@@ -312,7 +322,7 @@ pub(crate) fn calculate_color_matrix(
 
     let heatmap: bool = matches!(drawing_options.coloring, Coloring::Heatmap);
     let (modify_value_mult, modify_value_add) = if drawing_options.high_contrast || heatmap  {
-        stretch_values_matrix(image_info, image_computed_info, &drawing_options.clip)
+        stretch_values_matrix(image_info, image_computed_info, drawing_options)
     } else {
         (modify_value_mult, modify_value_add)
     };
@@ -333,10 +343,10 @@ pub(crate) fn calculate_color_matrix(
     }
 }
 
-pub(crate) fn calculate_pixel_color_from_colormap(
+/// The color of a pixel as drawn without a colormap, in the range [0, 1].
+pub(crate) fn calculate_pixel_color(
     pixel_value: &PixelValue,
     coloring_factors: &ColoringFactors,
-    colormap: &colormap::ColorMap,
     drawing_options: &DrawingOptions,
 ) -> Vec4 {
     let mut rgba = Vec4::from(pixel_value.as_rgba_f32());
@@ -347,6 +357,33 @@ pub(crate) fn calculate_pixel_color_from_colormap(
         rgba.y = 1.0 - rgba.y;
         rgba.z = 1.0 - rgba.z;
     }
+    rgba
+}
+
+/// The normalized value each channel of a pixel is displayed with, before inversion and colormaps.
+pub(crate) fn calculate_normalized_channel_values(
+    pixel_value: &PixelValue,
+    coloring_factors: &ColoringFactors,
+) -> Vec<f32> {
+    let rgba = Vec4::from(pixel_value.as_rgba_f32());
+    let rgba = coloring_factors.color_multiplier * (rgba / coloring_factors.normalization_factor)
+        + coloring_factors.color_addition;
+    match pixel_value.num_channels {
+        Channels::One => vec![rgba.x],
+        // the second channel is drawn as alpha, see RG_TO_RED_ALPHA
+        Channels::Two => vec![rgba.x, rgba.w],
+        Channels::Three => vec![rgba.x, rgba.y, rgba.z],
+        Channels::Four => rgba.to_array().to_vec(),
+    }
+}
+
+pub(crate) fn calculate_pixel_color_from_colormap(
+    pixel_value: &PixelValue,
+    coloring_factors: &ColoringFactors,
+    colormap: &colormap::ColorMap,
+    drawing_options: &DrawingOptions,
+) -> Vec4 {
+    let rgba = calculate_pixel_color(pixel_value, coloring_factors, drawing_options);
     let colormap_sampling_value = rgba.x.clamp(0.0, 1.0);
     let colormap_index =
         (colormap_sampling_value * (colormap.map.len() - 1) as f32).round() as usize;
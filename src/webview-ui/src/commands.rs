@@ -0,0 +1,488 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use yewdux::Dispatch;
+
+use crate::{
+    application_state::{
+        app_state::{
+            AnnotationAction, AppState, LineProfileAction, OverlayAction, PlaybackAction,
+            StoreAction, UiAction, UndoAction, UpdateDrawingOptions,
+        },
+        images::{DrawingContext, ImageAvailability},
+    },
+    coloring::Coloring,
+    common::{ViewId, ViewableObjectId},
+    components::display_options::features::{list_features, Feature},
+};
+
+const ZOOM_STEP: f32 = 1.25;
+
+/// An action of the viewer that can be bound to a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum Command {
+    NextImage,
+    PreviousImage,
+    NextBatchItem,
+    PreviousBatchItem,
+    TogglePlayback,
+    ZoomIn,
+    ZoomOut,
+    ResetCamera,
+    FitWidth,
+    ActualSize,
+    RotateClockwise,
+    RotateCounterclockwise,
+    FlipHorizontally,
+    FlipVertically,
+    ResetOrientation,
+    CycleColoring,
+    ToggleInvert,
+    ToggleHighContrast,
+    ResetDrawingOptions,
+    Undo,
+    Redo,
+    TogglePin,
+    ToggleOverlay,
+    TogglePixelInspector,
+    ToggleLineProfile,
+    FinishPolygon,
+    CancelDraft,
+    ShowShortcuts,
+}
+
+impl Command {
+    pub(crate) const ALL: [Command; 28] = [
+        Command::NextImage,
+        Command::PreviousImage,
+        Command::NextBatchItem,
+        Command::PreviousBatchItem,
+        Command::TogglePlayback,
+        Command::ZoomIn,
+        Command::ZoomOut,
+        Command::ResetCamera,
+        Command::FitWidth,
+        Command::ActualSize,
+        Command::RotateClockwise,
+        Command::RotateCounterclockwise,
+        Command::FlipHorizontally,
+        Command::FlipVertically,
+        Command::ResetOrientation,
+        Command::CycleColoring,
+        Command::ToggleInvert,
+        Command::ToggleHighContrast,
+        Command::ResetDrawingOptions,
+        Command::Undo,
+        Command::Redo,
+        Command::TogglePin,
+        Command::ToggleOverlay,
+        Command::TogglePixelInspector,
+        Command::ToggleLineProfile,
+        Command::FinishPolygon,
+        Command::CancelDraft,
+        Command::ShowShortcuts,
+    ];
+
+    /// The name of the command in the `svifpd.viewerUi.keyBindings` setting.
+    pub(crate) fn id(&self) -> &'static str {
+        match self {
+            Command::NextImage => "nextImage",
+            Command::PreviousImage => "previousImage",
+            Command::NextBatchItem => "nextBatchItem",
+            Command::PreviousBatchItem => "previousBatchItem",
+            Command::TogglePlayback => "togglePlayback",
+            Command::ZoomIn => "zoomIn",
+            Command::ZoomOut => "zoomOut",
+            Command::ResetCamera => "resetCamera",
+            Command::FitWidth => "fitWidth",
+            Command::ActualSize => "actualSize",
+            Command::RotateClockwise => "rotateClockwise",
+            Command::RotateCounterclockwise => "rotateCounterclockwise",
+            Command::FlipHorizontally => "flipHorizontally",
+            Command::FlipVertically => "flipVertically",
+            Command::ResetOrientation => "resetOrientation",
+            Command::CycleColoring => "cycleColoring",
+            Command::ToggleInvert => "toggleInvert",
+            Command::ToggleHighContrast => "toggleHighContrast",
+            Command::ResetDrawingOptions => "resetDrawingOptions",
+            Command::Undo => "undo",
+            Command::Redo => "redo",
+            Command::TogglePin => "togglePin",
+            Command::ToggleOverlay => "toggleOverlay",
+            Command::TogglePixelInspector => "togglePixelInspector",
+            Command::ToggleLineProfile => "toggleLineProfile",
+            Command::FinishPolygon => "finishPolygon",
+            Command::CancelDraft => "cancelDraft",
+            Command::ShowShortcuts => "showShortcuts",
+        }
+    }
+
+    pub(crate) fn from_id(id: &str) -> Option<Command> {
+        Command::ALL.into_iter().find(|command| command.id() == id)
+    }
+
+    pub(crate) fn description(&self) -> &'static str {
+        match self {
+            Command::NextImage => "Next image",
+            Command::PreviousImage => "Previous image",
+            Command::NextBatchItem => "Next batch item",
+            Command::PreviousBatchItem => "Previous batch item",
+            Command::TogglePlayback => "Play/pause batch",
+            Command::ZoomIn => "Zoom in",
+            Command::ZoomOut => "Zoom out",
+            Command::ResetCamera => "Fit to view (reset camera)",
+            Command::FitWidth => "Fit to width",
+            Command::ActualSize => "Actual size (1:1)",
+            Command::RotateClockwise => "Rotate 90° clockwise",
+            Command::RotateCounterclockwise => "Rotate 90° counterclockwise",
+            Command::FlipHorizontally => "Flip horizontally",
+            Command::FlipVertically => "Flip vertically",
+            Command::ResetOrientation => "Reset rotation and flips",
+            Command::CycleColoring => "Cycle coloring",
+            Command::ToggleInvert => "Toggle invert",
+            Command::ToggleHighContrast => "Toggle high contrast",
+            Command::ResetDrawingOptions => "Reset drawing options",
+            Command::Undo => "Undo drawing options or view change",
+            Command::Redo => "Redo drawing options or view change",
+            Command::TogglePin => "Pin/unpin image",
+            Command::ToggleOverlay => "Show/hide overlay",
+            Command::TogglePixelInspector => "Toggle pixel inspector",
+            Command::ToggleLineProfile => "Toggle line profile tool",
+            Command::FinishPolygon => "Finish polygon",
+            Command::CancelDraft => "Cancel annotation being drawn",
+            Command::ShowShortcuts => "Show keyboard shortcuts",
+        }
+    }
+
+    pub(crate) fn execute(self, view_id: ViewId) {
+        let dispatch = Dispatch::<AppState>::global();
+        let state = dispatch.get();
+        let cv = state.image_views.borrow().get_currently_viewing(view_id);
+        let image_id = cv.as_ref().map(|cv| cv.id().clone());
+
+        match self {
+            Command::NextImage => dispatch.apply(UiAction::Next(view_id)),
+            Command::PreviousImage => dispatch.apply(UiAction::Previous(view_id)),
+            Command::NextBatchItem => {
+                if let Some(cv) = cv {
+                    dispatch.apply(UiAction::ViewShiftScroll(view_id, cv, 1.0));
+                }
+            }
+            Command::PreviousBatchItem => {
+                if let Some(cv) = cv {
+                    dispatch.apply(UiAction::ViewShiftScroll(view_id, cv, -1.0));
+                }
+            }
+            Command::TogglePlayback => {
+                if state.playback.borrow().get(view_id).playing {
+                    dispatch.apply(PlaybackAction::Pause(view_id));
+                } else {
+                    dispatch.apply(PlaybackAction::Play(view_id));
+                }
+            }
+            Command::ZoomIn => dispatch.apply(UiAction::Zoom(view_id, ZOOM_STEP)),
+            Command::ZoomOut => dispatch.apply(UiAction::Zoom(view_id, 1.0 / ZOOM_STEP)),
+            Command::ResetCamera => dispatch.apply(UiAction::Home(view_id)),
+            Command::FitWidth => dispatch.apply(UiAction::FitWidth(view_id)),
+            Command::ActualSize => dispatch.apply(UiAction::ActualSize(view_id)),
+            Command::RotateClockwise => dispatch.apply(UiAction::Rotate(view_id, 1)),
+            Command::RotateCounterclockwise => dispatch.apply(UiAction::Rotate(view_id, -1)),
+            Command::FlipHorizontally => dispatch.apply(UiAction::FlipHorizontally(view_id)),
+            Command::FlipVertically => dispatch.apply(UiAction::FlipVertically(view_id)),
+            Command::ResetOrientation => dispatch.apply(UiAction::ResetOrientation(view_id)),
+            Command::CycleColoring => {
+                if let Some(image_id) = image_id {
+                    if let Some(coloring) = next_coloring(&state, &image_id) {
+                        dispatch.apply(StoreAction::UpdateDrawingOptions(
+                            image_id,
+                            DrawingContext::BaseImage,
+                            UpdateDrawingOptions::Coloring(coloring),
+                        ));
+                    }
+                }
+            }
+            Command::ToggleInvert | Command::ToggleHighContrast => {
+                if let Some(image_id) = image_id {
+                    let drawing_options = state
+                        .drawing_options
+                        .borrow()
+                        .get(&image_id, &DrawingContext::BaseImage)
+                        .cloned()
+                        .unwrap_or_default();
+                    let update = match self {
+                        Command::ToggleInvert => {
+                            UpdateDrawingOptions::Invert(!drawing_options.invert)
+                        }
+                        _ => UpdateDrawingOptions::HighContrast(!drawing_options.high_contrast),
+                    };
+                    dispatch.apply(StoreAction::UpdateDrawingOptions(
+                        image_id,
+                        DrawingContext::BaseImage,
+                        update,
+                    ));
+                }
+            }
+            Command::ResetDrawingOptions => {
+                if let Some(image_id) = image_id {
+                    dispatch.apply(StoreAction::UpdateDrawingOptions(
+                        image_id,
+                        DrawingContext::BaseImage,
+                        UpdateDrawingOptions::Reset,
+                    ));
+                }
+            }
+            Command::Undo => dispatch.apply(UndoAction::Undo(view_id)),
+            Command::Redo => dispatch.apply(UndoAction::Redo(view_id)),
+            Command::TogglePin => {
+                if let Some(image_id) = image_id {
+                    if state.images.borrow().is_pinned(&image_id) {
+                        dispatch.apply(UiAction::Unpin(image_id));
+                    } else {
+                        dispatch.apply(UiAction::Pin(image_id));
+                    }
+                }
+            }
+            Command::ToggleOverlay => {
+                if let Some(image_id) = image_id {
+                    let hidden = state
+                        .overlays
+                        .borrow()
+                        .get_image_overlay(view_id, &image_id)
+                        .map(|overlay| overlay.hidden);
+                    match hidden {
+                        Some(true) => dispatch.apply(OverlayAction::Show { view_id, image_id }),
+                        Some(false) => dispatch.apply(OverlayAction::Hide { view_id, image_id }),
+                        None => {}
+                    }
+                }
+            }
+            Command::TogglePixelInspector => dispatch.apply(UiAction::TogglePixelInspector),
+            Command::ToggleLineProfile => dispatch.apply(LineProfileAction::ToggleTool),
+            Command::FinishPolygon => dispatch.apply(AnnotationAction::FinishPolygon(view_id)),
+            Command::CancelDraft => dispatch.apply(AnnotationAction::CancelDraft(view_id)),
+            Command::ShowShortcuts => dispatch.apply(UiAction::ToggleShortcuts),
+        }
+    }
+}
+
+/// The coloring after the current one, among the colorings offered for the image.
+fn next_coloring(state: &AppState, image_id: &ViewableObjectId) -> Option<Coloring> {
+    let info = match state.image_cache.borrow().get(image_id) {
+        ImageAvailability::Available(image) => image.borrow().info.clone(),
+        _ => return None,
+    };
+    let features = list_features(&info);
+    let colorings = [
+        (Coloring::Default, None),
+        (Coloring::Grayscale, Some(Feature::Grayscale)),
+        (Coloring::SwapRgbBgr, Some(Feature::SwapRgbBgr)),
+        (Coloring::R, Some(Feature::R)),
+        (Coloring::G, Some(Feature::G)),
+        (Coloring::B, Some(Feature::B)),
+        (Coloring::Heatmap, Some(Feature::Heatmap)),
+        (Coloring::Segmentation, Some(Feature::Segmentation)),
+    ]
+    .into_iter()
+    .filter(|(_, feature)| feature.map_or(true, |f| features.contains(f)))
+    .map(|(coloring, _)| coloring)
+    .collect::<Vec<_>>();
+
+    let current = state
+        .drawing_options
+        .borrow()
+        .get(image_id, &DrawingContext::BaseImage)
+        .map_or(Coloring::Default, |d| d.coloring);
+    let next_index = colorings
+        .iter()
+        .position(|c| *c == current)
+        .map_or(0, |i| (i + 1) % colorings.len());
+    colorings.get(next_index).copied()
+}
+
+/// A key with modifiers, e.g. `shift+ArrowUp`. Keys are named like `KeyboardEvent.key`, with
+/// `Space` for the space bar.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct KeyBinding {
+    pub key: String,
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+}
+
+impl KeyBinding {
+    fn new(key: &str, shift: bool, ctrl: bool, alt: bool) -> Self {
+        // shift is already part of printable keys, e.g. `?` or `J`
+        let is_printable = key.chars().count() == 1;
+        Self {
+            key: key.to_string(),
+            shift: shift && !is_printable,
+            ctrl,
+            alt,
+        }
+    }
+
+    pub(crate) fn from_event(event: &web_sys::KeyboardEvent) -> Self {
+        Self::new(
+            &event.key(),
+            event.shift_key(),
+            event.ctrl_key() || event.meta_key(),
+            event.alt_key(),
+        )
+    }
+
+    pub(crate) fn parse(binding: &str) -> Option<Self> {
+        let binding = binding.trim();
+        let (modifiers, key) = if binding == "+" {
+            ("", "+")
+        } else if let Some(modifiers) = binding.strip_suffix("++") {
+            (modifiers, "+")
+        } else {
+            binding.rsplit_once('+').unwrap_or(("", binding))
+        };
+        let key = match key {
+            "" => return None,
+            "Space" | "space" => " ",
+            key => key,
+        };
+
+        let (mut shift, mut ctrl, mut alt) = (false, false, false);
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            match modifier.to_lowercase().as_str() {
+                "shift" => shift = true,
+                "ctrl" | "cmd" | "meta" => ctrl = true,
+                "alt" | "option" => alt = true,
+                _ => return None,
+            }
+        }
+        Some(Self::new(key, shift, ctrl, alt))
+    }
+}
+
+impl Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ctrl {
+            write!(f, "Ctrl+")?;
+        }
+        if self.alt {
+            write!(f, "Alt+")?;
+        }
+        if self.shift {
+            write!(f, "Shift+")?;
+        }
+        match self.key.as_str() {
+            " " => write!(f, "Space"),
+            key => write!(f, "{}", key),
+        }
+    }
+}
+
+/// The key bindings of the commands. A command can have several bindings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Keymap {
+    bindings: Vec<(KeyBinding, Command)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = [
+            ("ArrowDown", Command::NextImage),
+            ("ArrowUp", Command::PreviousImage),
+            ("shift+ArrowDown", Command::NextBatchItem),
+            ("shift+ArrowUp", Command::PreviousBatchItem),
+            ("Space", Command::TogglePlayback),
+            ("+", Command::ZoomIn),
+            ("=", Command::ZoomIn),
+            ("-", Command::ZoomOut),
+            ("Home", Command::ResetCamera),
+            ("w", Command::FitWidth),
+            ("1", Command::ActualSize),
+            ("]", Command::RotateClockwise),
+            ("[", Command::RotateCounterclockwise),
+            ("f", Command::FlipHorizontally),
+            ("v", Command::FlipVertically),
+            ("0", Command::ResetOrientation),
+            ("c", Command::CycleColoring),
+            ("i", Command::ToggleInvert),
+            ("h", Command::ToggleHighContrast),
+            ("r", Command::ResetDrawingOptions),
+            ("ctrl+z", Command::Undo),
+            ("ctrl+Z", Command::Redo),
+            ("ctrl+y", Command::Redo),
+            ("p", Command::TogglePin),
+            ("o", Command::ToggleOverlay),
+            ("l", Command::ToggleLineProfile),
+            ("Enter", Command::FinishPolygon),
+            ("Escape", Command::CancelDraft),
+            ("?", Command::ShowShortcuts),
+        ];
+        Self {
+            bindings: bindings
+                .into_iter()
+                .map(|(binding, command)| (KeyBinding::parse(binding).unwrap(), command))
+                .collect(),
+        }
+    }
+}
+
+impl Keymap {
+    /// The default keymap with the bindings of some commands replaced. An empty binding unbinds
+    /// the command. When overrides bind the same key, the command id that sorts last gets it.
+    pub(crate) fn with_overrides(overrides: &HashMap<String, String>) -> Self {
+        let mut keymap = Self::default();
+        let mut overridden = HashMap::<KeyBinding, &str>::new();
+        let mut overrides = overrides.iter().collect::<Vec<_>>();
+        overrides.sort();
+        for (command_id, binding) in overrides {
+            let Some(command) = Command::from_id(command_id) else {
+                log::warn!("Unknown command in key bindings: {}", command_id);
+                continue;
+            };
+            keymap.bindings.retain(|(_, c)| *c != command);
+            if binding.trim().is_empty() {
+                continue;
+            }
+            match KeyBinding::parse(binding) {
+                Some(binding) => {
+                    if let Some(other) = overridden.insert(binding.clone(), command_id) {
+                        log::warn!(
+                            "{} and {} are both bound to {}, {} gets it",
+                            other,
+                            command_id,
+                            binding,
+                            command_id
+                        );
+                    }
+                    // a key runs a single command
+                    keymap.bindings.retain(|(b, _)| *b != binding);
+                    keymap.bindings.push((binding, command));
+                }
+                None => log::warn!("Invalid key binding for {}: {}", command_id, binding),
+            }
+        }
+        keymap
+    }
+
+    pub(crate) fn command_for(&self, binding: &KeyBinding) -> Option<Command> {
+        self.bindings
+            .iter()
+            .find(|(b, _)| b == binding)
+            .map(|(_, command)| *command)
+    }
+
+    /// The bound commands with their bindings, in the order of [`Command::ALL`].
+    pub(crate) fn bound_commands(&self) -> Vec<(Command, Vec<&KeyBinding>)> {
+        Command::ALL
+            .into_iter()
+            .map(|command| {
+                let bindings = self
+                    .bindings
+                    .iter()
+                    .filter(|(_, c)| *c == command)
+                    .map(|(binding, _)| binding)
+                    .collect::<Vec<_>>();
+                (command, bindings)
+            })
+            .filter(|(_, bindings)| !bindings.is_empty())
+            .collect()
+    }
+}
@@ -1,13 +1,115 @@
 use std::{cell::RefCell, collections::HashMap, rc::Rc};
 
-use crate::{common::Size, math_utils};
+use crate::{common::Size, math_utils, rendering::constants::VIEW_SIZE};
 
-use super::{constants::all_views, types::ViewId};
+use super::{
+    constants::{all_views, MAX_PIXEL_SIZE_DEVICE},
+    types::ViewId,
+};
+
+/// The most zoomed out, the image then takes half of the view.
+pub(crate) const MIN_ZOOM: f32 = 0.5;
+
+/// How the image is turned in the view: rotated clockwise by `quarter_turns` × 90°, then
+/// mirrored left to right if `mirrored`. Every combination of 90° rotations and flips has
+/// exactly one such representation.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Orientation {
+    quarter_turns: u8,
+    mirrored: bool,
+}
+
+impl Orientation {
+    pub(crate) fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Whether the width and height of the image are swapped in the view.
+    pub(crate) fn is_transposed(&self) -> bool {
+        self.quarter_turns % 2 == 1
+    }
+
+    pub(crate) fn rotated_clockwise(&self, quarter_turns: i32) -> Self {
+        // mirroring reverses the direction of the rotation
+        let quarter_turns = if self.mirrored {
+            -quarter_turns
+        } else {
+            quarter_turns
+        };
+        Self {
+            quarter_turns: (self.quarter_turns as i32 + quarter_turns).rem_euclid(4) as u8,
+            mirrored: self.mirrored,
+        }
+    }
+
+    pub(crate) fn flipped_horizontally(&self) -> Self {
+        Self {
+            quarter_turns: self.quarter_turns,
+            mirrored: !self.mirrored,
+        }
+    }
+
+    pub(crate) fn flipped_vertically(&self) -> Self {
+        // a vertical flip is a horizontal flip after half a turn
+        self.rotated_clockwise(2).flipped_horizontally()
+    }
+
+    /// The size of the image as shown in the view.
+    pub(crate) fn displayed_size(&self, image_size: &Size) -> Size {
+        if self.is_transposed() {
+            Size {
+                width: image_size.height,
+                height: image_size.width,
+            }
+        } else {
+            *image_size
+        }
+    }
+
+    /// Maps view coordinates of the image to view coordinates of the turned image. Both fill
+    /// the view, which is square.
+    pub(crate) fn as_matrix(&self) -> glam::Mat3 {
+        let (sin, cos) = match self.quarter_turns {
+            0 => (0.0, 1.0),
+            1 => (1.0, 0.0),
+            2 => (0.0, -1.0),
+            _ => (-1.0, 0.0),
+        };
+        let rotation = glam::Mat3::from_cols(
+            glam::Vec3::new(cos, sin, 0.0),
+            glam::Vec3::new(-sin, cos, 0.0),
+            glam::Vec3::Z,
+        );
+        let mirror =
+            glam::Mat3::from_scale(glam::Vec2::new(if self.mirrored { -1.0 } else { 1.0 }, 1.0));
+        glam::Mat3::from_translation(half_view())
+            * mirror
+            * rotation
+            * glam::Mat3::from_translation(-half_view())
+    }
+
+    /// The pixel of the turned image that shows `pixel` of the image.
+    pub(crate) fn displayed_pixel(&self, pixel: glam::UVec2, image_size: &Size) -> glam::UVec2 {
+        let image_to_unit = glam::Vec2::new(
+            VIEW_SIZE.width / image_size.width,
+            VIEW_SIZE.height / image_size.height,
+        );
+        let displayed_size = self.displayed_size(image_size);
+        let unit_to_displayed = glam::Vec2::new(
+            displayed_size.width / VIEW_SIZE.width,
+            displayed_size.height / VIEW_SIZE.height,
+        );
+        let center = (pixel.as_vec2() + 0.5) * image_to_unit;
+        let displayed_center = self.as_matrix().transform_point2(center) * unit_to_displayed;
+        displayed_center.floor().as_uvec2()
+    }
+}
 
 #[derive(Copy, Clone)]
 pub(crate) struct Camera {
     pub translation: glam::Vec2,
     pub zoom: f32,
+    pub orientation: Orientation,
 }
 
 impl Default for Camera {
@@ -15,6 +117,7 @@ impl Default for Camera {
         Self {
             translation: glam::Vec2::ZERO,
             zoom: 1.0,
+            orientation: Orientation::default(),
         }
     }
 }
@@ -28,6 +131,138 @@ impl Camera {
             self.translation,
         )
     }
+
+    fn center(&self) -> glam::Vec2 {
+        self.translation + half_view() / self.zoom
+    }
+
+    /// The camera with the given zoom that has `center`, in view coordinates, at the center of
+    /// the view.
+    fn centered_at(center: glam::Vec2, zoom: f32, orientation: Orientation) -> Self {
+        Self {
+            translation: center - half_view() / zoom,
+            zoom,
+            orientation,
+        }
+    }
+
+    /// Turns the image in the view, keeping the part of the image at the center of the view
+    /// in place.
+    pub(crate) fn with_orientation(&self, orientation: Orientation) -> Self {
+        let center_in_image = self
+            .orientation
+            .as_matrix()
+            .inverse()
+            .transform_point2(self.center());
+        let center = orientation.as_matrix().transform_point2(center_in_image);
+        Self::centered_at(center, self.zoom, orientation)
+    }
+
+    /// Multiplies the zoom by `factor`, keeping the point at the center of the view in place.
+    pub(crate) fn zoomed_around_center(&self, factor: f32, geometry: &ViewGeometry) -> Self {
+        Self::centered_at(
+            self.center(),
+            geometry.clamp_zoom(self.zoom * factor),
+            self.orientation,
+        )
+    }
+
+    /// The zoom in percent of device pixels per image pixel, 100% shows the image pixels 1:1.
+    pub(crate) fn zoom_percent(&self, geometry: &ViewGeometry) -> f32 {
+        self.zoom * geometry.pixel_size_device() * 100.0
+    }
+
+    pub(crate) fn with_zoom_percent(&self, percent: f32, geometry: &ViewGeometry) -> Self {
+        let zoom = percent / 100.0 / geometry.pixel_size_device();
+        Self::centered_at(self.center(), geometry.clamp_zoom(zoom), self.orientation)
+    }
+
+    /// The camera where the image fills the width of the view, showing its top.
+    pub(crate) fn fit_width(geometry: &ViewGeometry) -> Self {
+        let half_extent = geometry.half_extent();
+        let zoom = geometry.clamp_zoom(half_extent.x / half_view().x);
+        let center_y = f32::min(half_extent.y / zoom, half_view().y);
+        Self::centered_at(
+            glam::Vec2::new(half_view().x, center_y),
+            zoom,
+            geometry.orientation,
+        )
+    }
+
+    /// Moves the camera so `pixel`, in image pixel coordinates, is at the center of the view.
+    pub(crate) fn centered_on_pixel(&self, pixel: glam::Vec2, geometry: &ViewGeometry) -> Self {
+        Self::centered_at(
+            geometry.image_to_view(pixel + 0.5),
+            self.zoom,
+            self.orientation,
+        )
+    }
+
+    /// The camera that fits the rectangle between the corners `a` and `b`, in image pixel
+    /// coordinates, in the view.
+    pub(crate) fn fit_rect(a: glam::Vec2, b: glam::Vec2, geometry: &ViewGeometry) -> Self {
+        let (a, b) = (geometry.image_to_view(a), geometry.image_to_view(b));
+        let (min, max) = (a.min(b), a.max(b));
+        let size = (max - min).max(glam::Vec2::splat(f32::EPSILON));
+        let zoom = (2.0 * geometry.half_extent() / size).min_element();
+        Self::centered_at(
+            (min + max) / 2.0,
+            geometry.clamp_zoom(zoom),
+            geometry.orientation,
+        )
+    }
+}
+
+fn half_view() -> glam::Vec2 {
+    glam::Vec2::new(VIEW_SIZE.width, VIEW_SIZE.height) / 2.0
+}
+
+/// The sizes that relate the camera to image and device pixels.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ViewGeometry {
+    /// In CSS pixels.
+    pub element_size: Size,
+    pub image_size: Size,
+    pub device_pixel_ratio: f32,
+    /// The orientation of the camera of the view.
+    pub orientation: Orientation,
+}
+
+impl ViewGeometry {
+    /// Half of the visible part of the view at zoom 1, in view coordinates. The image fits the
+    /// view, so one of the dimensions is always half of the view.
+    fn half_extent(&self) -> glam::Vec2 {
+        let image_size = self.displayed_image_size();
+        let element_aspect_ratio = self.element_size.width / self.element_size.height;
+        let scale = element_aspect_ratio / (image_size.width / image_size.height);
+        half_view() * glam::Vec2::new(scale.max(1.0), (1.0 / scale).max(1.0))
+    }
+
+    /// The size of an image pixel in device pixels at zoom 1.
+    fn pixel_size_device(&self) -> f32 {
+        let css_pixels_per_view_unit = self.element_size.width / (2.0 * self.half_extent().x);
+        css_pixels_per_view_unit * self.device_pixel_ratio * VIEW_SIZE.width
+            / self.displayed_image_size().width
+    }
+
+    fn clamp_zoom(&self, zoom: f32) -> f32 {
+        let max_zoom = MAX_PIXEL_SIZE_DEVICE as f32 / self.pixel_size_device();
+        zoom.clamp(MIN_ZOOM, max_zoom.max(MIN_ZOOM))
+    }
+
+    fn displayed_image_size(&self) -> Size {
+        self.orientation.displayed_size(&self.image_size)
+    }
+
+    /// Maps image pixel coordinates to view coordinates of the turned image.
+    fn image_to_view(&self, point: glam::Vec2) -> glam::Vec2 {
+        let point = point
+            * glam::Vec2::new(
+                VIEW_SIZE.width / self.image_size.width,
+                VIEW_SIZE.height / self.image_size.height,
+            );
+        self.orientation.as_matrix().transform_point2(point)
+    }
 }
 
 pub(crate) struct ViewsCameras(HashMap<ViewId, Rc<RefCell<Camera>>>);
@@ -49,8 +284,16 @@ impl ViewsCameras {
         self.0.get(&view_id).unwrap().replace(camera);
     }
 
+    /// Resets the pan and zoom of the view, the orientation is kept.
     pub(crate) fn reset(&mut self, view_id: ViewId) {
-        self.set(view_id, Camera::default());
+        let orientation = self.get(view_id).orientation;
+        self.set(
+            view_id,
+            Camera {
+                orientation,
+                ..Camera::default()
+            },
+        );
     }
 }
 
@@ -60,12 +303,30 @@ impl Default for ViewsCameras {
     }
 }
 
+/// Maps view coordinates of the image to clip space, `aspect_ratio` is the one of the image
+/// before it is turned by the orientation of the camera.
 pub(crate) fn calculate_view_projection(
     canvas_size: &Size,
     view_size: &Size,
     camera: &Camera,
     aspect_ratio: f32,
 ) -> glam::Mat3 {
+    calculate_camera_projection(canvas_size, view_size, camera, aspect_ratio)
+        * camera.orientation.as_matrix()
+}
+
+/// Maps view coordinates of the turned image, where the camera pans and zooms, to clip space.
+pub(crate) fn calculate_camera_projection(
+    canvas_size: &Size,
+    view_size: &Size,
+    camera: &Camera,
+    aspect_ratio: f32,
+) -> glam::Mat3 {
+    let aspect_ratio = if camera.orientation.is_transposed() {
+        1.0 / aspect_ratio
+    } else {
+        aspect_ratio
+    };
     let canvas_aspect_ratio = canvas_size.width / canvas_size.height;
     let scale = canvas_aspect_ratio / aspect_ratio;
 
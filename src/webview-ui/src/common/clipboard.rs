@@ -0,0 +1,13 @@
+use wasm_bindgen_futures::JsFuture;
+
+pub(crate) fn copy_to_clipboard(text: String) {
+    let Some(window) = web_sys::window() else {
+        return;
+    };
+    let promise = window.navigator().clipboard().write_text(&text);
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(err) = JsFuture::from(promise).await {
+            log::error!("Failed to copy to clipboard: {:?}", err);
+        }
+    });
+}
@@ -2,6 +2,12 @@ use super::ViewId;
 
 pub(crate) const MAX_PIXEL_SIZE_DEVICE: i32 = 250;
 
+/// Number of batch items requested ahead of the playback position.
+pub(crate) const PLAYBACK_PREFETCH_AHEAD: u32 = 8;
+
+/// Number of expressions whose drawing options are remembered across debug sessions.
+pub(crate) const MAX_REMEMBERED_DRAWING_OPTIONS: usize = 200;
+
 pub(crate) fn all_views() -> Vec<ViewId> {
     vec![ViewId::Primary]
 }
@@ -10,9 +16,20 @@ pub(crate) struct Times {
     pub(crate) data_fetcher_debounce: u32,
     pub(crate) keyboard_debounce: u32,
     pub(crate) view_shift_scroll_debounce: u32,
+    pub(crate) playback_prefetch_debounce: u32,
+    pub(crate) playback_prefetch_max_wait: u32,
+    pub(crate) memory_budget_debounce: u32,
+    pub(crate) memory_budget_max_wait: u32,
+    pub(crate) prefetch_debounce: u32,
 }
 pub(crate) const TIMES: Times = Times {
     data_fetcher_debounce: 700,
     keyboard_debounce: 100,
     view_shift_scroll_debounce: 250,
+    playback_prefetch_debounce: 50,
+    playback_prefetch_max_wait: 200,
+    memory_budget_debounce: 500,
+    memory_budget_max_wait: 2000,
+    // after data_fetcher_debounce, so the shown images are requested first
+    prefetch_debounce: 1000,
 };
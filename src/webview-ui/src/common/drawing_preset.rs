@@ -0,0 +1,93 @@
+use crate::{
+    application_state::{app_state::GlobalDrawingOptions, colormaps::ColorMapRegistry},
+    coloring::{Clip, Coloring, DrawingOptions},
+};
+
+/// A named set of drawing options, kept in the `svifpd.viewerUi.drawingPresets` setting.
+/// Options that are not set take their default value when the preset is applied.
+#[derive(tsify::Tsify, serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DrawingPreset {
+    pub name: String,
+    pub coloring: Option<Coloring>,
+    pub invert: Option<bool>,
+    pub high_contrast: Option<bool>,
+    pub ignore_alpha: Option<bool>,
+    pub clip_min: Option<f32>,
+    pub clip_max: Option<f32>,
+    pub zeros_as_transparent: Option<bool>,
+    pub global_alpha: Option<f32>,
+    /// Only used, and only saved, for the heatmap coloring.
+    pub heatmap_colormap: Option<String>,
+    /// Only used, and only saved, for the segmentation coloring.
+    pub segmentation_colormap: Option<String>,
+}
+
+impl DrawingPreset {
+    pub(crate) fn from_current(
+        name: String,
+        drawing_options: &DrawingOptions,
+        global_drawing_options: &GlobalDrawingOptions,
+    ) -> Self {
+        let coloring = drawing_options.coloring;
+        Self {
+            name,
+            coloring: Some(coloring),
+            invert: Some(drawing_options.invert),
+            high_contrast: Some(drawing_options.high_contrast),
+            ignore_alpha: Some(drawing_options.ignore_alpha),
+            clip_min: drawing_options.clip.min,
+            clip_max: drawing_options.clip.max,
+            zeros_as_transparent: Some(drawing_options.zeros_as_transparent),
+            global_alpha: Some(drawing_options.global_alpha),
+            heatmap_colormap: (coloring == Coloring::Heatmap)
+                .then(|| global_drawing_options.heatmap_colormap_name.clone()),
+            segmentation_colormap: (coloring == Coloring::Segmentation)
+                .then(|| global_drawing_options.segmentation_colormap_name.clone()),
+        }
+    }
+
+    /// The drawing options of the preset, for an image showing `batch_item`.
+    pub(crate) fn drawing_options(&self, batch_item: Option<u32>) -> DrawingOptions {
+        let default = DrawingOptions::default();
+        DrawingOptions {
+            coloring: self.coloring.unwrap_or(default.coloring),
+            invert: self.invert.unwrap_or(default.invert),
+            high_contrast: self.high_contrast.unwrap_or(default.high_contrast),
+            ignore_alpha: self.ignore_alpha.unwrap_or(default.ignore_alpha),
+            batch_item,
+            clip: Clip {
+                min: self.clip_min,
+                max: self.clip_max,
+            },
+            zeros_as_transparent: self
+                .zeros_as_transparent
+                .unwrap_or(default.zeros_as_transparent),
+            global_alpha: self.global_alpha.unwrap_or(default.global_alpha),
+            normalize_per_batch_item: default.normalize_per_batch_item,
+        }
+    }
+
+    /// Colormaps are shared by all images, so they are set globally. Unknown colormaps are
+    /// ignored.
+    pub(crate) fn apply_colormaps(
+        &self,
+        global_drawing_options: &mut GlobalDrawingOptions,
+        color_map_registry: &ColorMapRegistry,
+    ) {
+        let known = |name: &Option<String>| {
+            let name = name.as_ref()?;
+            if color_map_registry.get(name).is_none() {
+                log::warn!("Preset {}: unknown colormap {}", self.name, name);
+                return None;
+            }
+            Some(name.clone())
+        };
+        if let Some(name) = known(&self.heatmap_colormap) {
+            global_drawing_options.heatmap_colormap_name = name;
+        }
+        if let Some(name) = known(&self.segmentation_colormap) {
+            global_drawing_options.segmentation_colormap_name = name;
+        }
+    }
+}
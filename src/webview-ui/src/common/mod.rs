@@ -1,5 +1,7 @@
 pub(crate) mod types;
+pub(crate) mod clipboard;
 pub(crate) mod constants;
+pub(crate) mod drawing_preset;
 pub(crate) mod pixel_value;
 pub mod texture_image;
 pub mod camera;
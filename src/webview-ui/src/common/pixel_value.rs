@@ -17,7 +17,93 @@ use super::{DataOrdering, ImageInfo};
 
 static_assertions::const_assert_eq!(Channels::COUNT, 4); // If this is failing, you need to update the code below
 
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum FloatNotation {
+    /// Whichever of fixed or scientific notation fits in `precision` characters.
+    #[default]
+    Auto,
+    Fixed,
+    Scientific,
+}
+
+impl From<&str> for FloatNotation {
+    fn from(value: &str) -> Self {
+        match value {
+            "fixed" => Self::Fixed,
+            "scientific" => Self::Scientific,
+            _ => Self::Auto,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum IntegerBase {
+    #[default]
+    Decimal,
+    /// Negative values are shown in two's complement.
+    Hex,
+    Binary,
+}
+
+impl From<&str> for IntegerBase {
+    fn from(value: &str) -> Self {
+        match value {
+            "hex" => Self::Hex,
+            "binary" => Self::Binary,
+            _ => Self::Decimal,
+        }
+    }
+}
+
+/// How pixel values are displayed, in the pixel text and in the status bar.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PixelValueFormat {
+    pub float_notation: FloatNotation,
+    /// Maximum number of characters for [`FloatNotation::Auto`], number of digits after the
+    /// decimal point otherwise.
+    pub precision: usize,
+    pub integer_base: IntegerBase,
+    /// Show the normalized value used for display next to the raw value.
+    pub show_normalized: bool,
+}
+
+impl Default for PixelValueFormat {
+    fn default() -> Self {
+        Self {
+            float_notation: FloatNotation::default(),
+            precision: 7,
+            integer_base: IntegerBase::default(),
+            show_normalized: false,
+        }
+    }
+}
+
+impl PixelValueFormat {
+    pub(crate) fn format_float(&self, value: f32) -> String {
+        match self.float_notation {
+            FloatNotation::Auto => format!(
+                "{:.*}",
+                self.precision,
+                float_pretty_print::PrettyPrintFloat(value as f64)
+            ),
+            FloatNotation::Fixed => format!("{:.*}", self.precision, value),
+            FloatNotation::Scientific => format!("{:.*e}", self.precision, value),
+        }
+    }
+
+    fn format_integer<T>(&self, value: T) -> String
+    where
+        T: Display + std::fmt::LowerHex + std::fmt::Binary,
+    {
+        match self.integer_base {
+            IntegerBase::Decimal => format!("{}", value),
+            IntegerBase::Hex => format!("{:#x}", value),
+            IntegerBase::Binary => format!("{:#b}", value),
+        }
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Copy, Clone, Debug, PartialEq)]
 pub(crate) struct PixelValue {
     pub num_channels: Channels,
     pub datatype: Datatype,
@@ -26,25 +112,12 @@ pub(crate) struct PixelValue {
 
 impl Display for PixelValue {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let datatype = self.datatype;
         write!(f, "(")?;
         let mut sep = "";
-        (0..self.num_channels.into())
-            .map(|c| -> std::fmt::Result {
-                let res = match datatype {
-                    Datatype::Uint8 => write!(f, "{}{}", sep, self.get::<u8>(c)),
-                    Datatype::Uint16 => write!(f, "{}{}", sep, self.get::<u16>(c)),
-                    Datatype::Uint32 => write!(f, "{}{}", sep, self.get::<u32>(c)),
-                    Datatype::Float32 => write!(f, "{}{}", sep, self.get::<f32>(c)),
-                    Datatype::Int8 => write!(f, "{}{}", sep, self.get::<i8>(c)),
-                    Datatype::Int16 => write!(f, "{}{}", sep, self.get::<i16>(c)),
-                    Datatype::Int32 => write!(f, "{}{}", sep, self.get::<i32>(c)),
-                    Datatype::Bool => write!(f, "{}{}", sep, self.get::<u8>(c)),
-                };
-                sep = ", ";
-                res
-            })
-            .collect::<Result<Vec<_>, _>>()?;
+        for c in 0..self.num_channels.into() {
+            write!(f, "{}{}", sep, self.format_channel(c))?;
+            sep = ", ";
+        }
         write!(f, ")")
     }
 }
@@ -121,6 +194,57 @@ impl PixelValue {
         }
     }
 
+    pub(crate) fn format_channel(&self, channel: u32) -> String {
+        match self.datatype {
+            Datatype::Uint8 => self.get::<u8>(channel).to_string(),
+            Datatype::Uint16 => self.get::<u16>(channel).to_string(),
+            Datatype::Uint32 => self.get::<u32>(channel).to_string(),
+            Datatype::Float32 => self.get::<f32>(channel).to_string(),
+            Datatype::Int8 => self.get::<i8>(channel).to_string(),
+            Datatype::Int16 => self.get::<i16>(channel).to_string(),
+            Datatype::Int32 => self.get::<i32>(channel).to_string(),
+            Datatype::Bool => self.get::<u8>(channel).to_string(),
+        }
+    }
+
+    /// Formats a channel for display. The normalized value is shown next to the raw value if the
+    /// format asks for it and it is given.
+    pub(crate) fn format_channel_with(
+        &self,
+        channel: u32,
+        format: &PixelValueFormat,
+        normalized: Option<f32>,
+    ) -> String {
+        let raw = match self.datatype {
+            Datatype::Uint8 => format.format_integer(*self.get::<u8>(channel)),
+            Datatype::Uint16 => format.format_integer(*self.get::<u16>(channel)),
+            Datatype::Uint32 => format.format_integer(*self.get::<u32>(channel)),
+            Datatype::Float32 => format.format_float(*self.get::<f32>(channel)),
+            Datatype::Int8 => format.format_integer(*self.get::<i8>(channel)),
+            Datatype::Int16 => format.format_integer(*self.get::<i16>(channel)),
+            Datatype::Int32 => format.format_integer(*self.get::<i32>(channel)),
+            Datatype::Bool => format.format_integer((*self.get::<u8>(channel) != 0) as u8),
+        };
+        match normalized.filter(|_| format.show_normalized) {
+            Some(normalized) => format!("{} ({})", raw, format.format_float(normalized)),
+            None => raw,
+        }
+    }
+
+    /// Formats all channels for display. See [`PixelValue::format_channel_with`].
+    pub(crate) fn format_with(
+        &self,
+        format: &PixelValueFormat,
+        normalized: Option<&[f32]>,
+    ) -> Vec<String> {
+        (0..self.num_channels.into())
+            .map(|c| {
+                let normalized = normalized.and_then(|n| n.get(c as usize).copied());
+                self.format_channel_with(c, format, normalized)
+            })
+            .collect()
+    }
+
     pub(crate) fn as_rgba_f32(&self) -> [f32; 4] {
         let mut res = [0_f32; 4];
         for channel in 0..self.num_channels.into() {
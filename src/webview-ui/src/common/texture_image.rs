@@ -1,10 +1,16 @@
-use std::{collections::HashMap, fmt, iter::FromIterator};
+use std::{
+    collections::HashMap,
+    fmt,
+    iter::{FromIterator, Sum},
+    ops::Add,
+    sync::atomic::{AtomicU32, Ordering},
+};
 
 use crate::{
     math_utils::image_calculations::{calc_num_bytes_per_image, calc_num_bytes_per_plane},
     webgl_utils::{self, GLGuard},
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use itertools::Itertools;
 
 use super::{Channels, ComputedInfo, DataOrdering, Datatype, ImageData, ImageInfo, Size};
@@ -32,7 +38,45 @@ pub(crate) enum TexturesGroup {
     },
 }
 
+/// Memory held by texture images, in bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct MemoryUsage {
+    /// Pixel bytes kept to read the pixel values.
+    pub cpu_bytes: usize,
+    /// Pixel bytes uploaded to the textures.
+    pub gpu_bytes: usize,
+}
+
+impl MemoryUsage {
+    pub(crate) fn total(&self) -> usize {
+        self.cpu_bytes + self.gpu_bytes
+    }
+}
+
+impl Add for MemoryUsage {
+    type Output = MemoryUsage;
+
+    fn add(self, other: MemoryUsage) -> MemoryUsage {
+        MemoryUsage {
+            cpu_bytes: self.cpu_bytes + other.cpu_bytes,
+            gpu_bytes: self.gpu_bytes + other.gpu_bytes,
+        }
+    }
+}
+
+impl Sum for MemoryUsage {
+    fn sum<I: Iterator<Item = MemoryUsage>>(iter: I) -> MemoryUsage {
+        iter.fold(MemoryUsage::default(), Add::add)
+    }
+}
+
+static NEXT_TEXTURE_IMAGE_ID: AtomicU32 = AtomicU32::new(0);
+
 pub(crate) struct TextureImage {
+    /// Unique among the created texture images, tells apart data received for the same image.
+    pub id: u32,
+    /// Ids of the texture images merged into this one by [`TextureImage::update`].
+    pub merged_ids: Vec<u32>,
     pub info: ImageInfo,
     pub computed_info: ComputedInfo,
     pub bytes: HashMap<u32, Vec<u8>>,
@@ -65,44 +109,40 @@ impl TextureImage {
         )
     }
 
+    /// Creates the textures of one image, or one batch item, from its bytes.
     fn make_textures_group(
-        image: &ImageData,
+        info: &ImageInfo,
+        bytes: &[u8],
         gl: &web_sys::WebGl2RenderingContext,
-        offset: usize,
     ) -> Result<TexturesGroup> {
-        match image.info.data_ordering {
+        match info.data_ordering {
             DataOrdering::HWC => {
                 let texture = Self::make_texture(
                     gl,
-                    &image.bytes[offset..],
-                    image.info.width,
-                    image.info.height,
-                    image.info.channels,
-                    image.info.datatype,
+                    bytes,
+                    info.width,
+                    info.height,
+                    info.channels,
+                    info.datatype,
                 )?;
                 Ok(TexturesGroup::HWC(texture))
             }
 
             DataOrdering::CHW => {
-                let plane_size = calc_num_bytes_per_plane(
-                    image.info.width,
-                    image.info.height,
-                    image.info.datatype,
-                );
+                let plane_size = calc_num_bytes_per_plane(info.width, info.height, info.datatype);
 
                 let make_texture_for_channel = |channel: usize| {
                     Self::make_texture(
                         gl,
-                        &image.bytes
-                            [offset + plane_size * channel..offset + plane_size * (channel + 1)],
-                        image.info.width,
-                        image.info.height,
+                        &bytes[plane_size * channel..plane_size * (channel + 1)],
+                        info.width,
+                        info.height,
                         Channels::One,
-                        image.info.datatype,
+                        info.datatype,
                     )
                 };
 
-                match image.info.channels {
+                match info.channels {
                     Channels::One => {
                         let gray = make_texture_for_channel(0)?;
                         Ok(TexturesGroup::CHW_G { gray })
@@ -135,7 +175,11 @@ impl TextureImage {
         }
     }
 
-    pub(crate) fn try_new(image: ImageData, gl: &web_sys::WebGl2RenderingContext) -> Result<Self> {
+    /// Without a WebGL context only the bytes are kept, for drawing the image on the CPU.
+    pub(crate) fn try_new(
+        image: ImageData,
+        gl: Option<&web_sys::WebGl2RenderingContext>,
+    ) -> Result<Self> {
         let info = image.info.clone();
         let computed_info = image.computed_info.clone();
 
@@ -152,7 +196,11 @@ impl TextureImage {
             (start..end)
                 .map(|index| {
                     let offset = (index - start) as usize * batch_item_size;
-                    let textures = Self::make_textures_group(&image, gl, offset)?;
+                    let textures = gl
+                        .map(|gl| {
+                            Self::make_textures_group(&image.info, &image.bytes[offset..], gl)
+                        })
+                        .transpose()?;
                     let bytes = image.bytes[offset..offset + batch_item_size].to_vec();
 
                     Ok((index, textures, bytes))
@@ -162,7 +210,9 @@ impl TextureImage {
                 .map(|(batch_item, textures, bytes)| Ok((batch_item, (textures, bytes))))
                 .collect::<Result<HashMap<_, _>>>()
         } else {
-            let textures = Self::make_textures_group(&image, gl, 0)?;
+            let textures = gl
+                .map(|gl| Self::make_textures_group(&image.info, &image.bytes, gl))
+                .transpose()?;
             let bytes = image.bytes;
             Ok(HashMap::from_iter([(0u32, (textures, bytes))]))
         }?;
@@ -171,10 +221,15 @@ impl TextureImage {
             .into_iter()
             .map(|(k, (v1, v2))| ((k, v1), (k, v2)))
             .unzip();
-        let textures = HashMap::from_iter(textures);
+        let textures = textures
+            .into_iter()
+            .filter_map(|(batch_item, textures)| Some((batch_item, textures?)))
+            .collect();
         let bytes = HashMap::from_iter(bytes);
 
         Ok(Self {
+            id: NEXT_TEXTURE_IMAGE_ID.fetch_add(1, Ordering::Relaxed),
+            merged_ids: Vec::new(),
             info,
             computed_info,
             bytes,
@@ -182,6 +237,23 @@ impl TextureImage {
         })
     }
 
+    /// Creates the textures again from the bytes, once those of a lost WebGL context are gone.
+    pub(crate) fn restore_textures(&mut self, gl: &web_sys::WebGl2RenderingContext) -> Result<()> {
+        for (batch_item, textures) in self.textures.iter_mut() {
+            let bytes = self
+                .bytes
+                .get(batch_item)
+                .ok_or_else(|| anyhow!("No bytes kept for batch item {}", batch_item))?;
+            *textures = Self::make_textures_group(&self.info, bytes, gl)?;
+        }
+        Ok(())
+    }
+
+    /// Whether the data of the texture image with this id is held, directly or merged.
+    pub(crate) fn holds_data_of(&self, texture_id: u32) -> bool {
+        self.id == texture_id || self.merged_ids.contains(&texture_id)
+    }
+
     pub(crate) fn image_size(&self) -> Size {
         Size {
             width: self.info.width as f32,
@@ -189,19 +261,96 @@ impl TextureImage {
         }
     }
 
-    pub(crate) fn update(&mut self, other: TextureImage) {
-        // TODO verify that the other image has the same info
+    /// The batch items held, 0 for an image that is not batched.
+    pub(crate) fn batch_items(&self) -> impl Iterator<Item = u32> + '_ {
+        self.bytes.keys().copied()
+    }
+
+    pub(crate) fn has_batch_item(&self, batch_item: u32) -> bool {
+        self.bytes.contains_key(&batch_item)
+    }
+
+    pub(crate) fn batch_item_memory_usage(&self, batch_item: u32) -> MemoryUsage {
+        // the textures are created with the datatype and channels of the bytes
+        let gpu_bytes = if self.textures.contains_key(&batch_item) {
+            calc_num_bytes_per_image(
+                self.info.width,
+                self.info.height,
+                self.info.channels,
+                self.info.datatype,
+            )
+        } else {
+            0
+        };
+        MemoryUsage {
+            cpu_bytes: self.bytes.get(&batch_item).map_or(0, Vec::len),
+            gpu_bytes,
+        }
+    }
+
+    pub(crate) fn memory_usage(&self) -> MemoryUsage {
+        self.batch_items()
+            .map(|batch_item| self.batch_item_memory_usage(batch_item))
+            .sum()
+    }
+
+    /// Frees the bytes and the textures of the batch item.
+    pub(crate) fn remove_batch_item(&mut self, batch_item: u32) {
+        self.bytes.remove(&batch_item);
+        self.textures.remove(&batch_item);
+    }
+
+    /// Batch items can only be added from an image with the same shape, datatype and batch
+    /// size.
+    fn check_compatible(&self, other: &ImageInfo) -> Result<()> {
+        let layout = |info: &ImageInfo| {
+            let batch_size = info
+                .batch_info
+                .as_ref()
+                .map(|batch_info| batch_info.batch_size);
+            (
+                info.width,
+                info.height,
+                info.channels,
+                info.datatype,
+                info.data_ordering,
+                batch_size,
+            )
+        };
+        let describe = |info: &ImageInfo| {
+            let (width, height, channels, datatype, data_ordering, batch_size) = layout(info);
+            format!(
+                "{}x{}x{} {:?} {:?} with batch size {:?}",
+                height, width, channels, datatype, data_ordering, batch_size
+            )
+        };
+        if layout(&self.info) != layout(other) {
+            return Err(anyhow!(
+                "Cannot add batch items to {}: they are {}, the image is {}",
+                self.info.image_id,
+                describe(other),
+                describe(&self.info)
+            ));
+        }
+        Ok(())
+    }
+
+    /// Adds the batch items of another chunk of the same image.
+    pub(crate) fn update(&mut self, other: TextureImage) -> Result<()> {
+        self.check_compatible(&other.info)?;
 
         log::debug!(
             "TextureImage::update: current={:?}, other={:?}",
-            self.textures.keys().sorted(),
-            other.textures.keys().sorted(),
+            self.batch_items().sorted(),
+            other.batch_items().sorted(),
         );
 
         self.bytes.extend(other.bytes);
         self.textures.extend(other.textures);
-
-        // TODO update computed info
+        self.merged_ids.push(other.id);
+        self.merged_ids.extend(other.merged_ids);
+        self.computed_info.merge(other.computed_info);
+        Ok(())
     }
 }
 
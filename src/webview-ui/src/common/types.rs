@@ -1,4 +1,4 @@
-use std::{collections::HashMap, convert::TryFrom, fmt::Display};
+use std::{collections::HashMap, convert::TryFrom, fmt::Display, rc::Rc};
 
 use super::pixel_value::PixelValue;
 
@@ -15,7 +15,6 @@ impl SessionId {
 pub(crate) struct ViewableObjectId(SessionId, String);
 
 impl ViewableObjectId {
-    #[cfg(debug_assertions)]
     pub(crate) fn new(session: &SessionId, id: &str) -> Self {
         Self(session.clone(), id.to_owned())
     }
@@ -80,6 +79,7 @@ pub(crate) enum ValueVariableKind {
 }
 
 #[derive(
+    serde_repr::Serialize_repr,
     serde_repr::Deserialize_repr,
     Debug,
     Clone,
@@ -127,7 +127,9 @@ impl Display for Channels {
     }
 }
 
-#[derive(tsify::Tsify, serde::Deserialize, Debug, Clone, PartialEq, Eq, Hash, Copy)]
+#[derive(
+    tsify::Tsify, serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq, Hash, Copy,
+)]
 pub(crate) enum Datatype {
     #[serde(rename = "uint8")]
     Uint8,
@@ -180,10 +182,26 @@ impl Datatype {
     pub(crate) fn is_signed_integer(&self) -> bool {
         matches!(self, Datatype::Int8 | Datatype::Int16 | Datatype::Int32)
     }
+
+    /// The values the datatype can hold, `0..1` for floats which are usually normalized.
+    pub(crate) fn value_range(&self) -> (f32, f32) {
+        match self {
+            Datatype::Uint8 => (u8::MIN as f32, u8::MAX as f32),
+            Datatype::Uint16 => (u16::MIN as f32, u16::MAX as f32),
+            Datatype::Uint32 => (u32::MIN as f32, u32::MAX as f32),
+            Datatype::Float32 => (0.0, 1.0),
+            Datatype::Int8 => (i8::MIN as f32, i8::MAX as f32),
+            Datatype::Int16 => (i16::MIN as f32, i16::MAX as f32),
+            Datatype::Int32 => (i32::MIN as f32, i32::MAX as f32),
+            Datatype::Bool => (0.0, 1.0),
+        }
+    }
 }
 
 #[allow(clippy::upper_case_acronyms)]
-#[derive(tsify::Tsify, serde::Deserialize, Debug, Clone, PartialEq, Eq, Hash, Copy)]
+#[derive(
+    tsify::Tsify, serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq, Hash, Copy,
+)]
 pub(crate) enum DataOrdering {
     #[serde(rename = "hwc")]
     HWC,
@@ -269,10 +287,105 @@ impl<'a> Image {
     }
 }
 
+/// Statistics of the pixels of a batch item, computed by the compute worker.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub(crate) struct ImageStatistics {
+    pub min: PixelValue,
+    pub max: PixelValue,
+    /// Per channel, like `std` and `histograms`.
+    pub mean: Vec<f32>,
+    pub std: Vec<f32>,
+    /// [`HISTOGRAM_BINS`](crate::math_utils::image_calculations::HISTOGRAM_BINS) bins spread
+    /// between the min and the max of the channel.
+    pub histograms: Vec<Vec<u32>>,
+    /// Only kept for single-channel integer images with few unique values, like label images.
+    pub unique_values: Option<Vec<PixelValue>>,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub(crate) struct ComputedInfo {
+    /// Over all the batch items received, see [`ComputedInfo::range`].
     pub min: PixelValue,
     pub max: PixelValue,
+    /// `min` and `max` are the range of the datatype until the statistics arrive.
+    pub minmax_pending: bool,
+    /// Per batch item, 0 for an image that is not batched.
+    pub statistics: HashMap<u32, Rc<ImageStatistics>>,
+}
+
+impl ComputedInfo {
+    pub(crate) fn new(min: PixelValue, max: PixelValue) -> Self {
+        Self {
+            min,
+            max,
+            minmax_pending: false,
+            statistics: HashMap::new(),
+        }
+    }
+
+    /// Computed info to render with until the statistics of the image arrive.
+    pub(crate) fn pending(datatype: Datatype, channels: Channels) -> Self {
+        let (low, high) = datatype.value_range();
+        let mut min = PixelValue::new(channels, Datatype::Float32);
+        min.fill(low);
+        let mut max = PixelValue::new(channels, Datatype::Float32);
+        max.fill(high);
+        Self {
+            minmax_pending: true,
+            ..Self::new(min, max)
+        }
+    }
+
+    /// The min and max to normalize a batch item with: its own if asked and known, those of all
+    /// the batch items otherwise.
+    pub(crate) fn range(&self, batch_item: u32, per_batch_item: bool) -> (PixelValue, PixelValue) {
+        per_batch_item
+            .then(|| self.statistics.get(&batch_item))
+            .flatten()
+            .map(|statistics| (statistics.min, statistics.max))
+            .unwrap_or((self.min, self.max))
+    }
+
+    /// Grows the min and max of all the batch items to include `min..max`.
+    fn extend_range(&mut self, min: &PixelValue, max: &PixelValue) {
+        if self.minmax_pending {
+            self.min = *min;
+            self.max = *max;
+            self.minmax_pending = false;
+            return;
+        }
+        let combine = |a: &PixelValue, b: &PixelValue, f: fn(f32, f32) -> f32| {
+            let (a_values, b_values) = (a.as_rgba_f32(), b.as_rgba_f32());
+            let mut combined = PixelValue::new(a.num_channels, Datatype::Float32);
+            for channel in 0..a.num_channels.into() {
+                *combined.get_mut::<f32>(channel) =
+                    f(a_values[channel as usize], b_values[channel as usize]);
+            }
+            combined
+        };
+        self.min = combine(&self.min, min, f32::min);
+        self.max = combine(&self.max, max, f32::max);
+    }
+
+    /// Adds the statistics of batch items, and grows the min and max of all the batch items to
+    /// include theirs.
+    pub(crate) fn merge_statistics(
+        &mut self,
+        statistics: impl IntoIterator<Item = (u32, ImageStatistics)>,
+    ) {
+        for (batch_item, statistics) in statistics {
+            self.extend_range(&statistics.min, &statistics.max);
+            self.statistics.insert(batch_item, Rc::new(statistics));
+        }
+    }
+
+    /// Adds the computed info of other batch items of the same image.
+    pub(crate) fn merge(&mut self, other: ComputedInfo) {
+        if !other.minmax_pending {
+            self.extend_range(&other.min, &other.max);
+        }
+        self.statistics.extend(other.statistics);
+    }
 }
 
 pub(crate) struct ImageData {
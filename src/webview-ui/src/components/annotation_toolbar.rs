@@ -0,0 +1,257 @@
+use stylist::yew::use_style;
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+use yewdux::{functional::use_selector, Dispatch};
+
+use crate::{
+    application_state::{
+        annotations::{label_color, AnnotationTool, Annotations, ImageAnnotations, Shape},
+        app_state::{AnnotationAction, AppState},
+    },
+    common::{ViewId, ViewableObjectId},
+    vscode::vscode_requests::{AnnotationContent, AnnotationShape, VSCodeRequests},
+};
+
+use super::icon_button::IconButton;
+
+#[derive(Clone, PartialEq)]
+struct ToolbarState {
+    tool: Option<AnnotationTool>,
+    label: u8,
+    brush_radius: f32,
+    image_id: Option<ViewableObjectId>,
+    has_mask: bool,
+    num_shapes: usize,
+}
+
+fn to_annotation_shape(shape: &Shape) -> AnnotationShape {
+    match shape {
+        Shape::Polygon { label, points } => AnnotationShape::Polygon {
+            label: *label,
+            points: points.iter().map(|p| p.to_array()).collect(),
+        },
+        Shape::Box { label, start, end } => AnnotationShape::Box {
+            label: *label,
+            x: start.x,
+            y: start.y,
+            width: end.x - start.x,
+            height: end.y - start.y,
+        },
+    }
+}
+
+/// Sends the mask, or the shapes, of the image to the extension.
+fn save_annotations(image_id: &ViewableObjectId, as_mask: bool) {
+    let state = Dispatch::<AppState>::global().get();
+    let Some(expression) = state
+        .images
+        .borrow()
+        .get(image_id)
+        .map(|image| image.minimal().expression.clone())
+    else {
+        return;
+    };
+    let annotations = state.annotations.borrow();
+    let Some(ImageAnnotations { mask, shapes }) = annotations.get(image_id) else {
+        return;
+    };
+    let content = if as_mask {
+        let Some(mask) = mask else {
+            return;
+        };
+        AnnotationContent::Mask {
+            width: mask.width,
+            height: mask.height,
+            data: mask.data.clone(),
+        }
+    } else {
+        let Some(size) = state
+            .image_cache
+            .borrow()
+            .get(image_id)
+            .map(|texture| texture.borrow().image_size())
+        else {
+            return;
+        };
+        AnnotationContent::Shapes {
+            width: size.width as u32,
+            height: size.height as u32,
+            shapes: shapes.iter().map(to_annotation_shape).collect(),
+        }
+    };
+    VSCodeRequests::save_annotations(image_id.clone(), expression, content);
+}
+
+#[function_component]
+pub(crate) fn AnnotationToolbar() -> Html {
+    let toolbar_state = use_selector(|state: &AppState| {
+        let image_id = state
+            .image_views
+            .borrow()
+            .get_currently_viewing(ViewId::Primary)
+            .map(|cv| cv.id().clone());
+        let annotations = state.annotations.borrow();
+        let image_annotations = image_id.as_ref().and_then(|id| annotations.get(id));
+        ToolbarState {
+            tool: annotations.tool,
+            label: annotations.label,
+            brush_radius: annotations.brush_radius,
+            has_mask: image_annotations.is_some_and(|a| a.mask.is_some()),
+            num_shapes: image_annotations.map_or(0, |a| a.shapes.len()),
+            image_id,
+        }
+    });
+
+    let style = use_style!(
+        r#"
+        display: flex;
+        flex-direction: row;
+        align-items: center;
+        gap: 4px;
+
+        input[type="number"] {
+            width: 3.5em;
+        }
+        .swatch {
+            width: 12px;
+            height: 12px;
+            border: 1px solid var(--vscode-panel-border);
+        }
+        "#,
+    );
+    let currently_selected_style = use_style!(
+        r#"
+        background-color: var(--vscode-button-background);
+        :hover {
+            background-color: var(--vscode-button-background);
+        }
+        "#,
+    );
+
+    let dispatch = Dispatch::<AppState>::global();
+
+    let tool_buttons = AnnotationTool::ALL
+        .into_iter()
+        .map(|tool| {
+            let selected = toolbar_state.tool == Some(tool);
+            html! {
+                <IconButton
+                    class={classes!(selected.then_some(currently_selected_style.clone()))}
+                    aria_label={tool.name()}
+                    title={tool.name()}
+                    icon={tool.icon()}
+                    onclick={dispatch.apply_callback(move |_| AnnotationAction::ToggleTool(tool))}
+                />
+            }
+        })
+        .collect::<Html>();
+
+    let has_annotations = toolbar_state.has_mask || toolbar_state.num_shapes > 0;
+    if toolbar_state.tool.is_none() && !has_annotations {
+        return html! { <div class={style}>{tool_buttons}</div> };
+    }
+
+    let current_label = toolbar_state.label;
+    let on_label_change = dispatch.apply_callback(move |ev: Event| {
+        let label = ev
+            .target()
+            .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+            .and_then(|input| input.value().parse::<u8>().ok())
+            .unwrap_or(current_label);
+        AnnotationAction::SetLabel(label)
+    });
+    let current_radius = toolbar_state.brush_radius;
+    let on_radius_change = dispatch.apply_callback(move |ev: Event| {
+        let radius = ev
+            .target()
+            .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+            .and_then(|input| input.value().parse::<f32>().ok())
+            .filter(|radius| radius.is_finite())
+            .unwrap_or(current_radius);
+        AnnotationAction::SetBrushRadius(radius)
+    });
+
+    let [r, g, b] = label_color(toolbar_state.label);
+    let swatch_style = format!("background-color: rgb({}, {}, {});", r, g, b);
+
+    let brush_radius_input = toolbar_state
+        .tool
+        .is_some_and(|tool| tool.paints_mask())
+        .then(|| {
+            html! {
+                <div class="vscode-textfield" title="Brush radius (pixels)">
+                    <input
+                        type="number"
+                        min={Annotations::MIN_BRUSH_RADIUS.to_string()}
+                        max={Annotations::MAX_BRUSH_RADIUS.to_string()}
+                        step="0.5"
+                        value={toolbar_state.brush_radius.to_string()}
+                        onchange={on_radius_change}
+                        onkeydown={|ev: KeyboardEvent| { ev.stop_propagation(); }}
+                    />
+                </div>
+            }
+        });
+
+    let image_id = toolbar_state.image_id.clone();
+    let on_save_mask = {
+        let image_id = image_id.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(image_id) = &image_id {
+                save_annotations(image_id, true);
+            }
+        })
+    };
+    let on_save_shapes = {
+        let image_id = image_id.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(image_id) = &image_id {
+                save_annotations(image_id, false);
+            }
+        })
+    };
+    let on_clear = dispatch.apply_callback(move |_: MouseEvent| match &image_id {
+        Some(image_id) => AnnotationAction::Clear(image_id.clone()),
+        None => AnnotationAction::CancelDraft(ViewId::Primary),
+    });
+
+    html! {
+        <div class={style}>
+            {tool_buttons}
+            <span class="swatch" style={swatch_style} />
+            <div class="vscode-textfield" title="Label">
+                <input
+                    type="number"
+                    min="1"
+                    max={Annotations::MAX_LABEL.to_string()}
+                    step="1"
+                    value={toolbar_state.label.to_string()}
+                    onchange={on_label_change}
+                    onkeydown={|ev: KeyboardEvent| { ev.stop_propagation(); }}
+                />
+            </div>
+            {brush_radius_input}
+            <IconButton
+                aria_label={"Save mask"}
+                title={"Save mask"}
+                icon={"codicon codicon-symbol-array"}
+                disabled={!toolbar_state.has_mask}
+                onclick={on_save_mask}
+            />
+            <IconButton
+                aria_label={"Save shapes as JSON"}
+                title={"Save shapes as JSON"}
+                icon={"codicon codicon-json"}
+                disabled={toolbar_state.num_shapes == 0}
+                onclick={on_save_shapes}
+            />
+            <IconButton
+                aria_label={"Clear annotations"}
+                title={"Clear annotations"}
+                icon={"codicon codicon-trash"}
+                disabled={!has_annotations}
+                onclick={on_clear}
+            />
+        </div>
+    }
+}
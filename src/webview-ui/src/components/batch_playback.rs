@@ -0,0 +1,172 @@
+use gloo::timers::callback::Interval;
+use stylist::yew::use_style;
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+use yewdux::{functional::use_selector, Dispatch};
+
+use crate::{
+    application_state::{
+        app_state::{AppState, PlaybackAction},
+        playback::Playback,
+    },
+    common::{CurrentlyViewing, ViewId},
+};
+
+use super::icon_button::IconButton;
+
+#[derive(PartialEq, Properties)]
+pub(crate) struct BatchPlaybackControlsProps {
+    pub view_id: ViewId,
+}
+
+#[function_component]
+pub(crate) fn BatchPlaybackControls(props: &BatchPlaybackControlsProps) -> Html {
+    let BatchPlaybackControlsProps { view_id } = props;
+    let view_id = *view_id;
+
+    let playback =
+        use_selector(move |state: &AppState| state.playback.borrow().get(view_id).clone());
+    let position = use_selector(move |state: &AppState| -> Option<(u32, u32)> {
+        match state.image_views.borrow().get_currently_viewing(view_id)? {
+            CurrentlyViewing::BatchItem(image_id) => state.batch_item_position(&image_id),
+            CurrentlyViewing::Image(_) => None,
+        }
+    });
+
+    {
+        let playing = playback.playing;
+        let interval_millis = playback.interval_millis();
+        use_effect_with(
+            (playing, interval_millis),
+            move |&(playing, interval_millis)| {
+                let interval = playing.then(|| {
+                    Interval::new(interval_millis, move || {
+                        Dispatch::<AppState>::global().apply(PlaybackAction::Step(view_id));
+                    })
+                });
+                move || drop(interval)
+            },
+        );
+    }
+
+    let style = use_style!(
+        r#"
+        display: flex;
+        flex-direction: row;
+        align-items: center;
+        gap: 4px;
+        padding: 2px 4px;
+        background-color: var(--vscode-editor-background);
+        opacity: 0.9;
+
+        input[type="range"] {
+            flex-grow: 1;
+        }
+        input[type="number"] {
+            width: 3.5em;
+        }
+        .index {
+            font-size: 0.75rem;
+            white-space: nowrap;
+            font-variant-numeric: tabular-nums;
+        }
+        "#,
+    );
+    let currently_selected_style = use_style!(
+        r#"
+        background-color: var(--vscode-button-background);
+        :hover {
+            background-color: var(--vscode-button-background);
+        }
+        "#,
+    );
+
+    let Some((index, batch_size)) = *position else {
+        return html! {};
+    };
+
+    let dispatch = Dispatch::<AppState>::global();
+
+    let play_pause_button = if playback.playing {
+        html! {
+            <IconButton
+                aria_label={"Pause"}
+                title={"Pause"}
+                icon={"codicon codicon-debug-pause"}
+                onclick={dispatch.apply_callback(move |_| PlaybackAction::Pause(view_id))}
+            />
+        }
+    } else {
+        html! {
+            <IconButton
+                aria_label={"Play"}
+                title={"Play"}
+                icon={"codicon codicon-play"}
+                onclick={dispatch.apply_callback(move |_| PlaybackAction::Play(view_id))}
+            />
+        }
+    };
+
+    let loop_button = {
+        let looping = playback.looping;
+        html! {
+            <IconButton
+                class={classes!(looping.then_some(currently_selected_style))}
+                aria_label={"Loop"}
+                title={"Loop"}
+                icon={"codicon codicon-sync"}
+                onclick={dispatch.apply_callback(move |_| PlaybackAction::SetLoop(view_id, !looping))}
+            />
+        }
+    };
+
+    let on_seek = dispatch.apply_callback(move |ev: InputEvent| {
+        let value = ev
+            .target()
+            .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+            .and_then(|input| input.value().parse::<u32>().ok())
+            .unwrap_or(index);
+        PlaybackAction::Seek(view_id, value)
+    });
+
+    let current_fps = playback.fps;
+    let on_fps_change = dispatch.apply_callback(move |ev: Event| {
+        let fps = ev
+            .target()
+            .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+            .and_then(|input| input.value().parse::<f64>().ok())
+            .filter(|fps| fps.is_finite())
+            .map(|fps| fps.clamp(Playback::MIN_FPS, Playback::MAX_FPS))
+            .unwrap_or(current_fps);
+        PlaybackAction::SetFps(view_id, fps)
+    });
+
+    html! {
+        <div class={style}>
+            {play_pause_button}
+            <input
+                type="range"
+                min="0"
+                max={batch_size.saturating_sub(1).to_string()}
+                step="1"
+                value={index.to_string()}
+                oninput={on_seek}
+                onkeydown={|ev: KeyboardEvent| { ev.stop_propagation(); }}
+            />
+            <span class="index">{format!("{} / {}", index, batch_size.saturating_sub(1))}</span>
+            <div class="vscode-textfield" title="Frames per second">
+                <input
+                    type="number"
+                    min={Playback::MIN_FPS.to_string()}
+                    max={Playback::MAX_FPS.to_string()}
+                    step="any"
+                    value={playback.fps.to_string()}
+                    onchange={on_fps_change}
+                    onkeydown={|ev: KeyboardEvent| { ev.stop_propagation(); }}
+                />
+            </div>
+            <span class="index">{"fps"}</span>
+            {loop_button}
+        </div>
+    }
+}
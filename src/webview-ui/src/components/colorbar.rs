@@ -18,6 +18,9 @@ pub struct ColorbarProps {
     pub max: f32,
     pub clip_min: Option<f32>,
     pub clip_max: Option<f32>,
+    /// Number of images sharing the clip range through a link group, 0 if not linked.
+    #[prop_or_default]
+    pub linked: usize,
 }
 
 #[function_component]
@@ -28,6 +31,7 @@ pub fn Colorbar(props: &ColorbarProps) -> Html {
         max,
         clip_min,
         clip_max,
+        linked,
     } = props;
 
     let clip_min = clip_min.unwrap_or(*min);
@@ -233,6 +237,15 @@ pub fn Colorbar(props: &ColorbarProps) -> Html {
             transform: scale(1.2);
         }
 
+        .linked {
+            position: absolute;
+            top: -1.5em;
+            left: 0;
+            right: 0;
+            text-align: center;
+            user-select: none;
+        }
+
         "#,
     );
     let colorbar_ref = use_selector(|state: &AppState| {
@@ -380,6 +393,11 @@ pub fn Colorbar(props: &ColorbarProps) -> Html {
         html! {
             <div class={colorbar_style} style={vars} data-dragging={if dragging1 || dragging2 { "true" } else { "false" }}>
                 <div class="colorbar-container">
+                    if *linked > 0 {
+                        <div class="linked" title={format!("Range shared by {} linked images", linked)}>
+                            <i class="codicon codicon-link"></i>
+                        </div>
+                    }
                     <div class="bound-text" style="--position: 0;">
                         <div>{format!("{:.5}", float_pretty_print::PrettyPrintFloat(*min as f64))}</div>
                     </div>
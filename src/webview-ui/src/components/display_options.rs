@@ -19,7 +19,7 @@ pub(crate) struct DisplayOptionProps {
     pub drawing_context: DrawingContext,
 }
 
-mod features {
+pub(crate) mod features {
     use enumset::{EnumSet, EnumSetType};
 
     use crate::common::{Channels, Datatype, ImageInfo};
@@ -38,6 +38,7 @@ mod features {
         Segmentation,
         Heatmap,
         IgnoreAlpha,
+        NormalizePerBatchItem,
     }
 
     #[rustfmt::skip]
@@ -58,7 +59,7 @@ mod features {
         let binary_features = EnumSet::only(Feature::Segmentation);
         let no_additional_features = EnumSet::empty();
 
-        for_all | match (channels, datatype) {
+        let features = for_all | match (channels, datatype) {
             (Channels::One, Datatype::Uint8) => integer_gray_features,
             (Channels::One, Datatype::Uint16) => integer_gray_features,
             (Channels::One, Datatype::Uint32) => integer_gray_features,
@@ -91,8 +92,15 @@ mod features {
             (Channels::Four, Datatype::Int16) => rgba_features,
             (Channels::Four, Datatype::Int32) => rgba_features,
             (Channels::Four, Datatype::Bool) => bool_rgb_features,
-        } 
+        };
 
+        // the min and max of the batch items are only used to stretch the values
+        let stretch_features = Feature::HighContrast | Feature::Heatmap;
+        if entry.batch_info.is_some() && !features.is_disjoint(stretch_features) {
+            features | Feature::NormalizePerBatchItem
+        } else {
+            features
+        }
     }
 }
 
@@ -254,6 +262,18 @@ pub(crate) fn DisplayOption(props: &DisplayOptionProps) -> Html {
             onclick={make_drawing_options_update(UpdateDrawingOptions::IgnoreAlpha(!drawing_options.ignore_alpha))}
         />
     };
+    let normalize_per_batch_item_button = html! {
+        <IconButton
+            class={classes!(
+                base_style.clone(),
+                if drawing_options.normalize_per_batch_item { currently_selected_style.clone() } else { default_style.clone() })
+            }
+            aria_label={"Normalize Per Batch Item"}
+            title={"Normalize Per Batch Item (off: across the batch)"}
+            icon={"codicon codicon-layers"}
+            onclick={make_drawing_options_update(UpdateDrawingOptions::NormalizePerBatchItem(!drawing_options.normalize_per_batch_item))}
+        />
+    };
     let heatmap_button = html! {
         <IconButton
             class={classes!(
@@ -337,6 +357,9 @@ pub(crate) fn DisplayOption(props: &DisplayOptionProps) -> Html {
         buttons.push(segmentation_button);
         buttons.push(edges_button);
     }
+    if features.contains(features::Feature::NormalizePerBatchItem) {
+        buttons.push(normalize_per_batch_item_button);
+    }
     // if features.contains(features::Feature::Transpose) {
     //     buttons.push(transpose_button);
     // }
@@ -0,0 +1,50 @@
+use stylist::yew::use_style;
+use yew::prelude::*;
+use yewdux::use_selector_with_deps;
+
+use crate::{application_state::app_state::AppState, common::ViewableObjectId};
+
+#[derive(PartialEq, Properties)]
+pub(crate) struct ImageErrorMessageProps {
+    pub image_id: ViewableObjectId,
+}
+
+/// The last error of the image, if it has one.
+#[function_component]
+pub(crate) fn ImageErrorMessage(props: &ImageErrorMessageProps) -> Html {
+    let ImageErrorMessageProps { image_id } = props;
+
+    let error = use_selector_with_deps(
+        |state: &AppState, image_id| {
+            state
+                .image_errors
+                .borrow()
+                .get(image_id)
+                .map(ToString::to_string)
+        },
+        image_id.clone(),
+    );
+
+    let style = use_style!(
+        r#"
+        display: flex;
+        align-items: flex-start;
+        gap: 4px;
+        padding: 2px 5px;
+        font-size: 0.75rem;
+        color: var(--vscode-errorForeground);
+        overflow-wrap: anywhere;
+        "#,
+    );
+
+    let Some(error) = error.as_ref() else {
+        return html! {};
+    };
+
+    html! {
+        <div class={style} title={error.clone()}>
+            <span class="codicon codicon-error" />
+            <span>{error.clone()}</span>
+        </div>
+    }
+}
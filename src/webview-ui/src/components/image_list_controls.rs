@@ -0,0 +1,120 @@
+use stylist::yew::use_style;
+use yew::prelude::*;
+use yewdux::{use_selector, Dispatch};
+
+use crate::application_state::{
+    app_state::{AppState, UiAction},
+    image_list::{ImageListGrouping, ImageListSort},
+};
+
+fn chevron_icon() -> Html {
+    html! {
+        <span class="chevron-icon">
+            <svg width="16" height="16" viewBox="0 0 16 16" xmlns="http://www.w3.org/2000/svg" fill="currentColor">
+                <path
+                    fill-rule="evenodd"
+                    clip-rule="evenodd"
+                    d="M7.976 10.072l4.357-4.357.62.618L8.284 11h-.618L3 6.333l.619-.618 4.357 4.357z"
+                />
+            </svg>
+        </span>
+    }
+}
+
+/// Filter box, sorting and grouping of the image list.
+#[function_component]
+pub(crate) fn ImageListControls() -> Html {
+    let options = use_selector(|state: &AppState| state.image_list.clone());
+    let dispatch = Dispatch::<AppState>::global();
+
+    let on_filter_input = dispatch.apply_callback(|event: InputEvent| {
+        let input: web_sys::HtmlInputElement = event.target_unchecked_into();
+        UiAction::SetImageListFilter(input.value())
+    });
+    let on_sort_change = dispatch.apply_callback(|event: Event| {
+        let select: web_sys::HtmlSelectElement = event.target_unchecked_into();
+        let index = select.selected_index().max(0) as usize;
+        UiAction::SetImageListSort(ImageListSort::ALL.get(index).copied().unwrap_or_default())
+    });
+    let on_grouping_change = dispatch.apply_callback(|event: Event| {
+        let select: web_sys::HtmlSelectElement = event.target_unchecked_into();
+        let index = select.selected_index().max(0) as usize;
+        UiAction::SetImageListGrouping(
+            ImageListGrouping::ALL
+                .get(index)
+                .copied()
+                .unwrap_or_default(),
+        )
+    });
+
+    let sort_options = ImageListSort::ALL.iter().map(|sort| {
+        html! {
+            <option selected={*sort == options.sort}>{sort.name()}</option>
+        }
+    });
+    let grouping_options = ImageListGrouping::ALL.iter().map(|grouping| {
+        html! {
+            <option selected={*grouping == options.grouping}>{grouping.name()}</option>
+        }
+    });
+
+    let style = use_style!(
+        r#"
+        display: flex;
+        flex-direction: column;
+        gap: 4px;
+        padding: 4px;
+        border-bottom: 1px var(--vscode-panel-border) solid;
+
+        .filter {
+            box-sizing: border-box;
+            width: 100%;
+            padding: 3px 6px;
+            color: var(--vscode-input-foreground);
+            background-color: var(--vscode-input-background);
+            border: 1px solid var(--vscode-input-border, transparent);
+            border-radius: 2px;
+        }
+        .filter:focus {
+            outline: none;
+            border-color: var(--vscode-focusBorder);
+        }
+
+        .row {
+            display: flex;
+            flex-direction: row;
+            gap: 4px;
+        }
+        .row > .vscode-select {
+            flex: 1;
+            min-width: 0;
+        }
+        "#
+    );
+
+    html! {
+        <div class={style}>
+            <input
+                class="filter"
+                type="search"
+                placeholder="Filter by name, shape, dtype or info"
+                value={options.filter.clone()}
+                oninput={on_filter_input}
+            />
+            <div class="row">
+                <div class="vscode-select" title="Sort by">
+                    <select onchange={on_sort_change}>
+                        {for sort_options}
+                    </select>
+                    {chevron_icon()}
+                </div>
+                <div class="vscode-select" title="Group by">
+                    <select onchange={on_grouping_change}>
+                        {for grouping_options}
+                    </select>
+                    {chevron_icon()}
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -5,7 +5,7 @@ use yewdux::{use_selector, Dispatch};
 
 use crate::{
     application_state::{
-        app_state::{AppState, OverlayAction, UiAction},
+        app_state::{AppState, LinkAction, OverlayAction, UiAction},
         images::DrawingContext,
     },
     common::{Image, MinimalImageInfo, ValueVariableKind, ViewId},
@@ -13,7 +13,7 @@ use crate::{
     vscode::vscode_requests::VSCodeRequests,
 };
 
-use super::icon_button::IconButton;
+use super::{icon_button::IconButton, image_error::ImageErrorMessage, thumbnail::Thumbnail};
 
 fn make_info_row(label: &str, value: &str) -> Html {
     html! {
@@ -209,7 +209,7 @@ pub(crate) fn ImageListItem(props: &ImageListItemProps) -> Html {
                     }
                 }
             })}
-            class={remove_overlay_style}
+            class={remove_overlay_style.clone()}
         />
     };
     let set_remove_overlay_button = if *is_overlay {
@@ -218,6 +218,68 @@ pub(crate) fn ImageListItem(props: &ImageListItemProps) -> Html {
         overlay_button
     };
 
+    // linking is relative to the image in the primary view
+    let link_state = use_selector({
+        let image_id = image_id.clone();
+        move |state: &AppState| {
+            let cv = state
+                .image_views
+                .borrow()
+                .get_currently_viewing(ViewId::Primary)?;
+            if cv.id() == &image_id {
+                return None;
+            }
+            let link_groups = state.link_groups.borrow();
+            let group = link_groups.group_of(&image_id);
+            Some(group.is_some() && group == link_groups.group_of(cv.id()))
+        }
+    });
+    let link_button = html! {
+        <IconButton
+            aria_label={"Link Drawing Options"}
+            title={"Link drawing options with the current image"}
+            icon={"codicon codicon-link"}
+            onclick={Callback::from({
+                let image_id = image_id.clone();
+                move |event: MouseEvent| {
+                    event.prevent_default();
+                    event.stop_propagation();
+                    let state = Dispatch::<AppState>::global().get();
+                    let cv = state.image_views.borrow().get_currently_viewing(ViewId::Primary);
+                    if let Some(cv) = cv {
+                        Dispatch::<AppState>::global().apply(LinkAction::Link {
+                            image_id: image_id.clone(),
+                            other: cv.id().clone(),
+                        });
+                    }
+                }
+            })}
+        />
+    };
+    let unlink_button = html! {
+        <IconButton
+            aria_label={"Unlink Drawing Options"}
+            title={"Unlink drawing options"}
+            icon={"codicon codicon-link"}
+            onclick={Callback::from({
+                let image_id = image_id.clone();
+                move |event: MouseEvent| {
+                    event.prevent_default();
+                    event.stop_propagation();
+                    Dispatch::<AppState>::global().apply(LinkAction::Unlink {
+                        image_id: image_id.clone(),
+                    });
+                }
+            })}
+            class={remove_overlay_style.clone()}
+        />
+    };
+    let link_unlink_button = match *link_state {
+        Some(true) => unlink_button,
+        Some(false) => link_button,
+        None => html!(<></>),
+    };
+
     let item_style = use_style!(
         r#"
 
@@ -244,12 +306,16 @@ pub(crate) fn ImageListItem(props: &ImageListItemProps) -> Html {
             class={item_style.clone()}
         >
             <div class="item-label-container">
+                <Thumbnail image_id={image_id.clone()} />
                 {set_remove_overlay_button}
                 {pin_unpin_button}
+                {link_unlink_button}
                 <label class="item-label" title={expression.clone()}>{&expression}</label>
                 if *value_variable_kind == ValueVariableKind::Expression {{edit_button}} else {<></>}
             </div>
 
+            <ImageErrorMessage image_id={image_id.clone()} />
+
             <div class={info_grid_style.clone()}>
                 {for rows}
             </div>
@@ -5,9 +5,13 @@ use yew::prelude::*;
 use yewdux::prelude::*;
 
 use crate::{
-    application_state::{app_state::{AppState, StoreAction}, images::DrawingContext},
-    common::{CurrentlyViewing, Image, ViewId},
-    components::image_list_item::ImageListItem,
+    application_state::{
+        app_state::{AppState, StoreAction},
+        image_list::{arrange_images, ImageListGrouping},
+        images::DrawingContext,
+    },
+    common::{CurrentlyViewing, Image, ViewId, ViewableObjectId},
+    components::{image_list_controls::ImageListControls, image_list_item::ImageListItem},
 };
 
 #[derive(Debug)]
@@ -127,7 +131,9 @@ pub(crate) fn ImageSelectionList(props: &ImageSelectionListProps) -> Html {
 
     let active_session =
         use_selector(|state: &AppState| state.sessions.borrow().active_session.clone());
+    let sessions = use_selector(|state: &AppState| state.sessions.clone());
     let images_data = use_selector(|state: &AppState| state.images.clone());
+    let list_options = use_selector(|state: &AppState| state.image_list.clone());
     let selected_entry = use_selector(|state: &AppState| {
         state
             .image_views
@@ -137,69 +143,102 @@ pub(crate) fn ImageSelectionList(props: &ImageSelectionListProps) -> Html {
 
     let mut counter = 0..;
 
-    #[allow(unstable_name_collisions)]
-    // `intersperse` is currently used from the `itertools` crate, and we get a warning about it.
-    let entries = images_data
-        .borrow()
-        .iter()
-        .filter(|(id, _)| {
-            if let Some(active_session) = active_session.as_ref() {
-                id.session_id() == active_session
+    let images = images_data.borrow();
+    // grouping by session lists the images of all sessions
+    let images_of_sessions = images.iter().filter(|(id, _)| {
+        list_options.grouping == ImageListGrouping::Session
+            || active_session.as_ref().as_ref() == Some(id.session_id())
+    });
+    let groups = arrange_images(
+        images_of_sessions,
+        &list_options,
+        |id| images.is_pinned(id),
+        |id| {
+            let session_id = id.session_id();
+            sessions
+                .borrow()
+                .session_name
+                .get(session_id)
+                .cloned()
+                .unwrap_or_else(|| session_id.0.clone())
+        },
+    );
+
+    let make_entry = |id: &ViewableObjectId, info: &Image| {
+        let onclick = {
+            let dispatch = Dispatch::<AppState>::global();
+
+            dispatch.apply_callback({
+                let id = id.clone();
+                move |_| StoreAction::SetImageToView(id.clone(), ViewId::Primary)
+            })
+        };
+
+        let cv = {
+            let cv = selected_entry.as_ref().as_ref().cloned();
+            let current_id = cv.as_ref().map(|cv| cv.id());
+            if current_id == Some(id) {
+                cv
             } else {
-                false
+                None
             }
-        })
-        .map(|(id, info)| {
-            let onclick = {
-                let dispatch = Dispatch::<AppState>::global();
-
-                dispatch.apply_callback({
-                    let id = id.clone();
-                    move |_| StoreAction::SetImageToView(id.clone(), ViewId::Primary)
-                })
-            };
-
-            let cv = {
-                let cv = selected_entry.as_ref().as_ref().cloned();
-                let current_id = cv.as_ref().map(|cv| cv.id());
-                if current_id == Some(id) {
-                    cv
-                } else {
-                    None
-                }
-            };
-
-            let is_pinned = images_data.borrow().is_pinned(id);
-            let is_selected = cv.is_some() && cv.as_ref().map(|cv| cv.id()) == Some(id);
-
-            html! {
-                <div
-                    key={id.as_unique_string()}
-                    aria-selected={if is_selected {"true"} else {"false"}}
-                >
-                    <ImageItemWrapper
-                        container_ref={node_ref.clone()}
-                        info={info.clone()}
-                        currently_viewing={cv}
-                        is_pinned={is_pinned}
-                        onclick={onclick}
-                    />
+        };
+
+        let is_pinned = images.is_pinned(id);
+        let is_selected = cv.is_some() && cv.as_ref().map(|cv| cv.id()) == Some(id);
+
+        html! {
+            <div
+                key={id.as_unique_string()}
+                aria-selected={if is_selected {"true"} else {"false"}}
+            >
+                <ImageItemWrapper
+                    container_ref={node_ref.clone()}
+                    info={info.clone()}
+                    currently_viewing={cv}
+                    is_pinned={is_pinned}
+                    onclick={onclick}
+                />
+            </div>
+        }
+    };
+
+    let mut entries = Vec::new();
+    for group in groups {
+        if let Some(title) = group.title {
+            entries.push(html! {
+                <div key={format!("group-{}", title)} class="group-title" title={title.clone()}>
+                    {title.clone()}
                 </div>
-            }
-        })
-        .intersperse_with(|| {
-            html! { <hr
-            key={format!("divider-{}", counter.next().unwrap())}
-            class={css!("margin: 0; border-color: var(--vscode-menu-border);")} />
-            }
-        })
-        .collect::<Vec<_>>();
+            });
+        }
+        #[allow(unstable_name_collisions)]
+        // `intersperse` is currently used from the `itertools` crate, and we get a warning about it.
+        let items = group
+            .images
+            .into_iter()
+            .map(|(id, info)| make_entry(id, info))
+            .intersperse_with(|| {
+                html! { <hr
+                key={format!("divider-{}", counter.next().unwrap())}
+                class={css!("margin: 0; border-color: var(--vscode-menu-border);")} />
+                }
+            })
+            .collect::<Vec<_>>();
+        entries.extend(items);
+    }
+    if entries.is_empty() && !list_options.filter.trim().is_empty() {
+        entries.push(html! {
+            <div key="no-match" class="no-match">{"No images match the filter"}</div>
+        });
+    }
 
     let style = use_style!(
         r#"
         overflow-y: auto;
         overflow-x: hidden;
-        height: 100%;
+        flex: 1;
+        min-height: 0;
 
         .inner {
             width: 100%;
@@ -214,14 +253,35 @@ pub(crate) fn ImageSelectionList(props: &ImageSelectionListProps) -> Html {
         .inner > div[aria-selected="true"] * {
             color: var(--vscode-list-activeSelectionForeground);
         }
+
+        .inner > .group-title {
+            padding: 6px 5px 2px 5px;
+            font-weight: bold;
+            text-transform: uppercase;
+            font-size: 0.85em;
+            white-space: nowrap;
+            overflow: hidden;
+            text-overflow: ellipsis;
+            border-bottom: 1px solid var(--vscode-menu-border);
+            user-select: none;
+        }
+
+        .inner > .no-match {
+            padding: 10px 5px;
+            opacity: 0.7;
+            user-select: none;
+        }
     "#,
     );
 
     html! {
-        <div class={style} ref={node_ref}>
-            <div class={"inner"}>
-                {for entries}
+        <>
+            <ImageListControls />
+            <div class={style} ref={node_ref}>
+                <div class={"inner"}>
+                    {for entries}
+                </div>
             </div>
-        </div>
+        </>
     }
 }
@@ -0,0 +1,105 @@
+use std::rc::Rc;
+
+use stylist::yew::use_style;
+use yew::prelude::*;
+
+use crate::{
+    common::{pixel_value::PixelValueFormat, ImageStatistics},
+    math_utils::image_calculations::HISTOGRAM_BINS,
+};
+
+const HISTOGRAM_HEIGHT: u32 = 40;
+const CHANNEL_COLORS: [&str; 4] = [
+    "var(--vscode-charts-red)",
+    "var(--vscode-charts-green)",
+    "var(--vscode-charts-blue)",
+    "var(--vscode-foreground)",
+];
+
+fn histogram_points(histogram: &[u32]) -> String {
+    let highest = histogram.iter().copied().max().unwrap_or_default().max(1) as f32;
+    histogram
+        .iter()
+        .enumerate()
+        .map(|(bin, count)| {
+            let y = HISTOGRAM_HEIGHT as f32 * (1.0 - *count as f32 / highest);
+            format!("{},{:.2} {},{:.2}", bin, y, bin + 1, y)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(PartialEq, Properties)]
+pub(crate) struct ImageStatisticsInfoProps {
+    pub statistics: Rc<ImageStatistics>,
+}
+
+/// Mean and standard deviation of each channel, over their histograms.
+#[function_component]
+pub(crate) fn ImageStatisticsInfo(props: &ImageStatisticsInfoProps) -> Html {
+    let ImageStatisticsInfoProps { statistics } = props;
+
+    let format = PixelValueFormat::default();
+    let num_channels = statistics.histograms.len();
+    let color = |channel: usize| {
+        if num_channels == 1 {
+            CHANNEL_COLORS[3]
+        } else {
+            CHANNEL_COLORS[channel.min(3)]
+        }
+    };
+
+    let histograms = statistics
+        .histograms
+        .iter()
+        .enumerate()
+        .map(|(channel, histogram)| {
+            html! {
+                <polyline
+                    points={histogram_points(histogram)}
+                    fill="none"
+                    stroke={color(channel)}
+                    stroke-width="1"
+                    vector-effect="non-scaling-stroke"
+                />
+            }
+        });
+    let rows = statistics.mean.iter().zip(&statistics.std).enumerate().map(
+        |(channel, (mean, std))| {
+            html! {
+                <div class="row" style={format!("color: {};", color(channel))}>
+                    {format!("μ {} σ {}", format.format_float(*mean), format.format_float(*std))}
+                </div>
+            }
+        },
+    );
+
+    let style = use_style!(
+        r#"
+        box-sizing: border-box;
+        width: 100%;
+        padding: 0.5rem;
+        font-size: 0.75rem;
+        line-height: 1rem;
+
+        svg {
+            width: 100%;
+            border-bottom: 1px solid var(--vscode-panel-border);
+        }
+        "#,
+    );
+
+    html! {
+        <div class={style}>
+            <svg
+                height={HISTOGRAM_HEIGHT.to_string()}
+                viewBox={format!("0 0 {} {}", HISTOGRAM_BINS, HISTOGRAM_HEIGHT)}
+                preserveAspectRatio="none"
+                xmlns="http://www.w3.org/2000/svg">
+                <title>{"Histogram between the min and the max"}</title>
+                {for histograms}
+            </svg>
+            {for rows}
+        </div>
+    }
+}
@@ -0,0 +1,394 @@
+use std::rc::Rc;
+
+use itertools::Itertools;
+use stylist::yew::use_style;
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+use yewdux::{functional::use_selector, Dispatch};
+
+use crate::{
+    application_state::{
+        app_state::{AppState, LineProfileAction},
+        images::{DrawingContext, ImageAvailability},
+        line_profile::{sample_line, Interpolation, ProfileSample},
+    },
+    common::{clipboard::copy_to_clipboard, ViewId},
+};
+
+use super::icon_button::IconButton;
+
+const WIDTH: f32 = 640.0;
+const HEIGHT: f32 = 180.0;
+const MARGIN_LEFT: f32 = 56.0;
+const MARGIN_RIGHT: f32 = 12.0;
+const MARGIN_TOP: f32 = 10.0;
+const MARGIN_BOTTOM: f32 = 28.0;
+const NUM_TICKS: usize = 5;
+
+const CHANNEL_COLORS: [&[&str]; 4] = [
+    &["var(--vscode-foreground)"],
+    &["var(--vscode-foreground)", "gray"],
+    &["#e05050", "#50c050", "#5080f0"],
+    &["#e05050", "#50c050", "#5080f0", "gray"],
+];
+
+fn format_number(value: f32) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e9 {
+        format!("{}", value as i64)
+    } else {
+        let formatted = format!("{:.3}", value);
+        formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    }
+}
+
+fn samples_to_csv(samples: &[ProfileSample], num_channels: usize) -> String {
+    let header = ["distance", "x", "y"]
+        .into_iter()
+        .map(String::from)
+        .chain((0..num_channels).map(|c| format!("c{}", c)))
+        .join(",");
+    let rows = samples.iter().map(|sample| {
+        [sample.distance, sample.position.x, sample.position.y]
+            .into_iter()
+            .chain(sample.values.to_array().into_iter().take(num_channels))
+            .map(|v| v.to_string())
+            .join(",")
+    });
+    std::iter::once(header).chain(rows).join("\n") + "\n"
+}
+
+struct Scale {
+    domain: (f32, f32),
+    range: (f32, f32),
+}
+
+impl Scale {
+    fn apply(&self, value: f32) -> f32 {
+        let (d0, d1) = self.domain;
+        let (r0, r1) = self.range;
+        r0 + (value - d0) / (d1 - d0) * (r1 - r0)
+    }
+
+    fn ticks(&self) -> impl Iterator<Item = f32> + '_ {
+        let (d0, d1) = self.domain;
+        (0..NUM_TICKS).map(move |i| d0 + (d1 - d0) * i as f32 / (NUM_TICKS - 1) as f32)
+    }
+}
+
+#[derive(PartialEq, Properties)]
+pub(crate) struct LineProfilePlotProps {
+    pub view_id: ViewId,
+}
+
+#[function_component]
+pub(crate) fn LineProfilePlot(props: &LineProfilePlotProps) -> Html {
+    let LineProfilePlotProps { view_id } = props;
+    let view_id = *view_id;
+
+    let profile = use_selector(move |state: &AppState| {
+        let line_profiles = state.line_profiles.borrow();
+        (
+            line_profiles.enabled,
+            line_profiles.interpolation,
+            line_profiles.get(view_id).clone(),
+        )
+    });
+    let image = use_selector(move |state: &AppState| {
+        let cv = state.image_views.borrow().get_currently_viewing(view_id)?;
+        let batch_item = state
+            .drawing_options
+            .borrow()
+            .get(cv.id(), &DrawingContext::BaseImage)
+            .and_then(|d| d.batch_item);
+        Some((state.image_cache.borrow().get(cv.id()), batch_item))
+    });
+
+    let (enabled, interpolation, view_profile) = profile.as_ref().clone();
+    let line = view_profile.line;
+
+    let samples = use_memo(
+        (line, interpolation, Rc::clone(&image)),
+        |(line, interpolation, image)| -> Option<(Vec<ProfileSample>, usize)> {
+            let line = line.as_ref()?;
+            let (availability, batch_item) = image.as_ref().as_ref()?;
+            let ImageAvailability::Available(texture) = availability else {
+                return None;
+            };
+            let texture = texture.borrow();
+            let bytes = texture.bytes.get(&batch_item.unwrap_or(0))?;
+            let num_channels = u32::from(texture.info.channels) as usize;
+            Some((
+                sample_line(&texture.info, bytes, line, *interpolation),
+                num_channels,
+            ))
+        },
+    );
+
+    let svg_ref = use_node_ref();
+
+    let style = use_style!(
+        r#"
+        box-sizing: border-box;
+        padding: 4px;
+        background-color: var(--vscode-sideBar-background);
+        border-top: 1px solid var(--vscode-panel-border);
+        font-size: 0.75rem;
+        overflow-x: auto;
+
+        .header {
+            display: flex;
+            flex-direction: row;
+            align-items: center;
+            gap: 4px;
+            white-space: nowrap;
+        }
+        .title {
+            margin-right: auto;
+        }
+        .empty {
+            opacity: 0.7;
+        }
+        svg {
+            display: block;
+            font-family: var(--vscode-editor-font-family);
+            font-size: 10px;
+        }
+        svg .axis {
+            stroke: var(--vscode-panel-border);
+        }
+        svg text {
+            fill: var(--vscode-foreground);
+        }
+        svg .cursor {
+            stroke: var(--vscode-focusBorder);
+        }
+        "#,
+    );
+
+    if !enabled {
+        return html! {};
+    }
+
+    let dispatch = Dispatch::<AppState>::global();
+
+    let on_interpolation_change = dispatch.apply_callback(move |e: Event| {
+        let value = e
+            .target()
+            .and_then(|t| t.dyn_into::<web_sys::HtmlSelectElement>().ok())
+            .map(|select| select.value())
+            .unwrap_or_default();
+        let interpolation = Interpolation::ALL
+            .into_iter()
+            .find(|i| i.name() == value)
+            .unwrap_or_default();
+        LineProfileAction::SetInterpolation(interpolation)
+    });
+    let interpolation_options = Interpolation::ALL
+        .iter()
+        .map(|i| {
+            html! {
+                <option value={i.name()} selected={*i == interpolation}>{i.name()}</option>
+            }
+        })
+        .collect::<Html>();
+
+    let on_copy_csv = {
+        let samples = Rc::clone(&samples);
+        Callback::from(move |_: MouseEvent| {
+            if let Some((samples, num_channels)) = samples.as_ref() {
+                copy_to_clipboard(samples_to_csv(samples, *num_channels));
+            }
+        })
+    };
+
+    let header = html! {
+        <div class="header">
+            <span class="title">
+                {"Line profile"}
+                {line.map(|line| format!(" ({} px)", format_number(line.length()))).unwrap_or_default()}
+            </span>
+            <div class="vscode-select" title="Interpolation">
+                <select onchange={on_interpolation_change}>
+                    {interpolation_options}
+                </select>
+            </div>
+            <IconButton
+                aria_label={"Copy as CSV"}
+                title={"Copy as CSV"}
+                icon={"codicon codicon-copy"}
+                disabled={samples.is_none()}
+                onclick={on_copy_csv}
+            />
+            <IconButton
+                aria_label={"Clear line"}
+                title={"Clear line"}
+                icon={"codicon codicon-close"}
+                disabled={line.is_none()}
+                onclick={dispatch.apply_callback(move |_| LineProfileAction::Clear(view_id))}
+            />
+        </div>
+    };
+
+    let Some((samples, num_channels)) = samples.as_ref() else {
+        return html! {
+            <div class={style}>
+                {header}
+                <div class="empty">{"Drag on the image to draw a line"}</div>
+            </div>
+        };
+    };
+    let num_channels = *num_channels;
+    let length = samples.last().map_or(0.0, |s| s.distance);
+
+    let (min, max) = samples
+        .iter()
+        .flat_map(|s| s.values.to_array().into_iter().take(num_channels))
+        .filter(|v| v.is_finite())
+        .minmax()
+        .into_option()
+        .unwrap_or((0.0, 1.0));
+    let (min, max) = if min == max {
+        (min - 0.5, max + 0.5)
+    } else {
+        (min, max)
+    };
+
+    let x_scale = Scale {
+        domain: (0.0, length.max(f32::EPSILON)),
+        range: (MARGIN_LEFT, WIDTH - MARGIN_RIGHT),
+    };
+    let y_scale = Scale {
+        domain: (min, max),
+        range: (HEIGHT - MARGIN_BOTTOM, MARGIN_TOP),
+    };
+
+    let polylines = (0..num_channels)
+        .map(|c| {
+            let points = samples
+                .iter()
+                .filter(|s| s.values[c].is_finite())
+                .map(|s| {
+                    format!(
+                        "{},{}",
+                        x_scale.apply(s.distance),
+                        y_scale.apply(s.values[c])
+                    )
+                })
+                .join(" ");
+            html! {
+                <polyline
+                    points={points}
+                    fill="none"
+                    stroke={CHANNEL_COLORS[num_channels - 1][c]}
+                    stroke-width="1"
+                />
+            }
+        })
+        .collect::<Html>();
+
+    let x_ticks = x_scale
+        .ticks()
+        .map(|tick| {
+            let x = x_scale.apply(tick);
+            html! {
+                <>
+                    <line class="axis" x1={x.to_string()} x2={x.to_string()}
+                        y1={(HEIGHT - MARGIN_BOTTOM).to_string()}
+                        y2={(HEIGHT - MARGIN_BOTTOM + 4.0).to_string()} />
+                    <text x={x.to_string()} y={(HEIGHT - MARGIN_BOTTOM + 15.0).to_string()}
+                        text-anchor="middle">{format_number(tick)}</text>
+                </>
+            }
+        })
+        .collect::<Html>();
+    let y_ticks = y_scale
+        .ticks()
+        .map(|tick| {
+            let y = y_scale.apply(tick);
+            html! {
+                <>
+                    <line class="axis" x1={(MARGIN_LEFT - 4.0).to_string()}
+                        x2={MARGIN_LEFT.to_string()} y1={y.to_string()} y2={y.to_string()} />
+                    <text x={(MARGIN_LEFT - 6.0).to_string()} y={(y + 3.0).to_string()}
+                        text-anchor="end">{format_number(tick)}</text>
+                </>
+            }
+        })
+        .collect::<Html>();
+
+    let cursor_sample = view_profile.cursor.and_then(|t| {
+        let index = (t * samples.len().saturating_sub(1) as f32).round() as usize;
+        samples.get(index)
+    });
+    let cursor = cursor_sample.map(|sample| {
+        let x = x_scale.apply(sample.distance);
+        let values = sample
+            .values
+            .to_array()
+            .into_iter()
+            .take(num_channels)
+            .map(format_number)
+            .join(", ");
+        html! {
+            <>
+                <line class="cursor" x1={x.to_string()} x2={x.to_string()}
+                    y1={MARGIN_TOP.to_string()} y2={(HEIGHT - MARGIN_BOTTOM).to_string()} />
+                <text x={(WIDTH - MARGIN_RIGHT).to_string()} y={(MARGIN_TOP + 8.0).to_string()}
+                    text-anchor="end">
+                    {format!(
+                        "d: {}  x: {}, y: {}  ({})",
+                        format_number(sample.distance),
+                        format_number(sample.position.x),
+                        format_number(sample.position.y),
+                        values,
+                    )}
+                </text>
+            </>
+        }
+    });
+
+    let num_samples = samples.len();
+    let onmousemove = {
+        let svg_ref = svg_ref.clone();
+        dispatch.apply_callback(move |e: MouseEvent| {
+            let left = svg_ref
+                .cast::<web_sys::Element>()
+                .map_or(0.0, |svg| svg.get_bounding_client_rect().left() as f32);
+            let x = e.client_x() as f32 - left;
+            let t = ((x - MARGIN_LEFT) / (WIDTH - MARGIN_LEFT - MARGIN_RIGHT)).clamp(0.0, 1.0);
+            // snap to the nearest sample
+            let steps = num_samples.saturating_sub(1).max(1) as f32;
+            LineProfileAction::SetCursor(view_id, Some((t * steps).round() / steps))
+        })
+    };
+    let onmouseleave =
+        dispatch.apply_callback(move |_: MouseEvent| LineProfileAction::SetCursor(view_id, None));
+
+    html! {
+        <div class={style}>
+            {header}
+            <svg
+                ref={svg_ref}
+                width={WIDTH.to_string()}
+                height={HEIGHT.to_string()}
+                {onmousemove}
+                {onmouseleave}
+            >
+                <line class="axis"
+                    x1={MARGIN_LEFT.to_string()} x2={MARGIN_LEFT.to_string()}
+                    y1={MARGIN_TOP.to_string()} y2={(HEIGHT - MARGIN_BOTTOM).to_string()} />
+                <line class="axis"
+                    x1={MARGIN_LEFT.to_string()} x2={(WIDTH - MARGIN_RIGHT).to_string()}
+                    y1={(HEIGHT - MARGIN_BOTTOM).to_string()}
+                    y2={(HEIGHT - MARGIN_BOTTOM).to_string()} />
+                {x_ticks}
+                {y_ticks}
+                {polylines}
+                {cursor}
+            </svg>
+        </div>
+    }
+}
@@ -7,9 +7,12 @@ use yewdux::{prelude::Dispatch, use_selector};
 
 use crate::{
     application_state::{app_state::AppState, images::DrawingContext},
+    coloring::{calculate_color_matrix, calculate_normalized_channel_values},
     common::{pixel_value::PixelValue, AppMode, ViewId},
     components::{
-        main_toolbar::MainToolbar, sidebar::Sidebar, status_bar::StatusBar,
+        line_profile_plot::LineProfilePlot, main_toolbar::MainToolbar,
+        pixel_context_menu::use_pixel_context_menu, pixel_inspector::PixelInspector,
+        shortcuts_overlay::ShortcutsOverlay, sidebar::Sidebar, status_bar::StatusBar,
         view_container::ViewContainer,
     },
     mouse_events::PixelHoverHandler,
@@ -42,7 +45,7 @@ fn StatusBarWrapper(props: &StatusBarWrapperProps) -> Html {
     } = props;
 
     let pixel = use_state(|| Option::<UVec2>::None);
-    let pixel_value = use_state(|| Option::<PixelValue>::None);
+    let pixel_value = use_state(|| Option::<AttrValue>::None);
 
     use_effect({
         let pixel = pixel.clone();
@@ -67,17 +70,33 @@ fn StatusBarWrapper(props: &StatusBarWrapperProps) -> Html {
                             .and_then(|(pixel, image)| {
                                 image.map(|image| {
                                     let image = image.borrow();
-                                    let dispatch = Dispatch::<AppState>::global();
-                                    let batch_index = dispatch
-                                        .get()
+                                    let state = Dispatch::<AppState>::global().get();
+                                    let drawing_options = state
                                         .drawing_options
                                         .borrow()
                                         .get(&image.info.image_id, &DrawingContext::BaseImage)
-                                        .and_then(|d| d.batch_item)
-                                        .unwrap_or(0);
+                                        .cloned()
+                                        .unwrap_or_default();
+                                    let batch_index = drawing_options.batch_item.unwrap_or(0);
+                                    let format = &state.configuration.rendering.pixel_value_format;
 
                                     image.bytes.get(&batch_index).map(|bytes| {
-                                        PixelValue::from_image_info(&image.info, bytes, &pixel)
+                                        let pixel_value =
+                                            PixelValue::from_image_info(&image.info, bytes, &pixel);
+                                        let normalized_values = format.show_normalized.then(|| {
+                                            let coloring_factors = calculate_color_matrix(
+                                                &image.info,
+                                                &image.computed_info,
+                                                &drawing_options,
+                                            );
+                                            calculate_normalized_channel_values(
+                                                &pixel_value,
+                                                &coloring_factors,
+                                            )
+                                        });
+                                        let channels = pixel_value
+                                            .format_with(format, normalized_values.as_deref());
+                                        AttrValue::from(format!("({})", channels.join(", ")))
                                     })
                                 })
                             })
@@ -100,7 +119,7 @@ fn StatusBarWrapper(props: &StatusBarWrapperProps) -> Html {
     });
 
     html! {
-        <StatusBar pixel={*pixel} pixel_value={*pixel_value} />
+        <StatusBar view_id={*view_id} pixel={*pixel} pixel_value={(*pixel_value).clone()} />
     }
 }
 
@@ -130,6 +149,7 @@ pub(crate) fn Main(props: &MainProps) -> Html {
         .get_node_ref(*view_id)
         .clone();
     let app_mode = use_selector(|state: &AppState| state.app_mode);
+    use_pixel_context_menu(*view_id, Rc::clone(view_context));
     log::info!("app_mode: {:?}", app_mode);
 
     let main_style = use_style!(
@@ -137,11 +157,12 @@ pub(crate) fn Main(props: &MainProps) -> Html {
         display: grid;
         height: 100vh;
         grid-template-areas:
-            "sidebar main-toolbar"
-            "sidebar main"
-            "sidebar footer";
-        grid-template-rows: auto 1fr auto;
-        grid-template-columns: fit-content(200px) 1fr;
+            "sidebar main-toolbar main-toolbar"
+            "sidebar main inspector"
+            "sidebar line-profile inspector"
+            "sidebar footer footer";
+        grid-template-rows: auto 1fr auto auto;
+        grid-template-columns: fit-content(200px) 1fr auto;
   
         .main {
             grid-area: main;
@@ -163,6 +184,16 @@ pub(crate) fn Main(props: &MainProps) -> Html {
             grid-area: footer;
         }
 
+        .line-profile {
+            grid-area: line-profile;
+            min-width: 0;
+        }
+
+        .pixel-inspector {
+            grid-area: inspector;
+            min-height: 0;
+        }
+
         .view-container {
             width: 100%;
             height: 100%;
@@ -182,9 +213,16 @@ pub(crate) fn Main(props: &MainProps) -> Html {
             <div class={"main"}>
                 <ViewContainer node_ref={view_container_node_ref} class="view-container" view_id={ViewId::Primary}/>
             </div>
+            <div class={"line-profile"}>
+                <LineProfilePlot view_id={*view_id} />
+            </div>
+            <div class={"pixel-inspector"}>
+                <PixelInspector view_id={*view_id} view_context={view_context.clone()} />
+            </div>
             <div class={"status-bar"}>
                 <StatusBarWrapper view_id={*view_id} view_context={view_context.clone()} />
             </div>
+            <ShortcutsOverlay />
         </div>
     }
 }
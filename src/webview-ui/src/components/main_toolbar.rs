@@ -10,14 +10,20 @@ use yewdux::{prelude::use_selector, use_selector_with_deps, Dispatch};
 
 use crate::{
     application_state::{
-        app_state::{AppState, OverlayAction, StoreAction, UpdateGlobalDrawingOptions},
+        app_state::{
+            AppState, LineProfileAction, OverlayAction, StoreAction, UiAction,
+            UpdateDrawingOptions, UpdateGlobalDrawingOptions,
+        },
         images::DrawingContext,
         views::OverlayItem,
     },
     coloring::Coloring,
     colormap::ColorMapKind,
-    common::{AppMode, CurrentlyViewing, Image, SizeU32, ViewId},
-    components::{checkbox::Checkbox, display_options::DisplayOption, icon_button::IconButton},
+    common::{drawing_preset::DrawingPreset, AppMode, CurrentlyViewing, Image, SizeU32, ViewId},
+    components::{
+        annotation_toolbar::AnnotationToolbar, checkbox::Checkbox, display_options::DisplayOption,
+        icon_button::IconButton,
+    },
     vscode::vscode_requests::VSCodeRequests,
 };
 
@@ -125,11 +131,8 @@ pub fn OverlayMenuItem(props: &OverlayMenuItemProps) -> Html {
 
     let info = use_selector_with_deps(
         |state: &AppState, overlay: &OverlayItem| {
-            let images = state.images.borrow();
-            let image = images
-                .get(&overlay.id)
-                .unwrap_or_else(|| panic!("Image with id {:?} not found", overlay.id));
-            if let Image::Full(info) = image {
+            // the overlay image can be gone, e.g. when the images of its session are replaced
+            if let Some(Image::Full(info)) = state.images.borrow().get(&overlay.id) {
                 Some(info.clone())
             } else {
                 None
@@ -157,12 +160,11 @@ pub fn OverlayMenuItem(props: &OverlayMenuItemProps) -> Html {
 
     let overlay_expression = use_selector_with_deps(
         |state: &AppState, overlay_id| {
-            let images = state.images.borrow();
-            let info = images
+            state
+                .images
+                .borrow()
                 .get(overlay_id)
-                .unwrap_or_else(|| panic!("Image with id {:?} not found", overlay_id))
-                .minimal();
-            info.expression.clone()
+                .map(|image| image.minimal().expression.clone())
         },
         overlay.id.clone(),
     );
@@ -349,7 +351,9 @@ pub fn OverlayMenuItem(props: &OverlayMenuItemProps) -> Html {
     );
 
     // All hooks have been called. Now it's safe to return early.
-    let Some(info) = info.as_ref().as_ref() else {
+    let (Some(info), Some(overlay_expression)) =
+        (info.as_ref().as_ref(), overlay_expression.as_ref())
+    else {
         return html! {};
     };
 
@@ -376,7 +380,7 @@ pub fn OverlayMenuItem(props: &OverlayMenuItemProps) -> Html {
                     {show_hide_button}
                 </span>
                 <span class="overlay-expression">
-                    {overlay_expression}
+                    {overlay_expression.clone()}
                 </span>
                 { maybe_warning }
             </div>
@@ -393,6 +397,151 @@ pub fn OverlayMenuItem(props: &OverlayMenuItemProps) -> Html {
     }
 }
 
+/// Applies a preset of the `svifpd.viewerUi.drawingPresets` setting to the current image, or
+/// saves its drawing options as a new one.
+#[function_component]
+fn DrawingPresets() -> Html {
+    let presets = use_selector(|state: &AppState| state.configuration.drawing_presets.clone());
+    let current_image = use_selector(|state: &AppState| {
+        let cv = state
+            .image_views
+            .borrow()
+            .get_currently_viewing(ViewId::Primary)?;
+        let expression = state
+            .images
+            .borrow()
+            .get(cv.id())
+            .map(|image| image.minimal().expression.clone())?;
+        Some((cv.id().clone(), expression))
+    });
+    let select_ref = use_node_ref();
+
+    let onchange = {
+        let presets = presets.clone();
+        let current_image = current_image.clone();
+        let select_ref = select_ref.clone();
+        Callback::from(move |_: Event| {
+            let Some(select) = select_ref.cast::<web_sys::HtmlSelectElement>() else {
+                return;
+            };
+            // the first option is the placeholder
+            let preset = (select.selected_index() as usize)
+                .checked_sub(1)
+                .and_then(|index| presets.get(index))
+                .cloned();
+            // the select only triggers actions, it always shows its placeholder
+            select.set_selected_index(0);
+            if let (Some(preset), Some((image_id, _))) = (preset, current_image.as_ref()) {
+                Dispatch::<AppState>::global().apply(StoreAction::UpdateDrawingOptions(
+                    image_id.clone(),
+                    DrawingContext::BaseImage,
+                    UpdateDrawingOptions::Preset(preset),
+                ));
+            }
+        })
+    };
+
+    let on_save_click = {
+        let current_image = current_image.clone();
+        Callback::from(move |_: MouseEvent| {
+            let Some((image_id, expression)) = current_image.as_ref() else {
+                return;
+            };
+            let state = Dispatch::<AppState>::global().get();
+            let drawing_options = state
+                .drawing_options
+                .borrow()
+                .get(image_id, &DrawingContext::BaseImage)
+                .cloned()
+                .unwrap_or_default();
+            VSCodeRequests::save_drawing_preset(DrawingPreset::from_current(
+                expression.clone(),
+                &drawing_options,
+                &state.global_drawing_options,
+            ));
+        })
+    };
+
+    let has_image = current_image.is_some();
+    let options = presets.iter().map(|preset| {
+        html! {
+            <option>{preset.name.clone()}</option>
+        }
+    });
+
+    html! {
+        <>
+            <div class="vscode-select">
+                <select
+                    ref={select_ref}
+                    disabled={!has_image || presets.is_empty()}
+                    title="Apply a drawing preset"
+                    {onchange}
+                >
+                    <option selected={true} disabled={true}>{"Preset"}</option>
+                    {for options}
+                </select>
+                <span class="chevron-icon">
+                    <svg width="16" height="16" viewBox="0 0 16 16" xmlns="http://www.w3.org/2000/svg" fill="currentColor">
+                        <path
+                            fill-rule="evenodd"
+                            clip-rule="evenodd"
+                            d="M7.976 10.072l4.357-4.357.62.618L8.284 11h-.618L3 6.333l.619-.618 4.357 4.357z"
+                        />
+                    </svg>
+                </span>
+            </div>
+            <IconButton
+                icon="codicon codicon-save-as"
+                onclick={Some(on_save_click)}
+                title={Some(AttrValue::from("Save the drawing options as a preset"))}
+                disabled={Some(!has_image)}
+            />
+        </>
+    }
+}
+
+#[function_component]
+fn OrientationButtons() -> Html {
+    let is_turned = use_selector(|state: &AppState| {
+        !state
+            .view_cameras
+            .borrow()
+            .get(ViewId::Primary)
+            .orientation
+            .is_identity()
+    });
+    let dispatch = Dispatch::<AppState>::global();
+
+    html! {
+        <>
+            <IconButton
+                icon="codicon codicon-debug-restart"
+                onclick={dispatch.apply_callback(|_| UiAction::Rotate(ViewId::Primary, 1))}
+                title={Some(AttrValue::from("Rotate 90° clockwise"))}
+            />
+            <IconButton
+                icon="codicon codicon-arrow-swap"
+                onclick={dispatch.apply_callback(|_| UiAction::FlipHorizontally(ViewId::Primary))}
+                title={Some(AttrValue::from("Flip horizontally"))}
+            />
+            <IconButton
+                class={classes!(css!("transform: rotate(90deg);"))}
+                icon="codicon codicon-arrow-swap"
+                onclick={dispatch.apply_callback(|_| UiAction::FlipVertically(ViewId::Primary))}
+                title={Some(AttrValue::from("Flip vertically"))}
+            />
+            if *is_turned {
+                <IconButton
+                    icon="codicon codicon-discard"
+                    onclick={dispatch.apply_callback(|_| UiAction::ResetOrientation(ViewId::Primary))}
+                    title={Some(AttrValue::from("Reset rotation and flips"))}
+                />
+            }
+        </>
+    }
+}
+
 #[derive(PartialEq, Properties)]
 pub(crate) struct MainToolbarProps {}
 
@@ -537,6 +686,18 @@ pub(crate) fn MainToolbar(props: &MainToolbarProps) -> Html {
 
     let has_image = current_image_info.is_some();
 
+    let pixel_inspector_enabled = use_selector(|state: &AppState| state.pixel_inspector.enabled);
+    let line_profile_enabled =
+        use_selector(|state: &AppState| state.line_profiles.borrow().enabled);
+    let currently_selected_style = use_style!(
+        r#"
+        background-color: var(--vscode-button-background);
+        :hover {
+            background-color: var(--vscode-button-background);
+        }
+        "#,
+    );
+
     html! {
         <div class={style}>
 
@@ -561,6 +722,9 @@ pub(crate) fn MainToolbar(props: &MainToolbarProps) -> Html {
 
             <HeatmapColormapDropdown disabled={drawing_options.coloring != Coloring::Heatmap} />
 
+            <div class={classes!("vscode-vertical-divider", css!("height: 75%;"))} />
+            <DrawingPresets />
+
             <div class={classes!("vscode-vertical-divider", css!("height: 75%;"))} />
             <IconButton
                 icon="codicon codicon-save"
@@ -568,6 +732,24 @@ pub(crate) fn MainToolbar(props: &MainToolbarProps) -> Html {
                 title={Some(AttrValue::from("Save Image"))}
                 disabled={Some(!has_image)}
             />
+            <IconButton
+                class={classes!(pixel_inspector_enabled.then_some(currently_selected_style.clone()))}
+                icon="codicon codicon-inspect"
+                onclick={Dispatch::<AppState>::global().apply_callback(|_| UiAction::TogglePixelInspector)}
+                title={Some(AttrValue::from("Pixel Inspector"))}
+            />
+            <IconButton
+                class={classes!(line_profile_enabled.then_some(currently_selected_style))}
+                icon="codicon codicon-graph-line"
+                onclick={Dispatch::<AppState>::global().apply_callback(|_| LineProfileAction::ToggleTool)}
+                title={Some(AttrValue::from("Line Profile (drag on the image to draw a line)"))}
+            />
+
+            <div class={classes!("vscode-vertical-divider", css!("height: 75%;"))} />
+            <OrientationButtons />
+
+            <div class={classes!("vscode-vertical-divider", css!("height: 75%;"))} />
+            <AnnotationToolbar />
 
             if let Some(overlay) = overlay.as_ref() {
                 <div class={classes!("overlay-menu-item")}>
@@ -579,7 +761,10 @@ pub(crate) fn MainToolbar(props: &MainToolbarProps) -> Html {
                 <span class={classes!("tooltiptext")}>
                     <p>{"Click + Drag to pan"}</p>
                     <p>{"Scroll to zoom"}</p>
+                    <p>{"Ctrl + Drag to zoom to a rectangle"}</p>
                     <p>{"Shift + Scroll/Up/Down to change batch item"}</p>
+                    <p>{"Double click/Enter to close a polygon, Escape to cancel it"}</p>
+                    <p>{"Press ? to list all keyboard shortcuts"}</p>
                 </span>
             </div>
         </div>
@@ -1,21 +1,32 @@
+mod annotation_toolbar;
+mod batch_playback;
 mod button;
 mod checkbox;
 mod colorbar;
-mod display_options;
+pub(crate) mod display_options;
 pub(crate) mod icon_button;
+mod image_error;
+mod image_list_controls;
+mod image_statistics;
 pub(crate) mod image_list_item;
 pub(crate) mod image_selection_list;
 pub(crate) mod image_view_toolbar;
 pub(crate) mod legend;
+mod line_profile_plot;
 pub(crate) mod main;
 pub(crate) mod main_toolbar;
+mod pixel_context_menu;
+mod pixel_inspector;
 mod session_select;
 pub(crate) mod set_image_into_view_button;
+mod shortcuts_overlay;
 pub(crate) mod sidebar;
 pub(crate) mod single_view;
 pub(crate) mod spinner;
 pub(crate) mod status_bar;
+mod thumbnail;
 mod types;
+mod value_history;
 pub(crate) mod view_container;
 pub(crate) mod viewable_info_container;
 pub(crate) mod context_menu;
@@ -0,0 +1,162 @@
+use std::rc::Rc;
+
+use glam::UVec2;
+use yew::prelude::*;
+use yewdux::Dispatch;
+
+use crate::{
+    application_state::{
+        app_state::{AppState, StoreAction, UpdateDrawingOptions},
+        images::{DrawingContext, ImageAvailability},
+    },
+    common::{
+        clipboard::copy_to_clipboard, pixel_value::PixelValue, DataOrdering, ImageInfo,
+        ValueVariableKind, ViewId,
+    },
+    components::context_menu::{use_context_menu, ContextMenuData, ContextMenuItem},
+    mouse_events::ContextMenuHandler,
+    rendering::rendering_context::ViewContext,
+};
+
+/// A Python expression that indexes the pixel in the image, e.g. `img[y, x]`.
+fn pixel_expression(info: &ImageInfo, pixel: UVec2, batch_item: Option<u32>) -> String {
+    let expression = match info.value_variable_kind {
+        ValueVariableKind::Variable => info.expression.clone(),
+        ValueVariableKind::Expression => format!("({})", info.expression),
+    };
+    let batch_index = batch_item
+        .filter(|_| info.batch_info.is_some())
+        .map(|b| format!("{}, ", b))
+        .unwrap_or_default();
+    let channels = match info.data_ordering {
+        DataOrdering::HWC => "",
+        // works for both (C, H, W) and (H, W) arrays
+        DataOrdering::CHW => "..., ",
+    };
+    format!(
+        "{}[{}{}{}, {}]",
+        expression, batch_index, channels, pixel.y, pixel.x
+    )
+}
+
+fn channel_values(pixel_value: &PixelValue) -> String {
+    let channels = (0..pixel_value.num_channels.into())
+        .map(|c| pixel_value.format_channel(c))
+        .collect::<Vec<_>>();
+    format!("[{}]", channels.join(", "))
+}
+
+fn copy_items(
+    info: &ImageInfo,
+    pixel: UVec2,
+    pixel_value: Option<PixelValue>,
+    batch_item: Option<u32>,
+) -> Vec<(&'static str, Option<String>)> {
+    let format = Dispatch::<AppState>::global()
+        .get()
+        .configuration
+        .rendering
+        .pixel_value_format;
+    vec![
+        ("Copy x, y", Some(format!("{},{}", pixel.x, pixel.y))),
+        (
+            "Copy NumPy index [row, col]",
+            Some(format!("[{}, {}]", pixel.y, pixel.x)),
+        ),
+        (
+            "Copy pixel value",
+            pixel_value.map(|p| format!("({})", p.format_with(&format, None).join(", "))),
+        ),
+        (
+            "Copy channel values",
+            pixel_value.map(|p| channel_values(&p)),
+        ),
+        (
+            "Copy expression",
+            Some(pixel_expression(info, pixel, batch_item)),
+        ),
+    ]
+}
+
+/// Opens a context menu with clipboard actions for the pixel on right click in the view.
+#[hook]
+pub(crate) fn use_pixel_context_menu(view_id: ViewId, view_context: Rc<dyn ViewContext>) {
+    let context_menu = use_context_menu();
+
+    use_effect_with(view_id, move |view_id| {
+        let view_id = *view_id;
+        let callback = {
+            let view_context = Rc::clone(&view_context);
+            Callback::from(move |(event, pixel): (web_sys::MouseEvent, UVec2)| {
+                let Some(ImageAvailability::Available(image)) =
+                    view_context.get_image_for_view(view_id)
+                else {
+                    return;
+                };
+                let image = image.borrow();
+                let batch_item = Dispatch::<AppState>::global()
+                    .get()
+                    .drawing_options
+                    .borrow()
+                    .get(&image.info.image_id, &DrawingContext::BaseImage)
+                    .and_then(|d| d.batch_item);
+                let pixel_value = image
+                    .bytes
+                    .get(&batch_item.unwrap_or(0))
+                    .map(|bytes| PixelValue::from_image_info(&image.info, bytes, &pixel));
+
+                let copy_items = copy_items(&image.info, pixel, pixel_value, batch_item)
+                    .into_iter()
+                    .map(|(label, text)| {
+                        let context_menu = context_menu.clone();
+                        ContextMenuItem {
+                            label: label.to_string(),
+                            disabled: text.is_none(),
+                            action: Callback::from(move |_| {
+                                if let Some(text) = text.clone() {
+                                    copy_to_clipboard(text);
+                                }
+                                context_menu.set(None);
+                            }),
+                        }
+                    });
+                // the presets of the `svifpd.viewerUi.drawingPresets` setting
+                let presets = Dispatch::<AppState>::global()
+                    .get()
+                    .configuration
+                    .drawing_presets
+                    .clone();
+                let preset_items = presets.into_iter().map(|preset| {
+                    let context_menu = context_menu.clone();
+                    let image_id = image.info.image_id.clone();
+                    ContextMenuItem {
+                        label: format!("Apply preset: {}", preset.name),
+                        disabled: false,
+                        action: Callback::from(move |_| {
+                            Dispatch::<AppState>::global().apply(
+                                StoreAction::UpdateDrawingOptions(
+                                    image_id.clone(),
+                                    DrawingContext::BaseImage,
+                                    UpdateDrawingOptions::Preset(preset.clone()),
+                                ),
+                            );
+                            context_menu.set(None);
+                        }),
+                    }
+                });
+                let items = copy_items.chain(preset_items).collect();
+
+                context_menu.set(Some(ContextMenuData {
+                    x: event.client_x(),
+                    y: event.client_y(),
+                    items,
+                }));
+            })
+        };
+        let listener = ContextMenuHandler::install(view_id, view_context, callback);
+
+        move || {
+            drop(listener);
+        }
+    });
+}
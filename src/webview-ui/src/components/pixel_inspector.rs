@@ -0,0 +1,505 @@
+use std::rc::Rc;
+
+use glam::{UVec2, Vec4};
+use gloo::events::EventListener;
+use itertools::Itertools;
+use stylist::yew::use_style;
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+use yewdux::{functional::use_selector, Dispatch};
+
+use crate::{
+    application_state::{
+        app_state::{AppState, PixelInspectorOptions, UiAction},
+        images::DrawingContext,
+    },
+    coloring::{self, Coloring, DrawingOptions},
+    common::{
+        clipboard::copy_to_clipboard, pixel_value::PixelValue, texture_image::TextureImage,
+        Datatype, ViewId,
+    },
+    components::main::PixelHoverEvent,
+    mouse_events::PixelHoverHandler,
+    rendering::rendering_context::ViewContext,
+};
+
+use super::icon_button::IconButton;
+
+/// Mouse movement (in css pixels) between press and release that still counts as a click.
+const CLICK_TOLERANCE: i32 = 2;
+
+/// The pixels around a center pixel, clipped to the image bounds.
+struct Neighborhood {
+    center: UVec2,
+    xs: Vec<u32>,
+    ys: Vec<u32>,
+    /// Pixel values, row major.
+    rows: Vec<Vec<PixelValue>>,
+    datatype: Datatype,
+    num_channels: u32,
+}
+
+impl Neighborhood {
+    fn new(texture: &TextureImage, batch_index: u32, center: UVec2, size: u32) -> Option<Self> {
+        let info = &texture.info;
+        if center.x >= info.width || center.y >= info.height {
+            return None;
+        }
+        let bytes = texture.bytes.get(&batch_index)?;
+
+        let radius = size / 2;
+        let xs = (center.x.saturating_sub(radius)..=(center.x + radius).min(info.width - 1))
+            .collect_vec();
+        let ys = (center.y.saturating_sub(radius)..=(center.y + radius).min(info.height - 1))
+            .collect_vec();
+        let rows = ys
+            .iter()
+            .map(|&y| {
+                xs.iter()
+                    .map(|&x| PixelValue::from_image_info(info, bytes, &UVec2::new(x, y)))
+                    .collect_vec()
+            })
+            .collect_vec();
+
+        Some(Self {
+            center,
+            xs,
+            ys,
+            rows,
+            datatype: info.datatype,
+            num_channels: info.channels.into(),
+        })
+    }
+
+    fn numpy_value(&self, pixel_value: &PixelValue, channel: u32) -> String {
+        if self.datatype == Datatype::Float32 {
+            let value = *pixel_value.get::<f32>(channel);
+            if value.is_nan() {
+                return "np.nan".to_string();
+            } else if value.is_infinite() {
+                return if value > 0.0 { "np.inf" } else { "-np.inf" }.to_string();
+            }
+        }
+        pixel_value.format_channel(channel)
+    }
+
+    /// A NumPy array literal of shape (rows, cols) for single channel images, or
+    /// (rows, cols, channels) otherwise.
+    fn to_numpy(&self) -> String {
+        let rows = self
+            .rows
+            .iter()
+            .map(|row| {
+                let cells = row
+                    .iter()
+                    .map(|pixel_value| {
+                        let values = (0..self.num_channels)
+                            .map(|c| self.numpy_value(pixel_value, c))
+                            .join(", ");
+                        if self.num_channels == 1 {
+                            values
+                        } else {
+                            format!("[{}]", values)
+                        }
+                    })
+                    .join(", ");
+                format!("    [{}],\n", cells)
+            })
+            .join("");
+        format!(
+            "np.array([\n{}], dtype={})",
+            rows,
+            numpy_dtype(self.datatype)
+        )
+    }
+
+    /// One comma separated block of rows per channel. Blocks are separated by an empty line.
+    fn to_csv(&self) -> String {
+        (0..self.num_channels)
+            .map(|c| {
+                self.rows
+                    .iter()
+                    .map(|row| row.iter().map(|p| p.format_channel(c)).join(","))
+                    .join("\n")
+            })
+            .join("\n\n")
+            + "\n"
+    }
+}
+
+fn numpy_dtype(datatype: Datatype) -> &'static str {
+    match datatype {
+        Datatype::Uint8 => "np.uint8",
+        Datatype::Uint16 => "np.uint16",
+        Datatype::Uint32 => "np.uint32",
+        Datatype::Float32 => "np.float32",
+        Datatype::Int8 => "np.int8",
+        Datatype::Int16 => "np.int16",
+        Datatype::Int32 => "np.int32",
+        Datatype::Bool => "np.bool_",
+    }
+}
+
+fn css_color(color: Vec4) -> String {
+    let color = Vec4::from_array(color.to_array().map(|v| if v.is_nan() { 0.0 } else { v }))
+        .clamp(Vec4::ZERO, Vec4::ONE);
+    format!(
+        "rgba({}, {}, {}, {})",
+        (color.x * 255.0).round(),
+        (color.y * 255.0).round(),
+        (color.z * 255.0).round(),
+        color.w
+    )
+}
+
+fn css_text_color(color: Vec4) -> &'static str {
+    let gray = 0.299 * color.x + 0.587 * color.y + 0.114 * color.z;
+    if color.w < 0.5 {
+        // mostly transparent, the background shows through
+        "var(--vscode-foreground)"
+    } else if gray.is_nan() || gray < 0.5 {
+        "white"
+    } else {
+        "black"
+    }
+}
+
+#[derive(Properties)]
+pub(crate) struct PixelInspectorProps {
+    pub view_id: ViewId,
+    pub view_context: Rc<dyn ViewContext>,
+}
+
+impl PartialEq for PixelInspectorProps {
+    fn eq(&self, other: &Self) -> bool {
+        self.view_id == other.view_id && Rc::ptr_eq(&self.view_context, &other.view_context)
+    }
+}
+
+#[function_component]
+pub(crate) fn PixelInspector(props: &PixelInspectorProps) -> Html {
+    let PixelInspectorProps {
+        view_id,
+        view_context,
+    } = props;
+    let view_id = *view_id;
+
+    let options = use_selector(|state: &AppState| state.pixel_inspector.clone());
+    let global_drawing_options =
+        use_selector(|state: &AppState| state.global_drawing_options.clone());
+    let drawing_options = use_selector(move |state: &AppState| -> Option<DrawingOptions> {
+        let cv = state.image_views.borrow().get_currently_viewing(view_id)?;
+        Some(
+            state
+                .drawing_options
+                .borrow()
+                .get(cv.id(), &DrawingContext::BaseImage)
+                .cloned()
+                .unwrap_or_default(),
+        )
+    });
+
+    let pixel = use_state(|| Option::<UVec2>::None);
+    let frozen = use_state(|| false);
+    // the listeners outlive the render, they read the hovered pixel from here
+    let current_pixel = use_mut_ref(|| None::<UVec2>);
+    let mouse_down_position = use_mut_ref(|| None::<(i32, i32)>);
+
+    use_effect_with((view_id, options.enabled, *frozen), {
+        let pixel = pixel.clone();
+        let frozen = frozen.clone();
+        let view_context = Rc::clone(view_context);
+
+        move |(view_id, enabled, _)| {
+            let view_id = *view_id;
+            let listeners = enabled.then(|| {
+                let view_element = view_context.get_view_element(view_id);
+
+                let mut listeners = PixelHoverHandler::install(
+                    view_id,
+                    Rc::clone(&view_context),
+                    Callback::from({
+                        let pixel = pixel.clone();
+                        let current_pixel = current_pixel.clone();
+                        let frozen = *frozen;
+                        move |event: PixelHoverEvent| match event {
+                            PixelHoverEvent::Hovered(hovered) if !frozen => {
+                                *current_pixel.borrow_mut() = Some(hovered);
+                                pixel.set(Some(hovered))
+                            }
+                            PixelHoverEvent::None if !frozen => {
+                                *current_pixel.borrow_mut() = None;
+                                pixel.set(None)
+                            }
+                            // the image changed, read the values again
+                            PixelHoverEvent::Refresh => pixel.set(*current_pixel.borrow()),
+                            _ => {}
+                        }
+                    }),
+                );
+
+                listeners.push(EventListener::new(&view_element, "mousedown", {
+                    let mouse_down_position = mouse_down_position.clone();
+                    move |e| {
+                        if let Some(e) = e.dyn_ref::<web_sys::MouseEvent>() {
+                            *mouse_down_position.borrow_mut() = Some((e.client_x(), e.client_y()));
+                        }
+                    }
+                }));
+
+                // a click without dragging freezes or releases the inspector
+                listeners.push(EventListener::new(&view_element, "click", {
+                    let mouse_down_position = mouse_down_position.clone();
+                    let current_pixel = current_pixel.clone();
+                    move |e| {
+                        let Some(e) = e.dyn_ref::<web_sys::MouseEvent>() else {
+                            return;
+                        };
+                        let is_click =
+                            mouse_down_position
+                                .borrow_mut()
+                                .take()
+                                .map_or(false, |(x, y)| {
+                                    (e.client_x() - x).abs() <= CLICK_TOLERANCE
+                                        && (e.client_y() - y).abs() <= CLICK_TOLERANCE
+                                });
+                        if is_click && (*frozen || current_pixel.borrow().is_some()) {
+                            frozen.set(!*frozen);
+                        }
+                    }
+                }));
+
+                listeners
+            });
+
+            move || drop(listeners)
+        }
+    });
+
+    let style = use_style!(
+        r#"
+        box-sizing: border-box;
+        height: 100%;
+        overflow: auto;
+        padding: 4px;
+        background-color: var(--vscode-sideBar-background);
+        border-left: 1px solid var(--vscode-panel-border);
+        font-size: 0.75rem;
+
+        .header {
+            display: flex;
+            flex-direction: row;
+            align-items: center;
+            gap: 4px;
+            margin-bottom: 4px;
+            white-space: nowrap;
+        }
+        .position {
+            margin-right: auto;
+            font-variant-numeric: tabular-nums;
+        }
+        table {
+            border-collapse: collapse;
+            font-family: var(--vscode-editor-font-family);
+            font-variant-numeric: tabular-nums;
+        }
+        th {
+            font-weight: normal;
+            opacity: 0.7;
+            padding: 0 2px;
+        }
+        td {
+            padding: 1px 3px;
+            text-align: right;
+            border: 1px solid transparent;
+            white-space: nowrap;
+        }
+        td.center {
+            border-color: var(--vscode-focusBorder);
+        }
+        .empty {
+            opacity: 0.7;
+        }
+        "#,
+    );
+
+    if !options.enabled {
+        return html! {};
+    }
+
+    let dispatch = Dispatch::<AppState>::global();
+
+    let texture = view_context
+        .get_image_for_view(view_id)
+        .and_then(|image| image.map(|texture| texture));
+    let neighborhood = (*pixel)
+        .zip(texture.as_ref())
+        .zip(drawing_options.as_ref().as_ref())
+        .and_then(|((pixel, texture), drawing_options)| {
+            let batch_index = drawing_options.batch_item.unwrap_or(0);
+            Neighborhood::new(&texture.borrow(), batch_index, pixel, options.size)
+        })
+        .map(Rc::new);
+
+    let table = match (&neighborhood, texture.as_ref(), drawing_options.as_ref()) {
+        (Some(neighborhood), Some(texture), Some(drawing_options)) => {
+            let texture = texture.borrow();
+            let coloring_factors = coloring::calculate_color_matrix(
+                &texture.info,
+                &texture.computed_info,
+                drawing_options,
+            );
+            let colormap_name = match drawing_options.coloring {
+                Coloring::Heatmap => Some(&global_drawing_options.heatmap_colormap_name),
+                Coloring::Segmentation => Some(&global_drawing_options.segmentation_colormap_name),
+                _ => None,
+            };
+            let colormap =
+                colormap_name.and_then(|name| dispatch.get().color_map_registry.borrow().get(name));
+            let cell_color = |pixel_value: &PixelValue| match &colormap {
+                Some(colormap) => coloring::calculate_pixel_color_from_colormap(
+                    pixel_value,
+                    &coloring_factors,
+                    colormap,
+                    drawing_options,
+                ),
+                None => {
+                    coloring::calculate_pixel_color(pixel_value, &coloring_factors, drawing_options)
+                }
+            };
+
+            let header = neighborhood
+                .xs
+                .iter()
+                .map(|x| html! { <th>{x.to_string()}</th> })
+                .collect::<Html>();
+            let rows = neighborhood
+                .ys
+                .iter()
+                .zip(neighborhood.rows.iter())
+                .map(|(&y, row)| {
+                    let cells = neighborhood
+                        .xs
+                        .iter()
+                        .zip(row.iter())
+                        .map(|(&x, pixel_value)| {
+                            let color = cell_color(pixel_value);
+                            let is_center = UVec2::new(x, y) == neighborhood.center;
+                            html! {
+                                <td
+                                    class={classes!(is_center.then_some("center"))}
+                                    style={format!(
+                                        "background-color: {}; color: {};",
+                                        css_color(color),
+                                        css_text_color(color),
+                                    )}
+                                >
+                                    {for (0..neighborhood.num_channels).map(|c| html! {
+                                        <div>{pixel_value.format_channel(c)}</div>
+                                    })}
+                                </td>
+                            }
+                        })
+                        .collect::<Html>();
+                    html! {
+                        <tr>
+                            <th>{y.to_string()}</th>
+                            {cells}
+                        </tr>
+                    }
+                })
+                .collect::<Html>();
+
+            html! {
+                <table>
+                    <tr>
+                        <th></th>
+                        {header}
+                    </tr>
+                    {rows}
+                </table>
+            }
+        }
+        _ => html! {
+            <div class="empty">{"Hover over the image to inspect its pixels"}</div>
+        },
+    };
+
+    let on_size_change = dispatch.apply_callback(move |e: Event| {
+        let size = e
+            .target()
+            .and_then(|t| t.dyn_into::<web_sys::HtmlSelectElement>().ok())
+            .and_then(|select| select.value().parse::<u32>().ok())
+            .unwrap_or(PixelInspectorOptions::default().size);
+        UiAction::SetPixelInspectorSize(size)
+    });
+    let size_options = (PixelInspectorOptions::MIN_SIZE..=PixelInspectorOptions::MAX_SIZE)
+        .step_by(2)
+        .map(|size| {
+            html! {
+                <option value={size.to_string()} selected={size == options.size}>
+                    {format!("{0}×{0}", size)}
+                </option>
+            }
+        })
+        .collect::<Html>();
+
+    let on_copy_numpy = {
+        let neighborhood = neighborhood.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(neighborhood) = &neighborhood {
+                copy_to_clipboard(neighborhood.to_numpy());
+            }
+        })
+    };
+    let on_copy_csv = {
+        let neighborhood = neighborhood.clone();
+        Callback::from(move |_: MouseEvent| {
+            if let Some(neighborhood) = &neighborhood {
+                copy_to_clipboard(neighborhood.to_csv());
+            }
+        })
+    };
+    let on_unfreeze = {
+        let frozen = frozen.clone();
+        Callback::from(move |_: MouseEvent| frozen.set(false))
+    };
+
+    html! {
+        <div class={style}>
+            <div class="header">
+                <span class="position">
+                    {pixel.map(|p| format!("x: {}, y: {}", p.x, p.y)).unwrap_or_default()}
+                </span>
+                if *frozen {
+                    <IconButton
+                        aria_label={"Release"}
+                        title={"Frozen, click to release"}
+                        icon={"codicon codicon-lock"}
+                        onclick={on_unfreeze}
+                    />
+                }
+                <div class="vscode-select" title="Neighborhood size">
+                    <select onchange={on_size_change}>
+                        {size_options}
+                    </select>
+                </div>
+                <IconButton
+                    aria_label={"Copy as NumPy array"}
+                    title={"Copy as NumPy array"}
+                    icon={"codicon codicon-copy"}
+                    disabled={neighborhood.is_none()}
+                    onclick={on_copy_numpy}
+                />
+                <IconButton
+                    aria_label={"Copy as CSV"}
+                    title={"Copy as CSV"}
+                    icon={"codicon codicon-table"}
+                    disabled={neighborhood.is_none()}
+                    onclick={on_copy_csv}
+                />
+            </div>
+            {table}
+        </div>
+    }
+}
@@ -0,0 +1,77 @@
+use stylist::yew::use_style;
+use yew::prelude::*;
+use yewdux::{functional::use_selector, Dispatch};
+
+use crate::application_state::app_state::{AppState, UiAction};
+
+/// Lists the key bindings of the commands, toggled with `?`.
+#[function_component]
+pub(crate) fn ShortcutsOverlay() -> Html {
+    let visible = use_selector(|state: &AppState| state.shortcuts_visible);
+    let keymap = use_selector(|state: &AppState| state.configuration.keymap.clone());
+
+    let style = use_style!(
+        r#"
+        position: fixed;
+        inset: 0;
+        z-index: 1000;
+        display: flex;
+        align-items: center;
+        justify-content: center;
+        background-color: rgba(0, 0, 0, 0.4);
+
+        .panel {
+            background: var(--vscode-editorHoverWidget-background);
+            border: 1px solid var(--vscode-editorHoverWidget-border);
+            border-radius: 3px;
+            box-shadow: 0 2px 8px var(--vscode-widget-shadow);
+            color: var(--vscode-foreground);
+            padding: 8px 16px;
+            max-height: 80vh;
+            overflow-y: auto;
+        }
+
+        td {
+            padding: 2px 8px;
+        }
+
+        kbd {
+            background-color: var(--vscode-keybindingLabel-background);
+            border: 1px solid var(--vscode-keybindingLabel-border);
+            border-radius: 3px;
+            color: var(--vscode-keybindingLabel-foreground);
+            padding: 1px 4px;
+            margin-right: 4px;
+        }
+        "#,
+    );
+
+    if !*visible {
+        return html! {};
+    }
+
+    let rows = keymap
+        .bound_commands()
+        .into_iter()
+        .map(|(command, bindings)| {
+            html! {
+                <tr>
+                    <td>{command.description()}</td>
+                    <td>{for bindings.iter().map(|binding| html! { <kbd>{binding.to_string()}</kbd> })}</td>
+                </tr>
+            }
+        })
+        .collect::<Html>();
+
+    let dispatch = Dispatch::<AppState>::global();
+    html! {
+        <div class={style} onclick={dispatch.apply_callback(|_: MouseEvent| UiAction::HideShortcuts)}>
+            <div class="panel" onclick={|ev: MouseEvent| ev.stop_propagation()}>
+                <h3>{"Keyboard shortcuts"}</h3>
+                <table>
+                    {rows}
+                </table>
+            </div>
+        </div>
+    }
+}
@@ -235,6 +235,8 @@ pub(crate) fn Sidebar(props: &SidebarProps) -> Html {
         border-right: 1px solid var(--vscode-sideBar-border);
         height: 100%;
         min-width: 200px;
+        display: flex;
+        flex-direction: column;
     "#,
     );
     let sidebar_unpinned_style = use_style!(
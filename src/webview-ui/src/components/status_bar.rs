@@ -1,17 +1,167 @@
-use crate::common::pixel_value::PixelValue;
-use glam::UVec2;
+use glam::{UVec2, Vec2};
 use stylist::yew::use_style;
 use yew::prelude::*;
+use yewdux::{functional::use_selector, Dispatch};
+
+use crate::{
+    application_state::app_state::{AppState, UiAction},
+    common::ViewId,
+};
+
+/// Parses "x, y" or "x y" into a pixel position.
+fn parse_position(text: &str) -> Option<Vec2> {
+    let mut parts = text
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse::<f32>().ok());
+    let x = parts.next()??;
+    let y = parts.next()??;
+    parts.next().is_none().then_some(Vec2::new(x, y))
+}
+
+#[derive(PartialEq, Properties)]
+struct ZoomInputProps {
+    view_id: ViewId,
+}
+
+/// Shows the zoom of the view in percent, where 100% is one image pixel per device pixel.
+#[function_component]
+fn ZoomInput(props: &ZoomInputProps) -> Html {
+    let view_id = props.view_id;
+
+    let zoom_percent = use_selector(move |state: &AppState| {
+        let geometry = state.view_geometry(view_id)?;
+        Some(
+            state
+                .view_cameras
+                .borrow()
+                .get(view_id)
+                .zoom_percent(&geometry),
+        )
+    });
+
+    let Some(zoom_percent) = *zoom_percent else {
+        return html! {};
+    };
+    let value = if zoom_percent < 10.0 {
+        format!("{:.1}%", zoom_percent)
+    } else {
+        format!("{:.0}%", zoom_percent)
+    };
+
+    let onchange = Callback::from({
+        let value = value.clone();
+        move |event: Event| {
+            let input: web_sys::HtmlInputElement = event.target_unchecked_into();
+            let percent = input
+                .value()
+                .trim()
+                .trim_end_matches('%')
+                .trim()
+                .parse::<f32>();
+            match percent {
+                Ok(percent) if percent > 0.0 => {
+                    Dispatch::<AppState>::global()
+                        .apply(UiAction::SetZoomPercent(view_id, percent));
+                }
+                _ => {
+                    log::debug!("Invalid zoom percentage: {}", input.value());
+                    // the state does not change, so the render would leave the input as typed
+                    input.set_value(&value);
+                }
+            }
+        }
+    });
+
+    html! {
+        <input
+            class="input zoom"
+            title="Zoom (100% is one image pixel per screen pixel)"
+            {value}
+            {onchange}
+            onkeydown={|ev: KeyboardEvent| { ev.stop_propagation(); }}
+        />
+    }
+}
+
+#[derive(PartialEq, Properties)]
+struct GoToInputProps {
+    view_id: ViewId,
+}
+
+/// Centers the view on the pixel typed as "x, y".
+#[function_component]
+fn GoToInput(props: &GoToInputProps) -> Html {
+    let view_id = props.view_id;
+
+    let onkeydown = Callback::from(move |event: KeyboardEvent| {
+        event.stop_propagation();
+        if event.key() != "Enter" {
+            return;
+        }
+        let input: web_sys::HtmlInputElement = event.target_unchecked_into();
+        match parse_position(&input.value()) {
+            Some(pixel) => {
+                Dispatch::<AppState>::global().apply(UiAction::CenterOn(view_id, pixel));
+                input.set_value("");
+                let _ = input.blur();
+            }
+            None => log::debug!("Invalid position: {}", input.value()),
+        }
+    });
+
+    html! {
+        <input
+            class="input go-to"
+            placeholder="Go to x, y"
+            title="Center the view on a pixel"
+            {onkeydown}
+        />
+    }
+}
+
+fn megabytes(bytes: usize) -> f64 {
+    bytes as f64 / (1024.0 * 1024.0)
+}
+
+/// Memory taken by the loaded images and the value history, against the configured budget.
+#[function_component]
+fn MemoryUsageIndicator() -> Html {
+    let memory = use_selector(|state: &AppState| {
+        (state.memory_usage(), state.configuration.memory_budget_mb)
+    });
+    let (usage, budget_mb) = *memory;
+
+    let title = format!(
+        "Loaded images and value history: {:.1} MB of pixel data and {:.1} MB of textures. Above {} MB, the oldest steps of the history and the least recently shown images are unloaded.",
+        megabytes(usage.cpu_bytes),
+        megabytes(usage.gpu_bytes),
+        budget_mb,
+    );
+    let over_budget = megabytes(usage.total()) > budget_mb as f64;
+
+    html! {
+        <div class={classes!("memory", over_budget.then_some("over-budget"))} {title}>
+            {format!("{:.0} / {} MB", megabytes(usage.total()), budget_mb)}
+        </div>
+    }
+}
 
 #[derive(PartialEq, Properties)]
 pub(crate) struct StatusBarProps {
+    pub view_id: ViewId,
     pub pixel: Option<UVec2>,
-    pub pixel_value: Option<PixelValue>,
+    /// The formatted value of the pixel.
+    pub pixel_value: Option<AttrValue>,
 }
 
 #[function_component]
 pub(crate) fn StatusBar(props: &StatusBarProps) -> Html {
-    let StatusBarProps { pixel, pixel_value } = props;
+    let StatusBarProps {
+        view_id,
+        pixel,
+        pixel_value,
+    } = props;
 
     let style = use_style!(
         r#"
@@ -37,12 +187,43 @@ pub(crate) fn StatusBar(props: &StatusBarProps) -> Html {
         .right {
             margin-left: auto;
             margin-right: 4px;
+            display: flex;
+            flex-direction: row;
+            align-items: center;
+            gap: 4px;
         }
 
         .item {
             white-space: nowrap;
             width: 7ch;
         }
+
+        .input {
+            font-family: inherit;
+            font-size: inherit;
+            color: inherit;
+            background: transparent;
+            border: 1px solid transparent;
+            padding: 0 2px;
+        }
+        .input:hover, .input:focus {
+            border-color: var(--vscode-input-border, var(--vscode-focusBorder));
+            background-color: var(--vscode-input-background);
+        }
+        .zoom {
+            width: 7ch;
+            text-align: right;
+        }
+        .go-to {
+            width: 11ch;
+        }
+        .memory {
+            white-space: nowrap;
+            margin-right: 8px;
+        }
+        .memory.over-budget {
+            color: var(--vscode-editorWarning-foreground);
+        }
     "#,
     );
 
@@ -51,7 +232,12 @@ pub(crate) fn StatusBar(props: &StatusBarProps) -> Html {
             <div class="left">
                 <div class="item">{pixel.map(|p| format!("x: {}", p.x)).unwrap_or_default()}</div>
                 <div class="item">{pixel.map(|p| format!("y: {}", p.y)).unwrap_or_default()}</div>
-                <div class="item">{pixel_value.map(|p| format!("{}", p)).unwrap_or_default()}</div>
+                <div class="item">{pixel_value.clone().unwrap_or_default()}</div>
+            </div>
+            <div class="right">
+                <MemoryUsageIndicator />
+                <GoToInput view_id={*view_id} />
+                <ZoomInput view_id={*view_id} />
             </div>
             // <div class="right">
             //     <SessionSelect />
@@ -0,0 +1,114 @@
+use stylist::yew::use_style;
+use wasm_bindgen::{prelude::Closure, Clamped, JsCast};
+use yew::prelude::*;
+use yewdux::{use_selector, Dispatch};
+
+use crate::{
+    application_state::{app_state::AppState, thumbnails::THUMBNAIL_SIZE},
+    common::ViewableObjectId,
+};
+
+#[derive(PartialEq, Properties)]
+pub(crate) struct ThumbnailProps {
+    pub image_id: ViewableObjectId,
+}
+
+fn set_visible(image_id: &ViewableObjectId, visible: bool) {
+    Dispatch::<AppState>::global().reduce_mut(|state| {
+        state
+            .thumbnails
+            .borrow_mut()
+            .set_visible(image_id.clone(), visible);
+    });
+}
+
+/// The image rendered with its drawing options. It is only rendered while the item is scrolled
+/// into view, and stays empty until the image data is loaded.
+#[function_component]
+pub(crate) fn Thumbnail(props: &ThumbnailProps) -> Html {
+    let ThumbnailProps { image_id } = props;
+
+    let canvas_ref = use_node_ref();
+
+    let thumbnail = use_selector({
+        let image_id = image_id.clone();
+        move |state: &AppState| state.thumbnails.borrow().get(&image_id).cloned()
+    });
+
+    use_effect_with(
+        (canvas_ref.clone(), image_id.clone()),
+        |(canvas_ref, image_id)| {
+            let on_intersection = Closure::wrap(Box::new({
+                let image_id = image_id.clone();
+                move |entries: js_sys::Array| {
+                    // the last entry is the most recent state of the canvas
+                    if let Some(entry) = entries.iter().last().and_then(|entry| {
+                        entry.dyn_into::<web_sys::IntersectionObserverEntry>().ok()
+                    }) {
+                        set_visible(&image_id, entry.is_intersecting());
+                    }
+                }
+            }) as Box<dyn FnMut(js_sys::Array)>);
+            let observer =
+                web_sys::IntersectionObserver::new(on_intersection.as_ref().unchecked_ref()).ok();
+            if let (Some(observer), Some(canvas)) =
+                (&observer, canvas_ref.cast::<web_sys::Element>())
+            {
+                observer.observe(&canvas);
+            }
+
+            let image_id = image_id.clone();
+            move || {
+                if let Some(observer) = observer {
+                    observer.disconnect();
+                }
+                drop(on_intersection);
+                set_visible(&image_id, false);
+            }
+        },
+    );
+
+    use_effect_with(
+        (canvas_ref.clone(), thumbnail.clone()),
+        |(canvas_ref, thumbnail)| {
+            let context = canvas_ref
+                .cast::<web_sys::HtmlCanvasElement>()
+                .and_then(|canvas| canvas.get_context("2d").ok().flatten())
+                .and_then(|context| context.dyn_into::<web_sys::CanvasRenderingContext2d>().ok());
+            if let Some(context) = context {
+                let size = THUMBNAIL_SIZE as f64;
+                context.clear_rect(0.0, 0.0, size, size);
+                if let Some(thumbnail) = thumbnail.as_ref() {
+                    let image_data = web_sys::ImageData::new_with_u8_clamped_array_and_sh(
+                        Clamped(&thumbnail.pixels),
+                        THUMBNAIL_SIZE,
+                        THUMBNAIL_SIZE,
+                    );
+                    match image_data {
+                        Ok(image_data) => {
+                            let _ = context.put_image_data(&image_data, 0.0, 0.0);
+                        }
+                        Err(e) => log::error!("Thumbnail: could not create image data: {:?}", e),
+                    }
+                }
+            }
+        },
+    );
+
+    let style = use_style!(
+        r#"
+        flex-shrink: 0;
+        border: 1px solid var(--vscode-panel-border);
+        background-color: var(--vscode-editor-background);
+        "#
+    );
+
+    html! {
+        <canvas
+            ref={canvas_ref}
+            class={style}
+            width={THUMBNAIL_SIZE.to_string()}
+            height={THUMBNAIL_SIZE.to_string()}
+        />
+    }
+}
@@ -0,0 +1,179 @@
+use stylist::yew::use_style;
+use wasm_bindgen::JsCast;
+use yew::prelude::*;
+use yewdux::{functional::use_selector, Dispatch};
+
+use crate::{
+    application_state::{
+        app_state::{AppState, HistoryAction},
+        value_history::Step,
+    },
+    common::{ViewId, ViewableObjectId},
+};
+
+use super::icon_button::IconButton;
+
+const LIVE: &str = "live";
+
+fn step_from_value(value: &str) -> Option<u32> {
+    value.parse().ok()
+}
+
+fn step_options(steps: &[Step], selected: Option<u32>) -> Html {
+    steps
+        .iter()
+        .map(|step| {
+            html! {
+                <option value={step.index.to_string()} selected={selected == Some(step.index)}>
+                    {step.to_string()}
+                </option>
+            }
+        })
+        .chain(std::iter::once(html! {
+            <option value={LIVE} selected={selected.is_none()}>{LIVE}</option>
+        }))
+        .collect()
+}
+
+#[derive(PartialEq, Properties)]
+pub(crate) struct ValueHistoryTimelineProps {
+    pub view_id: ViewId,
+}
+
+#[function_component]
+pub(crate) fn ValueHistoryTimeline(props: &ValueHistoryTimelineProps) -> Html {
+    let ValueHistoryTimelineProps { view_id } = props;
+    let view_id = *view_id;
+
+    let history = use_selector(
+        move |state: &AppState| -> Option<(ViewableObjectId, Vec<Step>, Option<u32>)> {
+            let cv = state.image_views.borrow().get_currently_viewing(view_id)?;
+            let image_id = cv.id().clone();
+            let value_history = state.value_history.borrow();
+            let steps = value_history
+                .snapshots(&image_id)
+                .map(|snapshot| snapshot.step)
+                .collect::<Vec<_>>();
+            if steps.is_empty() {
+                return None;
+            }
+            let viewing_step = value_history.viewing_step(&image_id);
+            Some((image_id, steps, viewing_step))
+        },
+    );
+    let diff_steps = use_state(|| (None::<u32>, None::<u32>));
+
+    let style = use_style!(
+        r#"
+        display: flex;
+        flex-direction: column;
+        gap: 2px;
+        padding: 2px 4px;
+        background-color: var(--vscode-editor-background);
+        opacity: 0.9;
+        font-size: 0.75rem;
+
+        .row {
+            display: flex;
+            flex-direction: row;
+            align-items: center;
+            gap: 4px;
+        }
+        input[type="range"] {
+            flex-grow: 1;
+        }
+        .step {
+            white-space: nowrap;
+        }
+        "#,
+    );
+
+    let Some((image_id, steps, viewing_step)) = history.as_ref().clone() else {
+        return html! {};
+    };
+
+    let dispatch = Dispatch::<AppState>::global();
+
+    // the last position of the slider is the live data
+    let position = viewing_step
+        .and_then(|index| steps.iter().position(|step| step.index == index))
+        .unwrap_or(steps.len());
+    let current_label = steps
+        .get(position)
+        .map_or(LIVE.to_string(), |step| step.to_string());
+
+    let on_seek = {
+        let image_id = image_id.clone();
+        let steps = steps.clone();
+        dispatch.apply_callback(move |ev: InputEvent| {
+            let position = ev
+                .target()
+                .and_then(|t| t.dyn_into::<web_sys::HtmlInputElement>().ok())
+                .and_then(|input| input.value().parse::<usize>().ok())
+                .unwrap_or(steps.len());
+            HistoryAction::ShowStep(image_id.clone(), steps.get(position).map(|s| s.index))
+        })
+    };
+
+    let on_diff_select = |first: bool| {
+        let diff_steps = diff_steps.clone();
+        Callback::from(move |ev: Event| {
+            let value = ev
+                .target()
+                .and_then(|t| t.dyn_into::<web_sys::HtmlSelectElement>().ok())
+                .map(|select| select.value())
+                .unwrap_or_default();
+            let step = step_from_value(&value);
+            let (a, b) = *diff_steps;
+            diff_steps.set(if first { (step, b) } else { (a, step) });
+        })
+    };
+
+    let on_diff = {
+        let image_id = image_id.clone();
+        let steps = *diff_steps;
+        dispatch.apply_callback(move |_| HistoryAction::Diff {
+            view_id,
+            image_id: image_id.clone(),
+            steps,
+        })
+    };
+
+    html! {
+        <div class={style}>
+            <div class="row">
+                <i class="codicon codicon-history" title="Value history"></i>
+                <input
+                    type="range"
+                    min="0"
+                    max={steps.len().to_string()}
+                    step="1"
+                    value={position.to_string()}
+                    oninput={on_seek}
+                    onkeydown={|ev: KeyboardEvent| { ev.stop_propagation(); }}
+                />
+                <span class="step">{current_label}</span>
+            </div>
+            <div class="row">
+                <div class="vscode-select">
+                    <select onchange={on_diff_select(true)}>
+                        {step_options(&steps, diff_steps.0)}
+                    </select>
+                </div>
+                <span>{"-"}</span>
+                <div class="vscode-select">
+                    <select onchange={on_diff_select(false)}>
+                        {step_options(&steps, diff_steps.1)}
+                    </select>
+                </div>
+                <IconButton
+                    aria_label={"Show difference"}
+                    title={"Show difference"}
+                    icon={"codicon codicon-diff"}
+                    disabled={diff_steps.0 == diff_steps.1}
+                    onclick={on_diff}
+                />
+            </div>
+        </div>
+    }
+}
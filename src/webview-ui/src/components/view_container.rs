@@ -16,10 +16,11 @@ use crate::{
     colormap,
     common::{Channels, CurrentlyViewing, ViewId, ViewableObjectId},
     components::{
-        button::Button, colorbar::Colorbar, legend::Legend, spinner::Spinner,
+        batch_playback::BatchPlaybackControls, button::Button, colorbar::Colorbar,
+        image_error::ImageErrorMessage, image_statistics::ImageStatisticsInfo, legend::Legend,
+        spinner::Spinner, value_history::ValueHistoryTimeline,
         viewable_info_container::ViewableInfoContainer,
     },
-    math_utils,
 };
 
 fn get_segmentation_colormap(
@@ -182,6 +183,13 @@ fn make_info_items(
             });
         }
 
+        let batch_index = drawing_options.batch_item.unwrap_or(0);
+        if let Some(statistics) = texture.computed_info.statistics.get(&batch_index) {
+            info_items.push(html! {
+                <ImageStatisticsInfo statistics={statistics.clone()} />
+            });
+        }
+
         if drawing_options.coloring == Coloring::Segmentation {
             let colormap = get_segmentation_colormap(&dispatch)
                 .map_err(|e| {
@@ -194,13 +202,13 @@ fn make_info_items(
                 drawing_options,
             );
 
-            let batch_index = drawing_options.batch_item.unwrap_or(0);
-
-            if let Ok(values) = math_utils::image_calculations::image_unique_values_on_bytes(
-                &texture.bytes[&batch_index],
-                texture.info.datatype,
-                texture.info.channels,
-            ) {
+            // the legend shows up once the statistics of the batch item are computed
+            if let Some(values) = texture
+                .computed_info
+                .statistics
+                .get(&batch_index)
+                .and_then(|statistics| statistics.unique_values.as_ref())
+            {
                 let seg_values = values
                     .iter()
                     .map(|v| v.as_rgba_f32()[0] as i32)
@@ -308,18 +316,33 @@ pub fn ColorbarContainer(props: &ColorbarContainerProps) -> Html {
         )
     };
 
+    let linked = {
+        let view_id = *view_id;
+        use_selector(move |state: &AppState| {
+            state
+                .image_views
+                .borrow()
+                .get_currently_viewing(view_id)
+                .map(|cv| state.link_groups.borrow().group_size(cv.id()))
+                .unwrap_or_default()
+        })
+    };
+
     if let Some((_, availability, drawing_options)) = current_image.as_ref() {
         if drawing_options.coloring == Coloring::Heatmap {
             if let ImageAvailability::Available(texture) = availability {
-                let image_info = &texture.borrow().computed_info;
+                let (min, max) = texture.borrow().computed_info.range(
+                    drawing_options.batch_item.unwrap_or(0),
+                    drawing_options.normalize_per_batch_item,
+                );
                 let image_id = texture.borrow().info.image_id.clone();
-                let min = image_info.min.as_rgba_f32()[0];
-                let max = image_info.max.as_rgba_f32()[0];
+                let min = min.as_rgba_f32()[0];
+                let max = max.as_rgba_f32()[0];
                 let clip_min = drawing_options.clip.min;
                 let clip_max = drawing_options.clip.max;
 
                 return html! {
-                    <Colorbar image_id={image_id} min={min} max={max} clip_min={clip_min} clip_max={clip_max} />
+                    <Colorbar image_id={image_id} min={min} max={max} clip_min={clip_min} clip_max={clip_max} linked={*linked} />
                 };
             }
         }
@@ -327,6 +350,51 @@ pub fn ColorbarContainer(props: &ColorbarContainerProps) -> Html {
     html! {}
 }
 
+#[derive(PartialEq, Properties)]
+pub struct ZoomSelectionRectProps {
+    pub view_id: ViewId,
+}
+
+#[function_component]
+pub fn ZoomSelectionRect(props: &ZoomSelectionRectProps) -> Html {
+    let ZoomSelectionRectProps { view_id } = props;
+
+    let selection = {
+        let view_id = *view_id;
+        use_selector(move |state: &AppState| {
+            state
+                .zoom_selection
+                .as_ref()
+                .filter(|selection| selection.view_id == view_id)
+                .map(|selection| (selection.start, selection.end))
+        })
+    };
+
+    let style = use_style!(
+        r#"
+        position: absolute;
+        border: 1px dashed var(--vscode-focusBorder);
+        background-color: color-mix(in srgb, var(--vscode-focusBorder) 15%, transparent);
+        pointer-events: none;
+        z-index: 3;
+        "#,
+    );
+
+    let Some((start, end)) = *selection else {
+        return html! {};
+    };
+    let min = start.min(end);
+    let size = (end - start).abs();
+    let position = format!(
+        "left: {}px; top: {}px; width: {}px; height: {}px;",
+        min.x, min.y, size.x, size.y
+    );
+
+    html! {
+        <div class={style} style={position} />
+    }
+}
+
 #[derive(PartialEq, Properties)]
 pub(crate) struct ViewContainerProps {
     #[prop_or_default]
@@ -374,6 +442,10 @@ pub(crate) fn ViewContainer(props: &ViewContainerProps) -> Html {
     };
     let display_colorbar =
         use_selector(|state: &AppState| state.global_drawing_options.display_colorbar);
+    let is_batch_item = current_image
+        .as_ref()
+        .as_ref()
+        .is_some_and(|(_, _, _, is_batch_item)| *is_batch_item);
 
     let availability =
         current_image
@@ -384,7 +456,7 @@ pub(crate) fn ViewContainer(props: &ViewContainerProps) -> Html {
 
                 match (batch_item, availability) {
                     (Some(item), ImageAvailability::Available(image)) => {
-                        if image.borrow().textures.contains_key(&item) {
+                        if image.borrow().has_batch_item(item) {
                             availability.clone()
                         } else {
                             ImageAvailability::NotAvailable
@@ -450,11 +522,44 @@ pub(crate) fn ViewContainer(props: &ViewContainerProps) -> Html {
         "#,
     );
 
+    let history_container_style = use_style!(
+        r#"
+        position: absolute;
+        top: 0;
+        left: 0;
+        max-width: 50%;
+        z-index: 2;
+        "#,
+    );
+
+    let error_container_style = use_style!(
+        r#"
+        position: absolute;
+        top: 0;
+        left: 50%;
+        transform: translateX(-50%);
+        max-width: 50%;
+        z-index: 2;
+        background-color: var(--vscode-editor-background);
+        "#,
+    );
+
+    let playback_container_style = use_style!(
+        r#"
+        position: absolute;
+        bottom: 0;
+        left: 0;
+        right: 0;
+        z-index: 2;
+        "#,
+    );
+
     html! {
         <div class={classes!(class.clone(), css!("position: relative;"))}>
             <div ref={node_ref.clone()} class={style}>
                 {inner_element}
             </div>
+            <ZoomSelectionRect view_id={*view_id} />
             if !info_items.is_empty() {
                 <div class={info_container_style}>
                     <ViewableInfoContainer collapsed={true}>
@@ -465,6 +570,19 @@ pub(crate) fn ViewContainer(props: &ViewContainerProps) -> Html {
             <div class={classes!(colorbar_container_style, if *display_colorbar { "" } else { "hidden" })}>
                 <ColorbarContainer view_id={*view_id} />
             </div>
+            <div class={history_container_style}>
+                <ValueHistoryTimeline view_id={*view_id} />
+            </div>
+            if let Some((image_id, ..)) = current_image.as_ref() {
+                <div class={error_container_style}>
+                    <ImageErrorMessage image_id={image_id.clone()} />
+                </div>
+            }
+            if is_batch_item {
+                <div class={playback_container_style}>
+                    <BatchPlaybackControls view_id={*view_id} />
+                </div>
+            }
         </div>
     }
 }
@@ -0,0 +1,244 @@
+use std::{cell::RefCell, collections::HashSet};
+
+use serde::Deserialize;
+use wasm_bindgen::{prelude::Closure, JsCast, JsValue};
+use yewdux::{mrc::Mrc, Dispatch};
+
+use crate::{
+    application_state::{app_state::AppState, images::ImageCache, value_history::ValueHistory},
+    common::{texture_image::TextureImage, ViewableObjectId},
+};
+
+use super::task::{ComputeResult, ComputeTask};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum WorkerMessage {
+    Result(ComputeResult),
+    Failure { texture_id: u32, error: String },
+}
+
+struct ComputeWorker {
+    worker: web_sys::Worker,
+    /// Textures whose results are awaited, computed on the UI thread if the worker fails.
+    in_flight: HashSet<u32>,
+    /// Set when the worker could not load or crashed. It is kept since its closures may be
+    /// running.
+    failed: bool,
+    _on_message: Closure<dyn FnMut(web_sys::MessageEvent)>,
+    _on_error: Closure<dyn FnMut(JsValue)>,
+}
+
+enum WorkerState {
+    NotStarted,
+    Running(ComputeWorker),
+    /// The computations run on the UI thread.
+    Unavailable,
+}
+
+thread_local! {
+    static WORKER: RefCell<WorkerState> = const { RefCell::new(WorkerState::NotStarted) };
+}
+
+impl ComputeWorker {
+    /// Webviews cannot start workers from their resource URIs, so the worker is started from a
+    /// blob that imports the worker script.
+    fn spawn() -> Result<Self, JsValue> {
+        let window = web_sys::window().ok_or("no window")?;
+        let base_uri = js_sys::Reflect::get(&window, &"baseUri".into())?;
+        if !base_uri.is_string() {
+            return Err("window.baseUri is not set".into());
+        }
+        let base_uri = js_sys::JSON::stringify(&base_uri)?;
+        let source = format!(
+            "self.baseUri = {};\nimportScripts(self.baseUri + '/compute_worker.js');\n",
+            String::from(base_uri)
+        );
+
+        let options = web_sys::BlobPropertyBag::new();
+        options.set_type("text/javascript");
+        let blob = web_sys::Blob::new_with_str_sequence_and_options(
+            &js_sys::Array::of1(&source.into()),
+            &options,
+        )?;
+        let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+        let worker = web_sys::Worker::new(&url)?;
+
+        let on_message = Closure::wrap(Box::new(|event: web_sys::MessageEvent| {
+            match serde_wasm_bindgen::from_value::<WorkerMessage>(event.data()) {
+                Ok(WorkerMessage::Result(result)) => {
+                    finish(result.texture_id);
+                    apply_result(result);
+                }
+                Ok(WorkerMessage::Failure { texture_id, error }) => {
+                    log::error!(
+                        "ComputeWorker: task for texture {} failed: {}",
+                        texture_id,
+                        error
+                    );
+                    finish(texture_id);
+                    run_on_ui_thread(texture_id);
+                }
+                Err(e) => log::error!("ComputeWorker: could not parse message: {:?}", e),
+            }
+        }) as Box<dyn FnMut(web_sys::MessageEvent)>);
+        worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+
+        let on_error = Closure::wrap(Box::new(|event: JsValue| {
+            log::error!(
+                "ComputeWorker: worker failed, computing on the UI thread: {:?}",
+                event
+            );
+            let in_flight = WORKER.with(|state| match &mut *state.borrow_mut() {
+                WorkerState::Running(worker) => {
+                    worker.worker.terminate();
+                    worker.failed = true;
+                    std::mem::take(&mut worker.in_flight)
+                }
+                _ => HashSet::new(),
+            });
+            in_flight.into_iter().for_each(run_on_ui_thread);
+        }) as Box<dyn FnMut(JsValue)>);
+        worker.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+
+        Ok(Self {
+            worker,
+            in_flight: HashSet::new(),
+            failed: false,
+            _on_message: on_message,
+            _on_error: on_error,
+        })
+    }
+
+    fn post(&mut self, texture: &TextureImage) -> Result<(), JsValue> {
+        let task = ComputeTask::new(texture);
+        let total_size = task
+            .batch_items
+            .iter()
+            .map(|batch_item| texture.bytes[batch_item].len())
+            .sum::<usize>();
+        let bytes = js_sys::Uint8Array::new_with_length(total_size as u32);
+        let mut offset = 0;
+        for batch_item in &task.batch_items {
+            let item_bytes = &texture.bytes[batch_item];
+            bytes
+                .subarray(offset, offset + item_bytes.len() as u32)
+                .copy_from(item_bytes);
+            offset += item_bytes.len() as u32;
+        }
+
+        let message = js_sys::Object::new();
+        js_sys::Reflect::set(
+            &message,
+            &"task".into(),
+            &serde_wasm_bindgen::to_value(&task)?,
+        )?;
+        js_sys::Reflect::set(&message, &"bytes".into(), &bytes)?;
+        self.worker
+            .post_message_with_transfer(&message, &js_sys::Array::of1(&bytes.buffer()))?;
+        self.in_flight.insert(task.texture_id);
+        Ok(())
+    }
+}
+
+fn finish(texture_id: u32) {
+    WORKER.with(|state| {
+        if let WorkerState::Running(worker) = &mut *state.borrow_mut() {
+            worker.in_flight.remove(&texture_id);
+        }
+    });
+}
+
+/// The loaded textures. The value history can hold copies of the textures of the cache.
+fn textures<'a>(
+    image_cache: &'a ImageCache,
+    value_history: &'a ValueHistory,
+) -> impl Iterator<Item = &'a Mrc<TextureImage>> {
+    image_cache
+        .loaded_images()
+        .map(|(_, texture)| texture)
+        .chain(value_history.textures())
+}
+
+fn find_texture(state: &AppState, texture_id: u32) -> Option<Mrc<TextureImage>> {
+    let image_cache = state.image_cache.borrow();
+    let value_history = state.value_history.borrow();
+    let texture = textures(&image_cache, &value_history)
+        .find(|texture| texture.borrow().holds_data_of(texture_id))
+        .cloned();
+    texture
+}
+
+fn apply_result(result: ComputeResult) {
+    Dispatch::<AppState>::global().reduce_mut(|state| {
+        let image_cache = state.image_cache.borrow();
+        let value_history = state.value_history.borrow();
+        let mut statistics = Some(result.statistics);
+        // every copy of the texture is borrowed mutably, so that they all compare as changed
+        for texture in textures(&image_cache, &value_history)
+            .filter(|texture| texture.borrow().holds_data_of(result.texture_id))
+        {
+            let mut texture = texture.borrow_mut();
+            if let Some(statistics) = statistics.take() {
+                texture.computed_info.merge_statistics(statistics);
+            }
+        }
+        if statistics.is_some() {
+            log::debug!(
+                "ComputeWorker: texture {} is gone, dropping its statistics",
+                result.texture_id
+            );
+        }
+    });
+}
+
+/// Computes the statistics of a texture on the UI thread, once the current update is done.
+fn run_on_ui_thread(texture_id: u32) {
+    wasm_bindgen_futures::spawn_local(async move {
+        let state = Dispatch::<AppState>::global().get();
+        let Some(texture) = find_texture(&state, texture_id) else {
+            return;
+        };
+        let result = {
+            let texture = texture.borrow();
+            let task = ComputeTask::new(&texture);
+            task.run_on_items(
+                task.batch_items
+                    .iter()
+                    .map(|batch_item| (*batch_item, texture.bytes[batch_item].as_slice())),
+            )
+        };
+        match result {
+            Ok(result) => apply_result(result),
+            Err(e) => log::error!("ComputeWorker: could not compute statistics: {:?}", e),
+        }
+    });
+}
+
+/// Computes the statistics of the data of a texture image, which is merged into its computed
+/// info when they arrive.
+pub(crate) fn submit(image_id: &ViewableObjectId, texture: &TextureImage) {
+    WORKER.with(|state| {
+        let mut state = state.borrow_mut();
+        if let WorkerState::NotStarted = *state {
+            *state = match ComputeWorker::spawn() {
+                Ok(worker) => WorkerState::Running(worker),
+                Err(e) => {
+                    log::warn!("ComputeWorker: could not start the worker: {:?}", e);
+                    WorkerState::Unavailable
+                }
+            };
+        }
+
+        let posted = match &mut *state {
+            WorkerState::Running(worker) if !worker.failed => worker
+                .post(texture)
+                .map_err(|e| log::error!("ComputeWorker: could not post {:?}: {:?}", image_id, e))
+                .is_ok(),
+            _ => false,
+        };
+        if !posted {
+            run_on_ui_thread(texture.id);
+        }
+    });
+}
@@ -0,0 +1,11 @@
+//! Computations on the pixels of images that are too slow for the UI thread: min and max,
+//! statistics, histograms and unique values.
+//!
+//! They run in a worker that loads this same wasm module (see `compute_worker.js`), and the
+//! results are merged into the [`ComputedInfo`](crate::common::ComputedInfo) of the texture
+//! image when they arrive. Until then the image is rendered with the range of its datatype.
+
+mod client;
+mod task;
+
+pub(crate) use client::submit;
@@ -0,0 +1,85 @@
+use anyhow::Result;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::{
+    common::{texture_image::TextureImage, Channels, DataOrdering, Datatype, ImageStatistics},
+    math_utils::image_calculations::image_statistics_on_bytes,
+};
+
+/// Sent to the worker with the bytes of the batch items, one after the other.
+#[derive(Serialize, Deserialize, Debug)]
+pub(super) struct ComputeTask {
+    /// [`TextureImage::id`] of the data.
+    pub texture_id: u32,
+    pub datatype: Datatype,
+    pub channels: Channels,
+    pub data_ordering: DataOrdering,
+    pub batch_items: Vec<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub(super) struct ComputeResult {
+    pub texture_id: u32,
+    pub statistics: Vec<(u32, ImageStatistics)>,
+}
+
+impl ComputeTask {
+    pub(super) fn new(texture: &TextureImage) -> Self {
+        Self {
+            texture_id: texture.id,
+            datatype: texture.info.datatype,
+            channels: texture.info.channels,
+            data_ordering: texture.info.data_ordering,
+            batch_items: texture.bytes.keys().copied().sorted().collect(),
+        }
+    }
+
+    pub(super) fn run_on_items<'a>(
+        &self,
+        items: impl Iterator<Item = (u32, &'a [u8])>,
+    ) -> Result<ComputeResult> {
+        let statistics = items
+            .map(|(batch_item, bytes)| {
+                let statistics = image_statistics_on_bytes(
+                    bytes,
+                    self.datatype,
+                    self.channels,
+                    self.data_ordering,
+                )?;
+                Ok((batch_item, statistics))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ComputeResult {
+            texture_id: self.texture_id,
+            statistics,
+        })
+    }
+
+    fn run(&self, bytes: &[u8]) -> Result<ComputeResult> {
+        let item_size = (bytes.len() / self.batch_items.len().max(1)).max(1);
+        self.run_on_items(
+            self.batch_items
+                .iter()
+                .copied()
+                .zip(bytes.chunks(item_size)),
+        )
+    }
+}
+
+/// Entry point of the compute worker. Takes `{ task, bytes }` and returns the
+/// [`ComputeResult`].
+#[wasm_bindgen(js_name = "runComputeTask", skip_typescript)]
+pub fn run_compute_task(message: JsValue) -> Result<JsValue, JsValue> {
+    let task = js_sys::Reflect::get(&message, &"task".into())?;
+    let task: ComputeTask = serde_wasm_bindgen::from_value(task)?;
+    let bytes = js_sys::Reflect::get(&message, &"bytes".into())?
+        .dyn_into::<js_sys::Uint8Array>()?
+        .to_vec();
+
+    let result = task
+        .run(&bytes)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok(serde_wasm_bindgen::to_value(&result)?)
+}
@@ -1,3 +1,10 @@
+use std::collections::HashMap;
+
+use crate::{
+    commands::Keymap,
+    common::{drawing_preset::DrawingPreset, pixel_value::PixelValueFormat},
+};
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub(crate) enum AutoUpdateImages {
     True,
@@ -21,25 +28,97 @@ impl From<&str> for AutoUpdateImages {
     }
 }
 
+/// What the drawing options of an image are remembered by, across debug sessions.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub(crate) enum RememberDrawingOptions {
+    Off,
+    #[default]
+    Expression,
+    ExpressionShapeAndType,
+}
+
+impl From<&str> for RememberDrawingOptions {
+    fn from(value: &str) -> Self {
+        match value {
+            "off" => Self::Off,
+            "expressionShapeAndType" => Self::ExpressionShapeAndType,
+            _ => Self::Expression,
+        }
+    }
+}
+
+impl RememberDrawingOptions {
+    /// The key the drawing options of the image are remembered by, if they are remembered.
+    pub(crate) fn key(
+        &self,
+        expression: &str,
+        additional_info: &HashMap<String, String>,
+    ) -> Option<String> {
+        match self {
+            Self::Off => None,
+            Self::Expression => Some(expression.to_string()),
+            Self::ExpressionShapeAndType => {
+                let info = |name: &str| additional_info.get(name).map_or("", String::as_str);
+                Some(format!(
+                    "{} shape={} dtype={}",
+                    expression,
+                    info("shape"),
+                    info("dtype")
+                ))
+            }
+        }
+    }
+}
+
+/// What is requested ahead of being shown, so that going to the next image or batch item does
+/// not wait for its data.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) struct PrefetchConfiguration {
+    /// Number of images before and after the shown image, in the order of the image list.
+    pub images: u32,
+    /// Number of batch items before and after the shown batch item.
+    pub batch_items: u32,
+}
+
+impl Default for PrefetchConfiguration {
+    fn default() -> Self {
+        Self {
+            images: 1,
+            batch_items: 2,
+        }
+    }
+}
+
+/// Default memory budget of the loaded images, in megabytes.
+pub(crate) const DEFAULT_MEMORY_BUDGET_MB: u32 = 1024;
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub(crate) struct RenderingConfiguration {
     pub minimum_size_to_render_pixel_border: usize,
     pub minimum_size_to_render_pixel_values: usize,
+    pub pixel_value_format: PixelValueFormat,
 }
 impl Default for RenderingConfiguration {
     fn default() -> RenderingConfiguration {
         Self {
             minimum_size_to_render_pixel_border: 30,
             minimum_size_to_render_pixel_values: 50,
+            pixel_value_format: PixelValueFormat::default(),
         }
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub(crate) struct Configuration {
     pub rendering: RenderingConfiguration,
     pub invert_scroll_direction: bool,
     pub auto_update_images: AutoUpdateImages,
+    pub keymap: Keymap,
+    pub remember_drawing_options: RememberDrawingOptions,
+    pub drawing_presets: Vec<DrawingPreset>,
+    /// Memory the loaded images may take before the least recently used ones are unloaded.
+    pub memory_budget_mb: u32,
+    pub prefetch: PrefetchConfiguration,
 }
 
 #[allow(clippy::derivable_impls)] // we want to manually implement Default, because I want to have it explicit here
@@ -49,6 +128,18 @@ impl Default for Configuration {
             rendering: Default::default(),
             invert_scroll_direction: false,
             auto_update_images: AutoUpdateImages::default(),
+            keymap: Keymap::default(),
+            remember_drawing_options: RememberDrawingOptions::default(),
+            drawing_presets: Vec::new(),
+            memory_budget_mb: DEFAULT_MEMORY_BUDGET_MB,
+            prefetch: PrefetchConfiguration::default(),
         }
     }
 }
+
+impl Configuration {
+    /// Saturates on wasm32, where a budget of 4 GB or more does not fit in `usize`.
+    pub(crate) fn memory_budget_bytes(&self) -> usize {
+        (self.memory_budget_mb as usize).saturating_mul(1024 * 1024)
+    }
+}
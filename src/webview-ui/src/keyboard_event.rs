@@ -8,89 +8,43 @@ use yewdux::Dispatch;
 use crate::{
     application_state::app_state::{AppState, UiAction},
     bindings::lodash::debounce_closure,
+    commands::KeyBinding,
     common::{constants, ViewId},
 };
 
-struct KeyboardEvent<'a> {
-    key: &'a str,
-    shift: bool,
-    ctrl: bool,
-    alt: bool,
+/// Whether the key is typed into a text field, rather than meant for the viewer.
+fn is_typing(event: &web_sys::KeyboardEvent) -> bool {
+    let Some(element) = event
+        .target()
+        .and_then(|target| target.dyn_into::<web_sys::HtmlElement>().ok())
+    else {
+        return false;
+    };
+    matches!(element.tag_name().as_str(), "INPUT" | "TEXTAREA" | "SELECT")
+        || element.is_content_editable()
 }
 
 pub(crate) struct KeyboardHandler {}
 
 impl KeyboardHandler {
     fn handle_key(event: &web_sys::KeyboardEvent) {
-        let key = event.key();
-
-        let shift = event.shift_key();
-        let ctrl = event.ctrl_key();
-        let alt = event.alt_key();
-
-        let keyboard_event = KeyboardEvent {
-            key: key.as_str(),
-            shift,
-            ctrl,
-            alt,
-        };
+        if is_typing(event) {
+            return;
+        }
+        let binding = KeyBinding::from_event(event);
 
         let dispatch = Dispatch::<AppState>::global();
-        let cv = dispatch
-            .get()
-            .image_views
-            .borrow()
-            .get_currently_viewing(ViewId::Primary);
+        let state = dispatch.get();
 
-        match keyboard_event {
-            // arrow up/down => change image
-            KeyboardEvent {
-                key: "ArrowDown",
-                shift: false,
-                ctrl: false,
-                alt: false,
-            } => {
-                event.prevent_default();
-                dispatch.apply(UiAction::Next(ViewId::Primary));
-            }
-            KeyboardEvent {
-                key: "ArrowUp",
-                shift: false,
-                ctrl: false,
-                alt: false,
-            } => {
-                event.prevent_default();
-                dispatch.apply(UiAction::Previous(ViewId::Primary));
-            }
+        // escape closes the shortcuts overlay before cancelling anything else
+        if state.shortcuts_visible && binding.key == "Escape" {
+            dispatch.apply(UiAction::HideShortcuts);
+            return;
+        }
 
-            // shift + arrow up/down => scroll batch
-            KeyboardEvent {
-                key: "ArrowDown",
-                shift: true,
-                ctrl: false,
-                alt: false,
-            } => {
-                event.prevent_default();
-                if let Some(cv) = cv {
-                    dispatch.apply(UiAction::ViewShiftScroll(ViewId::Primary, cv, 1.0));
-                }
-            }
-            KeyboardEvent {
-                key: "ArrowUp",
-                shift: true,
-                ctrl: false,
-                alt: false,
-            } => {
-                event.prevent_default();
-                if let Some(cv) = cv {
-                    dispatch.apply(UiAction::ViewShiftScroll(
-                        ViewId::Primary,
-                        cv,
-                        -1.0,
-                    ));
-                }
-            }
-            _ => {}
+        if let Some(command) = state.configuration.keymap.command_for(&binding) {
+            event.prevent_default();
+            command.execute(ViewId::Primary);
         }
     }
 
@@ -11,8 +11,10 @@ mod application_state;
 mod bindings;
 mod coloring;
 mod colormap;
+mod commands;
 mod common;
 mod components;
+mod compute;
 mod configurations;
 mod hooks;
 mod keyboard_event;
@@ -52,6 +54,11 @@ fn run() -> Result<(), JsValue> {
 
     init_log();
 
+    // the compute worker loads this module too, it only runs the exported computations
+    if web_sys::window().is_none() {
+        return Ok(());
+    }
+
     #[cfg(debug_assertions)]
     web_sys::console::clear();
 
@@ -5,7 +5,7 @@ use anyhow::{anyhow, Result};
 use glam::{Mat3, Vec2, Vec3};
 
 use crate::{
-    common::{pixel_value::PixelValue, Channels, Datatype, Size},
+    common::{pixel_value::PixelValue, Channels, DataOrdering, Datatype, ImageStatistics, Size},
     rendering::constants::VIEW_SIZE,
 };
 
@@ -33,7 +33,8 @@ pub(crate) fn calculate_pixels_information(
         VIEW_SIZE.width / image_size.width,
         VIEW_SIZE.height / image_size.height,
     ));
-    let view_projection_inv = (*view_projection * image_pixels_to_view).inverse();
+    let image_pixels_to_ndc = *view_projection * image_pixels_to_view;
+    let view_projection_inv = image_pixels_to_ndc.inverse();
 
     let tl_world = view_projection_inv * tl_ndc;
     let br_world = view_projection_inv * br_ndc;
@@ -51,7 +52,11 @@ pub(crate) fn calculate_pixels_information(
     let upper_x_px = i32::min(image_size.width as i32, (f32::ceil(br.x) as i32) + 1);
     let upper_y_px = i32::min(image_size.height as i32, (f32::ceil(br.y) as i32) + 1);
 
-    let pixel_size_device = (rendered_area_size.width / (brx - tlx)) as i32;
+    // the image can be turned in the view, so measure a pixel along its x axis on the screen
+    let pixel_step_ndc = image_pixels_to_ndc.x_axis.truncate();
+    let pixel_size_device =
+        (pixel_step_ndc * Vec2::new(rendered_area_size.width, rendered_area_size.height) / 2.0)
+            .length() as i32;
 
     PixelsInformation {
         lower_x_px,
@@ -62,134 +67,6 @@ pub(crate) fn calculate_pixels_information(
     }
 }
 
-trait MinMax<T> {
-    const MIN: T;
-    const MAX: T;
-}
-
-macro_rules! impl_minmax {
-    ($t:ty) => {
-        impl MinMax<$t> for $t {
-            const MIN: $t = <$t>::MIN;
-            const MAX: $t = <$t>::MAX;
-        }
-    };
-}
-
-impl_minmax!(u8);
-impl_minmax!(u16);
-impl_minmax!(u32);
-impl_minmax!(i8);
-impl_minmax!(i16);
-impl_minmax!(i32);
-impl_minmax!(f32);
-
-fn minmax<const CH: usize, T, Out>(values: &[T]) -> ([Out; CH], [Out; CH])
-where
-    T: MinMax<T> + Copy + PartialOrd,
-    Out: From<T>,
-{
-    // let start = instant::Instant::now();
-
-    let mut min: [T; CH] = [T::MAX; CH];
-    let mut max: [T; CH] = [T::MIN; CH];
-
-    let num_pixels = values.len() / CH;
-    let mut index = 0;
-    for _ in 0..num_pixels {
-        for channel in 0..CH {
-            let value = values[index];
-            let current_min = &mut min[channel];
-            let current_max = &mut max[channel];
-            if value < *current_min {
-                *current_min = value;
-            }
-            if value > *current_max {
-                *current_max = value;
-            }
-            index += 1;
-        }
-    }
-
-    // let end = instant::Instant::now();
-    // log::debug!("minmax took {:?}", end - start);
-
-    let min: [Out; CH] = min.map(|v| Out::from(v));
-    let max: [Out; CH] = max.map(|v| Out::from(v));
-    (min, max)
-}
-
-fn f32_pixel_value_from_minmax(
-    channels: Channels,
-    min: &[f64],
-    max: &[f64],
-) -> (PixelValue, PixelValue) {
-    let mut min_pixel_value = PixelValue::new(channels, Datatype::Float32);
-    min_pixel_value.fill(f32::MAX);
-    let mut max_pixel_value = PixelValue::new(channels, Datatype::Float32);
-    max_pixel_value.fill(f32::MIN);
-
-    for ch in 0..channels as u32 {
-        *max_pixel_value.get_mut::<f32>(ch) = max[ch as usize] as f32;
-        *min_pixel_value.get_mut::<f32>(ch) = min[ch as usize] as f32;
-    }
-
-    (min_pixel_value, max_pixel_value)
-}
-
-fn make_minmax_pixel_value_from_bytes<T>(
-    channels: Channels,
-    bytes: &[u8],
-) -> (PixelValue, PixelValue)
-where
-    T: MinMax<T> + Copy + PartialOrd + bytemuck::Pod,
-    f64: From<T>,
-{
-    match channels {
-        Channels::One => {
-            let data = bytemuck::cast_slice::<u8, T>(bytes);
-            let (min, max) = minmax::<1, T, f64>(data);
-            f32_pixel_value_from_minmax(channels, &min, &max)
-        }
-        Channels::Two => {
-            let data = bytemuck::cast_slice::<u8, T>(bytes);
-            let (min, max) = minmax::<2, T, f64>(data);
-            f32_pixel_value_from_minmax(channels, &min, &max)
-        }
-        Channels::Three => {
-            let data = bytemuck::cast_slice::<u8, T>(bytes);
-            let (min, max) = minmax::<3, T, f64>(data);
-            f32_pixel_value_from_minmax(channels, &min, &max)
-        }
-        Channels::Four => {
-            let data = bytemuck::cast_slice::<u8, T>(bytes);
-            let (min, max) = minmax::<4, T, f64>(data);
-            f32_pixel_value_from_minmax(channels, &min, &max)
-        }
-    }
-}
-
-pub(crate) fn image_minmax_on_bytes(
-    bytes: &[u8],
-    datatype: Datatype,
-    channels: Channels,
-) -> (PixelValue, PixelValue) {
-    let (min, max) = match datatype {
-        Datatype::Uint8 => make_minmax_pixel_value_from_bytes::<u8>(channels, bytes),
-        Datatype::Uint16 => make_minmax_pixel_value_from_bytes::<u16>(channels, bytes),
-        Datatype::Uint32 => make_minmax_pixel_value_from_bytes::<u32>(channels, bytes),
-        Datatype::Float32 => make_minmax_pixel_value_from_bytes::<f32>(channels, bytes),
-        Datatype::Int8 => make_minmax_pixel_value_from_bytes::<i8>(channels, bytes),
-        Datatype::Int16 => make_minmax_pixel_value_from_bytes::<i16>(channels, bytes),
-        Datatype::Int32 => make_minmax_pixel_value_from_bytes::<i32>(channels, bytes),
-        Datatype::Bool => make_minmax_pixel_value_from_bytes::<u8>(channels, bytes),
-    };
-
-    // log::debug!("min: {}, max: {}", min, max);
-
-    (min, max)
-}
-
 fn collect_unique_values<T>(
     data: &[T],
 ) -> Result<Vec<PixelValue>, <PixelValue as TryFrom<T>>::Error>
@@ -250,3 +127,121 @@ pub(crate) fn calc_num_bytes_per_image(
 ) -> usize {
     calc_num_bytes_per_plane(width, height, datatype) * channels as usize
 }
+
+fn values_as_f32<T>(bytes: &[u8]) -> Vec<f32>
+where
+    T: bytemuck::Pod,
+    f64: From<T>,
+{
+    bytemuck::cast_slice::<u8, T>(bytes)
+        .iter()
+        .map(|v| f64::from(*v) as f32)
+        .collect()
+}
+
+pub(crate) fn image_values_as_f32(bytes: &[u8], datatype: Datatype) -> Vec<f32> {
+    match datatype {
+        Datatype::Uint8 => values_as_f32::<u8>(bytes),
+        Datatype::Uint16 => values_as_f32::<u16>(bytes),
+        Datatype::Uint32 => values_as_f32::<u32>(bytes),
+        Datatype::Float32 => values_as_f32::<f32>(bytes),
+        Datatype::Int8 => values_as_f32::<i8>(bytes),
+        Datatype::Int16 => values_as_f32::<i16>(bytes),
+        Datatype::Int32 => values_as_f32::<i32>(bytes),
+        Datatype::Bool => values_as_f32::<u8>(bytes),
+    }
+}
+
+/// Number of bins of the histograms in [`ImageStatistics`].
+pub(crate) const HISTOGRAM_BINS: usize = 256;
+/// Images with more unique values are not label images, their unique values are not kept.
+const MAX_UNIQUE_VALUES: usize = 1024;
+
+/// Min, max, mean, standard deviation and histogram of each channel, ignoring values that are
+/// not finite, and the unique values of single-channel integer images that have few of them.
+pub(crate) fn image_statistics_on_bytes(
+    bytes: &[u8],
+    datatype: Datatype,
+    channels: Channels,
+    data_ordering: DataOrdering,
+) -> Result<ImageStatistics> {
+    let values = image_values_as_f32(bytes, datatype);
+    let num_channels = channels as usize;
+    let plane_size = values.len() / num_channels;
+    let channel_values = |channel: usize| -> Box<dyn Iterator<Item = f32> + '_> {
+        let channel_values: Box<dyn Iterator<Item = &f32> + '_> = match data_ordering {
+            DataOrdering::HWC => Box::new(values.iter().skip(channel).step_by(num_channels)),
+            DataOrdering::CHW => {
+                Box::new(values[channel * plane_size..(channel + 1) * plane_size].iter())
+            }
+        };
+        Box::new(channel_values.copied().filter(|v| v.is_finite()))
+    };
+
+    let mut min = Vec::with_capacity(num_channels);
+    let mut max = Vec::with_capacity(num_channels);
+    let mut mean = Vec::with_capacity(num_channels);
+    let mut std = Vec::with_capacity(num_channels);
+    let mut histograms = Vec::with_capacity(num_channels);
+    for channel in 0..num_channels {
+        let (mut channel_min, mut channel_max) = (f32::MAX, f32::MIN);
+        let (mut count, mut sum, mut sum_of_squares) = (0_usize, 0_f64, 0_f64);
+        for value in channel_values(channel) {
+            channel_min = channel_min.min(value);
+            channel_max = channel_max.max(value);
+            count += 1;
+            sum += value as f64;
+            sum_of_squares += value as f64 * value as f64;
+        }
+        if count == 0 {
+            (channel_min, channel_max) = (0.0, 0.0);
+        }
+        let channel_mean = sum / count.max(1) as f64;
+        let variance = sum_of_squares / count.max(1) as f64 - channel_mean * channel_mean;
+
+        let mut histogram = vec![0_u32; HISTOGRAM_BINS];
+        let range = channel_max - channel_min;
+        for value in channel_values(channel) {
+            let bin = if range > 0.0 {
+                ((value - channel_min) / range * HISTOGRAM_BINS as f32) as usize
+            } else {
+                0
+            };
+            histogram[bin.min(HISTOGRAM_BINS - 1)] += 1;
+        }
+
+        min.push(channel_min);
+        max.push(channel_max);
+        mean.push(channel_mean as f32);
+        std.push(variance.max(0.0).sqrt() as f32);
+        histograms.push(histogram);
+    }
+
+    Ok(ImageStatistics {
+        min: PixelValue::try_from(min)?,
+        max: PixelValue::try_from(max)?,
+        mean,
+        std,
+        histograms,
+        unique_values: image_unique_values_on_bytes(bytes, datatype, channels)
+            .ok()
+            .filter(|values| values.len() <= MAX_UNIQUE_VALUES),
+    })
+}
+
+/// Element-wise `a - b`, as float32 bytes.
+pub(crate) fn image_difference_on_bytes(a: &[u8], b: &[u8], datatype: Datatype) -> Result<Vec<u8>> {
+    if a.len() != b.len() {
+        return Err(anyhow!(
+            "Cannot diff images of different sizes ({} and {} bytes)",
+            a.len(),
+            b.len()
+        ));
+    }
+    let difference = image_values_as_f32(a, datatype)
+        .into_iter()
+        .zip(image_values_as_f32(b, datatype))
+        .map(|(a, b)| a - b)
+        .collect::<Vec<f32>>();
+    Ok(bytemuck::cast_slice(&difference).to_vec())
+}
@@ -8,7 +8,9 @@ use yew::Callback;
 use yewdux::Dispatch;
 
 use crate::{
-    application_state::app_state::{AppState, UiAction},
+    application_state::app_state::{
+        AnnotationAction, AppState, LineProfileAction, UiAction, ZoomSelection,
+    },
     bindings::{lethargy_ts, lodash::debounce_closure},
     common::{
         camera,
@@ -77,6 +79,48 @@ fn get_clip_space_mouse_position(e: MouseEvent, element: &web_sys::HtmlElement)
     [clip_x, clip_y].into()
 }
 
+/// The position of the mouse in image pixel coordinates, where pixel (x, y) covers
+/// [x, x + 1) × [y, y + 1). The position is not clamped to the image.
+fn get_image_pixel_position(
+    event: &MouseEvent,
+    view_id: ViewId,
+    view_context: &dyn ViewContext,
+    view_element: &web_sys::HtmlElement,
+) -> Option<Vec2> {
+    let image_size = view_context.get_image_size_for_view(view_id)?;
+    let aspect_ratio = image_size.width / image_size.height;
+
+    let camera = view_context.get_camera_for_view(view_id);
+    let element_size = Size {
+        width: view_element.client_width() as f32,
+        height: view_element.client_height() as f32,
+    };
+
+    let clip_coordinates = get_clip_space_mouse_position(event.clone(), view_element);
+
+    let view_projection =
+        camera::calculate_view_projection(&element_size, &VIEW_SIZE, &camera, aspect_ratio);
+    let mouse_position = view_projection.inverse() * clip_coordinates.to_hom();
+
+    Some(Vec2::new(
+        mouse_position.x * image_size.width,
+        mouse_position.y * image_size.height,
+    ))
+}
+
+fn is_zoom_selection_event(event: &MouseEvent) -> bool {
+    event.ctrl_key() || event.meta_key()
+}
+
+/// The position of the mouse in CSS pixels relative to the element.
+fn get_element_mouse_position(event: &MouseEvent, element: &web_sys::HtmlElement) -> Vec2 {
+    let rect = element.get_bounding_client_rect();
+    Vec2::new(
+        event.client_x() as f32 - rect.left() as f32,
+        event.client_y() as f32 - rect.top() as f32,
+    )
+}
+
 pub(crate) struct PanHandler {
     is_panning: bool,
     start_camera: camera::Camera,
@@ -109,6 +153,15 @@ impl PanHandler {
                 let event = event
                     .dyn_ref::<web_sys::MouseEvent>()
                     .expect("Unable to cast event to MouseEvent");
+                // dragging draws instead while the line profile or an annotation tool is active,
+                // and selects a rectangle to zoom into with ctrl
+                let state = Dispatch::<AppState>::global().get();
+                if state.line_profiles.borrow().enabled
+                    || state.annotations.borrow().is_active()
+                    || is_zoom_selection_event(event)
+                {
+                    return;
+                }
                 let camera = view_context.get_camera_for_view(view_id);
                 let element_size = Size {
                     width: view_element.client_width() as f32,
@@ -120,7 +173,8 @@ impl PanHandler {
                 };
                 let aspect_ratio = image_size.width / image_size.height;
 
-                let start_in_view_projection_matrix = camera::calculate_view_projection(
+                // the camera pans in the view coordinates of the turned image
+                let start_in_view_projection_matrix = camera::calculate_camera_projection(
                     &element_size,
                     &VIEW_SIZE,
                     &camera,
@@ -253,7 +307,6 @@ impl ZoomHandler {
                             &camera,
                             aspect_ratio,
                         );
-                        let view_projection_matrix_inv = view_projection.inverse();
                         let image_size = match view_context.get_image_size_for_view(view_id) {
                             Some(it) => it,
                             None => return,
@@ -281,18 +334,26 @@ impl ZoomHandler {
                             &view_element,
                         );
 
+                        // the camera zooms in the view coordinates of the turned image
+                        let view_projection_matrix_inv = camera::calculate_camera_projection(
+                            &element_size,
+                            &VIEW_SIZE,
+                            &camera,
+                            aspect_ratio,
+                        )
+                        .inverse();
                         let pre_zoom_position =
                             (view_projection_matrix_inv * clip_coordinates.to_hom()).xy();
 
                         let new_zoom = camera.zoom * (f32::powf(2.0, delta_y as f32 / 100.0));
-                        let new_zoom = f32::max(new_zoom, 0.5);
+                        let new_zoom = f32::max(new_zoom, camera::MIN_ZOOM);
 
                         let new_camera = camera::Camera {
                             zoom: new_zoom,
                             ..camera
                         };
 
-                        let view_projection_matrix_inv = camera::calculate_view_projection(
+                        let view_projection_matrix_inv = camera::calculate_camera_projection(
                             &element_size,
                             &VIEW_SIZE,
                             &new_camera,
@@ -359,38 +420,14 @@ impl PixelHoverHandler {
                 let event = event
                     .dyn_ref::<web_sys::MouseEvent>()
                     .expect("Unable to cast event to MouseEvent");
-                let image_size = match view_context.get_image_size_for_view(view_id) {
-                    Some(it) => it,
-                    None => return,
-                };
-                let aspect_ratio = image_size.width / image_size.height;
-
-                let camera = view_context.get_camera_for_view(view_id);
-                let element_size = Size {
-                    width: view_element.client_width() as f32,
-                    height: view_element.client_height() as f32,
+                let Some(image_size) = view_context.get_image_size_for_view(view_id) else {
+                    return;
                 };
-
-                let clip_coordinates = get_clip_space_mouse_position(event.clone(), &view_element);
-
-                let view_projection = camera::calculate_view_projection(
-                    &element_size,
-                    &VIEW_SIZE,
-                    &camera,
-                    aspect_ratio,
-                );
-                let view_projection_matrix_inv = view_projection.inverse();
-                let image_size = match view_context.get_image_size_for_view(view_id) {
-                    Some(it) => it,
-                    None => return,
+                let Some(mouse_position_pixels) =
+                    get_image_pixel_position(event, view_id, view_context.as_ref(), &view_element)
+                else {
+                    return;
                 };
-
-                let mouse_position = view_projection_matrix_inv * clip_coordinates.to_hom();
-
-                let mouse_position_pixels = Vec2::new(
-                    mouse_position.x * image_size.width,
-                    mouse_position.y * image_size.height,
-                );
                 let mouse_position_pixels = mouse_position_pixels.floor();
 
                 if mouse_position_pixels.x < 0.0
@@ -436,6 +473,324 @@ impl PixelHoverHandler {
     }
 }
 
+pub(crate) struct LineProfileHandler;
+
+impl LineProfileHandler {
+    pub(crate) fn install(
+        view_id: ViewId,
+        view_context: Rc<dyn ViewContext>,
+    ) -> Vec<EventListener> {
+        let view_element = view_context.get_view_element(view_id);
+
+        let position_of = {
+            let view_element = view_element.clone();
+            move |event: &Event| {
+                let event = event
+                    .dyn_ref::<web_sys::MouseEvent>()
+                    .expect("Unable to cast event to MouseEvent");
+                get_image_pixel_position(event, view_id, view_context.as_ref(), &view_element)
+            }
+        };
+        let is_enabled = || {
+            Dispatch::<AppState>::global()
+                .get()
+                .line_profiles
+                .borrow()
+                .enabled
+        };
+        let is_drawing = move || {
+            Dispatch::<AppState>::global()
+                .get()
+                .line_profiles
+                .borrow()
+                .get(view_id)
+                .is_drawing
+        };
+
+        let mousedown = {
+            let position_of = position_of.clone();
+            Callback::from(move |event: Event| {
+                if !is_enabled() {
+                    return;
+                }
+                if let Some(position) = position_of(&event) {
+                    Dispatch::<AppState>::global()
+                        .apply(LineProfileAction::StartLine(view_id, position));
+                }
+            })
+        };
+        let mousemove = Callback::from(move |event: Event| {
+            if !is_drawing() {
+                return;
+            }
+            if let Some(position) = position_of(&event) {
+                Dispatch::<AppState>::global()
+                    .apply(LineProfileAction::MoveLineEnd(view_id, position));
+            }
+        });
+        let mouseup = Callback::from(move |_event: Event| {
+            if is_drawing() {
+                Dispatch::<AppState>::global().apply(LineProfileAction::FinishLine(view_id));
+            }
+        });
+
+        vec![
+            EventListener::new(&view_element, "mousedown", move |e| {
+                mousedown.emit(e.clone())
+            }),
+            EventListener::new(&view_element, "mousemove", move |e| {
+                mousemove.emit(e.clone())
+            }),
+            EventListener::new(&view_element, "mouseup", {
+                let mouseup = mouseup.clone();
+                move |e| mouseup.emit(e.clone())
+            }),
+            EventListener::new(&view_element, "mouseleave", move |e| {
+                mouseup.emit(e.clone())
+            }),
+        ]
+    }
+}
+
+pub(crate) struct AnnotationHandler;
+
+impl AnnotationHandler {
+    pub(crate) fn install(
+        view_id: ViewId,
+        view_context: Rc<dyn ViewContext>,
+    ) -> Vec<EventListener> {
+        let view_element = view_context.get_view_element(view_id);
+
+        let position_of = {
+            let view_element = view_element.clone();
+            move |event: &MouseEvent| {
+                get_image_pixel_position(event, view_id, view_context.as_ref(), &view_element)
+            }
+        };
+        let is_active = || {
+            Dispatch::<AppState>::global()
+                .get()
+                .annotations
+                .borrow()
+                .is_active()
+        };
+
+        let mousedown = {
+            let position_of = position_of.clone();
+            Callback::from(move |event: Event| {
+                let event = event
+                    .dyn_ref::<web_sys::MouseEvent>()
+                    .expect("Unable to cast event to MouseEvent");
+                if !is_active() || event.button() != 0 {
+                    return;
+                }
+                if let Some(position) = position_of(event) {
+                    Dispatch::<AppState>::global()
+                        .apply(AnnotationAction::Press(view_id, position));
+                }
+            })
+        };
+        let mousemove = Callback::from(move |event: Event| {
+            if !is_active() {
+                return;
+            }
+            let event = event
+                .dyn_ref::<web_sys::MouseEvent>()
+                .expect("Unable to cast event to MouseEvent");
+            Dispatch::<AppState>::global()
+                .apply(AnnotationAction::Move(view_id, position_of(event)));
+        });
+        let mouseup = Callback::from(move |_event: Event| {
+            if is_active() {
+                Dispatch::<AppState>::global().apply(AnnotationAction::Release(view_id));
+            }
+        });
+        let mouseleave = Callback::from(move |_event: Event| {
+            if is_active() {
+                let dispatch = Dispatch::<AppState>::global();
+                dispatch.apply(AnnotationAction::Release(view_id));
+                dispatch.apply(AnnotationAction::Move(view_id, None));
+            }
+        });
+        let dblclick = Callback::from(move |_event: Event| {
+            if is_active() {
+                Dispatch::<AppState>::global().apply(AnnotationAction::FinishPolygon(view_id));
+            }
+        });
+
+        vec![
+            EventListener::new(&view_element, "mousedown", move |e| {
+                mousedown.emit(e.clone())
+            }),
+            EventListener::new(&view_element, "mousemove", move |e| {
+                mousemove.emit(e.clone())
+            }),
+            EventListener::new(&view_element, "mouseup", move |e| mouseup.emit(e.clone())),
+            EventListener::new(&view_element, "mouseleave", move |e| {
+                mouseleave.emit(e.clone())
+            }),
+            EventListener::new(&view_element, "dblclick", move |e| dblclick.emit(e.clone())),
+        ]
+    }
+}
+
+/// Ctrl + drag selects a rectangle to zoom into.
+pub(crate) struct ZoomSelectionHandler {
+    /// Where the selection started, in image pixel coordinates.
+    start: Option<Vec2>,
+}
+
+impl ZoomSelectionHandler {
+    pub(crate) fn install(
+        view_id: ViewId,
+        view_context: Rc<dyn ViewContext>,
+    ) -> Vec<EventListener> {
+        let handler = Rc::new(RefCell::new(Self { start: None }));
+        let view_element = view_context.get_view_element(view_id);
+
+        let position_of = {
+            let view_element = view_element.clone();
+            move |event: &MouseEvent| {
+                get_image_pixel_position(event, view_id, view_context.as_ref(), &view_element)
+            }
+        };
+        let is_enabled = || {
+            let state = Dispatch::<AppState>::global().get();
+            let is_enabled =
+                !state.line_profiles.borrow().enabled && !state.annotations.borrow().is_active();
+            is_enabled
+        };
+
+        let mousedown = {
+            let handler = Rc::clone(&handler);
+            let view_element = view_element.clone();
+            let position_of = position_of.clone();
+            Callback::from(move |event: Event| {
+                let event = event
+                    .dyn_ref::<web_sys::MouseEvent>()
+                    .expect("Unable to cast event to MouseEvent");
+                if event.button() != 0 || !is_zoom_selection_event(event) || !is_enabled() {
+                    return;
+                }
+                let Some(start) = position_of(event) else {
+                    return;
+                };
+                handler.borrow_mut().start = Some(start);
+                let position = get_element_mouse_position(event, &view_element);
+                Dispatch::<AppState>::global().apply(UiAction::SetZoomSelection(Some(
+                    ZoomSelection {
+                        view_id,
+                        start: position,
+                        end: position,
+                    },
+                )));
+            })
+        };
+        let mousemove = {
+            let handler = Rc::clone(&handler);
+            let view_element = view_element.clone();
+            Callback::from(move |event: Event| {
+                if handler.borrow().start.is_none() {
+                    return;
+                }
+                let event = event
+                    .dyn_ref::<web_sys::MouseEvent>()
+                    .expect("Unable to cast event to MouseEvent");
+                let end = get_element_mouse_position(event, &view_element);
+                Dispatch::<AppState>::global().reduce_mut(|state| {
+                    if let Some(selection) = state.zoom_selection.as_mut() {
+                        selection.end = end;
+                    }
+                });
+            })
+        };
+        let mouseup = {
+            let handler = Rc::clone(&handler);
+            Callback::from(move |event: Event| {
+                let Some(start) = handler.borrow_mut().start.take() else {
+                    return;
+                };
+                let event = event
+                    .dyn_ref::<web_sys::MouseEvent>()
+                    .expect("Unable to cast event to MouseEvent");
+                let dispatch = Dispatch::<AppState>::global();
+                dispatch.apply(UiAction::SetZoomSelection(None));
+                if let Some(end) = position_of(event) {
+                    // a click without dragging is not a selection
+                    if (end - start).abs().min_element() >= 1.0 {
+                        dispatch.apply(UiAction::ZoomToRect(view_id, start, end));
+                    }
+                }
+            })
+        };
+        let mouseleave = Callback::from(move |_event: Event| {
+            if handler.borrow_mut().start.take().is_some() {
+                Dispatch::<AppState>::global().apply(UiAction::SetZoomSelection(None));
+            }
+        });
+
+        vec![
+            EventListener::new(&view_element, "mousedown", move |e| {
+                mousedown.emit(e.clone())
+            }),
+            EventListener::new(&view_element, "mousemove", move |e| {
+                mousemove.emit(e.clone())
+            }),
+            EventListener::new(&view_element, "mouseup", move |e| mouseup.emit(e.clone())),
+            EventListener::new(&view_element, "mouseleave", move |e| {
+                mouseleave.emit(e.clone())
+            }),
+        ]
+    }
+}
+
+pub(crate) struct ContextMenuHandler;
+
+impl ContextMenuHandler {
+    /// Calls `callback` with the mouse event and the pixel under the mouse on right click inside
+    /// the image.
+    pub(crate) fn install(
+        view_id: ViewId,
+        view_context: Rc<dyn ViewContext>,
+        callback: Callback<(MouseEvent, UVec2)>,
+    ) -> EventListener {
+        let view_element = view_context.get_view_element(view_id);
+
+        let contextmenu = {
+            let view_element = view_element.clone();
+            Callback::from(move |event: Event| {
+                let event = event
+                    .dyn_ref::<web_sys::MouseEvent>()
+                    .expect("Unable to cast event to MouseEvent");
+                let Some(image_size) = view_context.get_image_size_for_view(view_id) else {
+                    return;
+                };
+                let Some(position) =
+                    get_image_pixel_position(event, view_id, view_context.as_ref(), &view_element)
+                else {
+                    return;
+                };
+                let position = position.floor();
+                if position.x < 0.0
+                    || position.y < 0.0
+                    || position.x >= image_size.width
+                    || position.y >= image_size.height
+                {
+                    return;
+                }
+                let pixel = UVec2::new(position.x as u32, position.y as u32);
+                event.prevent_default();
+                callback.emit((event.clone(), pixel));
+            })
+        };
+
+        let options = EventListenerOptions::enable_prevent_default();
+        EventListener::new_with_options(&view_element, "contextmenu", options, move |e| {
+            contextmenu.emit(e.clone())
+        })
+    }
+}
+
 pub(crate) struct ShiftScrollHandler;
 
 impl ShiftScrollHandler {
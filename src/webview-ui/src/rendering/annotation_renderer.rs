@@ -0,0 +1,287 @@
+use anyhow::Result;
+use glam::{Mat3, Vec2, Vec4};
+use std::collections::HashMap;
+use std::rc::Rc;
+use web_sys::WebGl2RenderingContext;
+use web_sys::WebGl2RenderingContext as GL;
+
+use crate::application_state::annotations::{label_color, LabelMask};
+use crate::common::camera;
+use crate::common::{Datatype, Size, ViewId, ViewableObjectId};
+use crate::webgl_utils::draw::draw_buffer_info;
+use crate::webgl_utils::program::{set_buffers_and_attributes, set_uniforms};
+use crate::webgl_utils::{self, BufferInfo, DrawMode, GLGuard, ProgramBundle, UniformValue};
+
+use super::constants::VIEW_SIZE;
+use super::image_renderer::create_image_plane_attributes;
+use super::line_profile_renderer::create_line_attributes;
+use super::rendering_context::{AnnotationData, RenderingContext};
+use super::utils::scissor_view;
+
+const MASK_OPACITY: f32 = 0.5;
+const VERTEX_SIZE: f32 = 5.0;
+
+fn label_color_vec4(label: u8) -> Vec4 {
+    let [r, g, b] = label_color(label);
+    Vec4::new(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0, 1.0)
+}
+
+fn create_mask_texture(gl: &GL, mask: &LabelMask) -> Result<GLGuard<web_sys::WebGlTexture>> {
+    gl.pixel_storei(GL::UNPACK_ALIGNMENT, 1);
+    webgl_utils::textures::create_texture_from_bytes(
+        gl,
+        &mask.data,
+        mask.width,
+        mask.height,
+        1, // channels
+        Datatype::Uint8,
+        webgl_utils::types::CreateTextureParametersBuilder::default()
+            .mag_filter(webgl_utils::constants::TextureMagFilter::Nearest)
+            .min_filter(webgl_utils::constants::TextureMinFilter::Nearest)
+            .wrap_s(webgl_utils::constants::TextureWrap::ClampToEdge)
+            .wrap_t(webgl_utils::constants::TextureWrap::ClampToEdge)
+            .build()
+            .unwrap(),
+    )
+}
+
+/// The mask of an image as uploaded to the GPU.
+struct MaskTexture {
+    image_id: ViewableObjectId,
+    revision: u32,
+    texture: GLGuard<web_sys::WebGlTexture>,
+}
+
+struct Programs {
+    mask: ProgramBundle,
+    line: ProgramBundle,
+}
+
+struct RenderingData {
+    programs: Programs,
+    image_plane_buffer: BufferInfo,
+    line_buffer: BufferInfo,
+    mask_textures: HashMap<ViewId, MaskTexture>,
+}
+
+pub(crate) struct AnnotationRenderer {}
+
+impl AnnotationRenderer {
+    pub(crate) fn setup_rendering_callback(
+        rendering_context: Rc<dyn RenderingContext>,
+    ) -> Result<Box<dyn FnMut()>> {
+        let gl = rendering_context.gl().clone();
+
+        let programs = Self::create_programs(&gl)?;
+        let image_plane_buffer =
+            create_image_plane_attributes(&gl, 0.0, 0.0, VIEW_SIZE.width, VIEW_SIZE.height)?;
+        let line_buffer = create_line_attributes(&gl)?;
+
+        let mut rendering_data = RenderingData {
+            programs,
+            image_plane_buffer,
+            line_buffer,
+            mask_textures: HashMap::new(),
+        };
+
+        Ok(Box::new(move || {
+            AnnotationRenderer::render(&gl, &mut rendering_data, rendering_context.as_ref());
+        }))
+    }
+
+    fn create_programs(gl: &WebGl2RenderingContext) -> Result<Programs> {
+        let mask = webgl_utils::program::GLProgramBuilder::create(gl)
+            .vertex_shader(include_str!("../shaders/image.vert"))
+            .fragment_shader(include_str!("../shaders/mask.frag"))
+            .attribute("vin_position")
+            .build()?;
+        let line = webgl_utils::program::GLProgramBuilder::create(gl)
+            .vertex_shader(include_str!("../shaders/line.vert"))
+            .fragment_shader(include_str!("../shaders/line.frag"))
+            .attribute("vin_position")
+            .build()?;
+        Ok(Programs { mask, line })
+    }
+
+    fn render(
+        gl: &WebGl2RenderingContext,
+        rendering_data: &mut RenderingData,
+        rendering_context: &dyn RenderingContext,
+    ) {
+        for view_id in rendering_context.visible_nodes() {
+            match rendering_context.get_annotation_data(view_id) {
+                Some(ref data) => {
+                    if let Err(e) = Self::render_annotations(gl, rendering_data, view_id, data) {
+                        log::error!("AnnotationRenderer::render: {}", e);
+                    }
+                }
+                None => {
+                    rendering_data.mask_textures.remove(&view_id);
+                }
+            }
+        }
+    }
+
+    fn render_annotations(
+        gl: &WebGl2RenderingContext,
+        rendering_data: &mut RenderingData,
+        view_id: ViewId,
+        data: &AnnotationData,
+    ) -> Result<()> {
+        scissor_view(gl, &data.html_element);
+
+        let html_element_size = Size {
+            width: data.html_element.client_width() as f32,
+            height: data.html_element.client_height() as f32,
+        };
+        let aspect_ratio = data.image_size.width / data.image_size.height;
+        let view_projection = camera::calculate_view_projection(
+            &html_element_size,
+            &VIEW_SIZE,
+            &data.camera,
+            aspect_ratio,
+        );
+
+        let annotations = data.annotations.borrow();
+        let image_annotations = annotations.get(&data.image_id);
+
+        match image_annotations.and_then(|a| a.mask.as_ref()) {
+            Some(mask) => {
+                let is_outdated = rendering_data
+                    .mask_textures
+                    .get(&view_id)
+                    .map_or(true, |t| {
+                        t.image_id != data.image_id || t.revision != mask.revision
+                    });
+                if is_outdated {
+                    let texture = create_mask_texture(gl, mask)?;
+                    rendering_data.mask_textures.insert(
+                        view_id,
+                        MaskTexture {
+                            image_id: data.image_id.clone(),
+                            revision: mask.revision,
+                            texture,
+                        },
+                    );
+                }
+                Self::render_mask(gl, rendering_data, view_id, view_projection);
+            }
+            None => {
+                rendering_data.mask_textures.remove(&view_id);
+            }
+        }
+
+        let image_pixels_to_view = Mat3::from_scale(Vec2::new(
+            VIEW_SIZE.width / data.image_size.width,
+            VIEW_SIZE.height / data.image_size.height,
+        ));
+        let projection = view_projection * image_pixels_to_view;
+
+        for shape in image_annotations.iter().flat_map(|a| a.shapes.iter()) {
+            let color = label_color_vec4(shape.label());
+            Self::render_outline(
+                gl,
+                rendering_data,
+                projection,
+                &shape.outline(),
+                true,
+                color,
+            );
+        }
+
+        if let Some((points, closed)) = annotations.draft(view_id).outline() {
+            let color = label_color_vec4(annotations.label);
+            Self::render_outline(gl, rendering_data, projection, &points, closed, color);
+            Self::render_vertices(gl, rendering_data, projection, &points, color);
+        }
+
+        Ok(())
+    }
+
+    fn render_mask(
+        gl: &WebGl2RenderingContext,
+        rendering_data: &RenderingData,
+        view_id: ViewId,
+        view_projection: Mat3,
+    ) {
+        let Some(mask_texture) = rendering_data.mask_textures.get(&view_id) else {
+            return;
+        };
+        let program = &rendering_data.programs.mask;
+        let buffer = &rendering_data.image_plane_buffer;
+        gl.use_program(Some(&program.program));
+        set_buffers_and_attributes(program, buffer);
+
+        let uniform_values = HashMap::from([
+            (
+                "u_projectionMatrix",
+                UniformValue::Mat3Owned(view_projection),
+            ),
+            ("u_mask", UniformValue::Texture(&mask_texture.texture)),
+            ("u_opacity", UniformValue::FloatOwned(MASK_OPACITY)),
+        ]);
+        set_uniforms(program, &uniform_values);
+        draw_buffer_info(gl, buffer, DrawMode::Triangles);
+    }
+
+    /// Draws the outline one segment at a time, with the line program of the line profile.
+    fn render_outline(
+        gl: &WebGl2RenderingContext,
+        rendering_data: &RenderingData,
+        projection: Mat3,
+        points: &[Vec2],
+        closed: bool,
+        color: Vec4,
+    ) {
+        let program = &rendering_data.programs.line;
+        let buffer = &rendering_data.line_buffer;
+        gl.use_program(Some(&program.program));
+        set_buffers_and_attributes(program, buffer);
+
+        let closing_segment = closed
+            .then(|| points.last().zip(points.first()))
+            .flatten()
+            .map(|(last, first)| (*last, *first));
+        let segments = points
+            .windows(2)
+            .map(|w| (w[0], w[1]))
+            .chain(closing_segment);
+
+        let mut uniform_values = HashMap::from([
+            ("u_projectionMatrix", UniformValue::Mat3Owned(projection)),
+            ("u_point_size", UniformValue::FloatOwned(1.0)),
+            ("u_color", UniformValue::Vec4Owned(color)),
+        ]);
+        for (start, end) in segments {
+            uniform_values.insert("u_start", UniformValue::Vec2Owned(start));
+            uniform_values.insert("u_end", UniformValue::Vec2Owned(end));
+            set_uniforms(program, &uniform_values);
+            draw_buffer_info(gl, buffer, DrawMode::Lines);
+        }
+    }
+
+    fn render_vertices(
+        gl: &WebGl2RenderingContext,
+        rendering_data: &RenderingData,
+        projection: Mat3,
+        points: &[Vec2],
+        color: Vec4,
+    ) {
+        let program = &rendering_data.programs.line;
+        let buffer = &rendering_data.line_buffer;
+        gl.use_program(Some(&program.program));
+        set_buffers_and_attributes(program, buffer);
+
+        let mut uniform_values = HashMap::from([
+            ("u_projectionMatrix", UniformValue::Mat3Owned(projection)),
+            ("u_point_size", UniformValue::FloatOwned(VERTEX_SIZE)),
+            ("u_color", UniformValue::Vec4Owned(color)),
+        ]);
+        for point in points {
+            uniform_values.insert("u_start", UniformValue::Vec2Owned(*point));
+            uniform_values.insert("u_end", UniformValue::Vec2Owned(*point));
+            set_uniforms(program, &uniform_values);
+            draw_buffer_info(gl, buffer, DrawMode::Points);
+        }
+    }
+}
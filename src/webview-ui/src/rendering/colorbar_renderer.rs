@@ -146,8 +146,12 @@ impl ColorBarRenderer {
                 ..data.drawing_options
             },
         );
+        let (min, max) = texture_image.computed_info.range(
+            data.drawing_options.batch_item.unwrap_or(0),
+            data.drawing_options.normalize_per_batch_item,
+        );
         let min_value_normalized = {
-            let mut min = texture_image.computed_info.min.as_rgba_f32()[0];
+            let mut min = min.as_rgba_f32()[0];
             if let Some(clip_min) = clip.min {
                 min = clip_min;
             }
@@ -156,7 +160,7 @@ impl ColorBarRenderer {
                 + coloring_factors.color_addition.x
         };
         let max_value_normalized = {
-            let mut max = texture_image.computed_info.max.as_rgba_f32()[0];
+            let mut max = max.as_rgba_f32()[0];
             if let Some(clip_max) = clip.max {
                 max = clip_max;
             }
@@ -168,8 +172,7 @@ impl ColorBarRenderer {
         let colormap_name = &data.global_drawing_options.heatmap_colormap_name;
 
         let colormap_texture: web_sys::WebGlTexture = rendering_context
-            .get_color_map_texture(colormap_name)
-            .expect("Could not get color map texture")
+            .get_color_map_texture(colormap_name)?
             .obj
             .clone();
 
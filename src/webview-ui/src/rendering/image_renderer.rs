@@ -10,8 +10,10 @@ use glam::{Mat3, UVec2, Vec2, Vec4};
 use web_sys::{WebGl2RenderingContext as GL, WebGl2RenderingContext};
 
 use crate::application_state::app_state::GlobalDrawingOptions;
+use crate::application_state::image_errors::ImageError;
 use crate::application_state::images::DrawingContext;
 use crate::application_state::images::ImageAvailability;
+use crate::application_state::thumbnails::{Thumbnail, ThumbnailKey, THUMBNAIL_SIZE};
 use crate::application_state::views::OverlayItem;
 use crate::coloring;
 use crate::coloring::{calculate_color_matrix, Coloring, DrawingOptions};
@@ -26,10 +28,12 @@ use crate::common::DataOrdering;
 use crate::common::Datatype;
 use crate::common::Size;
 use crate::common::ViewId;
+use crate::common::ViewableObjectId;
 use crate::math_utils::image_calculations::calculate_pixels_information;
 use crate::webgl_utils;
 use crate::webgl_utils::attributes::{create_buffer_info_from_arrays, Arrays};
 use crate::webgl_utils::draw::draw_buffer_info;
+use crate::webgl_utils::error::WebGlError;
 use crate::webgl_utils::program::{set_buffers_and_attributes, set_uniforms};
 use crate::webgl_utils::types::*;
 
@@ -62,11 +66,21 @@ struct RenderingData {
     programs: Programs,
     text_renderer: PixelTextRenderer,
     placeholder_texture: GLGuard<web_sys::WebGlTexture>,
+    thumbnail_target: ThumbnailTarget,
 
     image_plane_buffer: BufferInfo,
 }
 
-fn create_image_plane_attributes(
+/// Offscreen framebuffer the thumbnails are rendered into, before reading them back.
+struct ThumbnailTarget {
+    framebuffer: GLGuard<web_sys::WebGlFramebuffer>,
+    _texture: GLGuard<web_sys::WebGlTexture>,
+}
+
+/// Rendering a thumbnail reads pixels back from the GPU, so only a few are rendered per frame.
+const MAX_THUMBNAILS_PER_FRAME: usize = 2;
+
+pub(super) fn create_image_plane_attributes(
     gl: &GL,
     x: f32,
     y: f32,
@@ -138,6 +152,65 @@ fn create_placeholder_texture(gl: &GL) -> Result<GLGuard<web_sys::WebGlTexture>>
     )
 }
 
+fn create_thumbnail_target(gl: &GL) -> Result<ThumbnailTarget> {
+    let texture = webgl_utils::gl_guarded(gl.clone(), |gl| {
+        gl.create_texture()
+            .ok_or_else(|| WebGlError::last_webgl_error_or_unknown(gl, "create_texture"))
+    })?;
+    gl.bind_texture(GL::TEXTURE_2D, Some(&texture));
+    gl.tex_storage_2d(
+        GL::TEXTURE_2D,
+        1,
+        GL::RGBA8,
+        THUMBNAIL_SIZE as i32,
+        THUMBNAIL_SIZE as i32,
+    );
+
+    let framebuffer = webgl_utils::gl_guarded(gl.clone(), |gl| {
+        gl.create_framebuffer()
+            .ok_or_else(|| WebGlError::last_webgl_error_or_unknown(gl, "create_framebuffer"))
+    })?;
+    gl.bind_framebuffer(GL::FRAMEBUFFER, Some(&framebuffer));
+    gl.framebuffer_texture_2d(
+        GL::FRAMEBUFFER,
+        GL::COLOR_ATTACHMENT0,
+        GL::TEXTURE_2D,
+        Some(&texture),
+        0,
+    );
+    gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+
+    Ok(ThumbnailTarget {
+        framebuffer,
+        _texture: texture,
+    })
+}
+
+/// The colormap texture of the coloring, if it uses one. A colormap that can't be used is
+/// reported as an error of the image.
+fn colormap_texture(
+    rendering_context: &dyn RenderingContext,
+    image_id: &ViewableObjectId,
+    drawing_options: &DrawingOptions,
+    global_drawing_options: &GlobalDrawingOptions,
+) -> Option<web_sys::WebGlTexture> {
+    let colormap_name = match drawing_options.coloring {
+        Coloring::Heatmap => &global_drawing_options.heatmap_colormap_name,
+        Coloring::Segmentation | Coloring::Edges => {
+            &global_drawing_options.segmentation_colormap_name
+        }
+        _ => return None,
+    };
+    match rendering_context.get_color_map_texture(colormap_name) {
+        Ok(color_map_texture) => Some(color_map_texture.obj.clone()),
+        Err(e) => {
+            rendering_context
+                .report_image_error(image_id, ImageError::Colormap(format!("{:#}", e)));
+            None
+        }
+    }
+}
+
 fn text_color(pixel_color: Vec4, drawing_options: &DrawingOptions) -> Vec4 {
     let multipliers: [f32; 3] = [0.299, 0.587, 0.114];
     let mut gray = multipliers[0] * pixel_color.x
@@ -184,6 +257,8 @@ impl ImageRenderer {
 
         let placeholder_texture = create_placeholder_texture(&gl).unwrap();
 
+        let thumbnail_target = create_thumbnail_target(&gl).unwrap();
+
         let image_plane_attributes =
             create_image_plane_attributes(&gl, 0.0, 0.0, VIEW_SIZE.width, VIEW_SIZE.height)
                 .unwrap();
@@ -202,6 +277,7 @@ impl ImageRenderer {
             programs,
             text_renderer,
             placeholder_texture,
+            thumbnail_target,
             image_plane_buffer: image_plane_attributes,
         };
 
@@ -275,6 +351,121 @@ impl ImageRenderer {
         if let Err(e) = render_result {
             log::error!("Renderer::render: {}", e);
         }
+
+        for (image_id, key) in rendering_context
+            .thumbnails_to_render()
+            .into_iter()
+            .take(MAX_THUMBNAILS_PER_FRAME)
+        {
+            let thumbnail = ImageRenderer::render_thumbnail(
+                gl,
+                rendering_data,
+                rendering_context,
+                &image_id,
+                &key,
+            );
+            match thumbnail {
+                Result::Ok(Some(pixels)) => rendering_context.set_thumbnail(
+                    image_id,
+                    Thumbnail {
+                        key,
+                        pixels: Rc::new(pixels),
+                    },
+                ),
+                Result::Ok(None) => {}
+                Err(e) => log::error!("Renderer::render_thumbnail: {}", e),
+            }
+        }
+    }
+
+    /// Renders the image fitted in the thumbnail framebuffer, and reads back its RGBA pixels,
+    /// top row first. Returns `None` if the texture is not available.
+    fn render_thumbnail(
+        gl: &WebGl2RenderingContext,
+        rendering_data: &RenderingData,
+        rendering_context: &dyn RenderingContext,
+        image_id: &ViewableObjectId,
+        key: &ThumbnailKey,
+    ) -> Result<Option<Vec<u8>>> {
+        let ImageAvailability::Available(texture) = rendering_context.texture_by_id(image_id)
+        else {
+            return Ok(None);
+        };
+        let texture = texture.borrow();
+        let batch_item = key.drawing_options.batch_item;
+        if !texture.textures.contains_key(&batch_item.unwrap_or(0)) {
+            return Ok(None);
+        }
+
+        let (_, global_drawing_options) =
+            rendering_context.drawing_options(image_id, &DrawingContext::BaseImage);
+        let colormap_texture = colormap_texture(
+            rendering_context,
+            image_id,
+            &key.drawing_options,
+            &global_drawing_options,
+        );
+
+        let size = THUMBNAIL_SIZE as i32;
+        gl.bind_framebuffer(
+            GL::FRAMEBUFFER,
+            Some(&rendering_data.thumbnail_target.framebuffer),
+        );
+        gl.viewport(0, 0, size, size);
+        gl.scissor(0, 0, size, size);
+        gl.clear_color(0.0, 0.0, 0.0, 0.0);
+        gl.clear(GL::COLOR_BUFFER_BIT);
+
+        let program = ImageRenderer::program_for_texture(&texture, &rendering_data.programs);
+        let mut uniform_values = HashMap::new();
+        ImageRenderer::prepare_texture_uniforms(
+            rendering_context,
+            rendering_data,
+            &texture,
+            colormap_texture.as_ref(),
+            batch_item,
+            &Size {
+                width: THUMBNAIL_SIZE as f32,
+                height: THUMBNAIL_SIZE as f32,
+            },
+            &camera::Camera::default(),
+            &DrawingContext::BaseImage,
+            &mut uniform_values,
+        );
+        uniform_values.insert("u_is_overlay", UniformValue::Bool(&false));
+        uniform_values.insert("u_overlay_alpha", UniformValue::Float(&0.0));
+        uniform_values.insert(
+            "u_zeros_as_transparent",
+            UniformValue::Bool(&key.drawing_options.zeros_as_transparent),
+        );
+
+        gl.use_program(Some(&program.program));
+        set_uniforms(program, &uniform_values);
+        set_buffers_and_attributes(program, &rendering_data.image_plane_buffer);
+        draw_buffer_info(gl, &rendering_data.image_plane_buffer, DrawMode::Triangles);
+
+        let mut pixels = vec![0_u8; (THUMBNAIL_SIZE * THUMBNAIL_SIZE * 4) as usize];
+        let read_result = gl.read_pixels_with_opt_u8_array(
+            0,
+            0,
+            size,
+            size,
+            GL::RGBA,
+            GL::UNSIGNED_BYTE,
+            Some(&mut pixels),
+        );
+        gl.bind_framebuffer(GL::FRAMEBUFFER, None);
+        read_result.map_err(|jsvalue| WebGlError::from_js_value(&jsvalue, "read_pixels"))?;
+
+        // WebGL reads the bottom row first
+        let row_length = (THUMBNAIL_SIZE * 4) as usize;
+        let pixels = pixels
+            .chunks_exact(row_length)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+        Ok(Some(pixels))
     }
 
     fn render_view(
@@ -399,27 +590,22 @@ impl ImageRenderer {
         texture: &'a TextureImage,
         colormap_texture: Option<&'a web_sys::WebGlTexture>,
         batch_item: Option<u32>,
-        image_view_data: &ImageViewData,
+        html_element_size: &Size,
+        camera: &camera::Camera,
         drawing_context: &DrawingContext,
         uniform_values: &mut HashMap<&'static str, UniformValue<'a>>,
     ) {
         let texture_info = &texture.info;
         let config = rendering_context.rendering_configuration();
 
-        let html_element_size = Size {
-            width: image_view_data.html_element.client_width() as f32,
-            height: image_view_data.html_element.client_height() as f32,
-        };
-        let camera = &image_view_data.camera;
-
         let image_size = texture.image_size();
         let aspect_ratio = image_size.width / image_size.height;
 
         let view_projection =
-            camera::calculate_view_projection(&html_element_size, &VIEW_SIZE, camera, aspect_ratio);
+            camera::calculate_view_projection(html_element_size, &VIEW_SIZE, camera, aspect_ratio);
 
         let pixels_info =
-            calculate_pixels_information(&image_size, &view_projection, &html_element_size);
+            calculate_pixels_information(&image_size, &view_projection, html_element_size);
         let enable_borders =
             pixels_info.image_pixel_size_device > config.minimum_size_to_render_pixel_border as _;
         let image_size = texture.image_size();
@@ -512,8 +698,25 @@ impl ImageRenderer {
         let pixels_info =
             calculate_pixels_information(&image_size, &view_projection, &html_element_size);
 
+        // the text is laid out on the pixels of the turned image, so it stays upright
+        let orientation = camera.orientation;
+        let text_projection = camera::calculate_camera_projection(
+            &html_element_size,
+            &VIEW_SIZE,
+            camera,
+            aspect_ratio,
+        );
+        let displayed_size = orientation.displayed_size(&image_size);
+        let image_pixels_to_view = Mat3::from_scale(Vec2::new(
+            VIEW_SIZE.width / displayed_size.width,
+            VIEW_SIZE.height / displayed_size.height,
+        ));
+
         let coloring_factors =
             calculate_color_matrix(texture_info, &texture.computed_info, drawing_options);
+        let pixel_value_format = rendering_context
+            .rendering_configuration()
+            .pixel_value_format;
 
         let is_batched = batch_item.is_some();
         let batch_index = batch_item.unwrap_or(0);
@@ -525,11 +728,6 @@ impl ImageRenderer {
 
         for x in pixels_info.lower_x_px..pixels_info.upper_x_px {
             for y in pixels_info.lower_y_px..pixels_info.upper_y_px {
-                let image_pixels_to_view = Mat3::from_scale(Vec2::new(
-                    VIEW_SIZE.width / texture.image_size().width,
-                    VIEW_SIZE.height / texture.image_size().height,
-                ));
-
                 let pixel = UVec2::new(x as _, y as _);
 
                 let batch_index = if is_batched { batch_index } else { 0 };
@@ -539,6 +737,12 @@ impl ImageRenderer {
                     &texture.bytes[&batch_index],
                     &pixel,
                 );
+                let normalized_values = pixel_value_format.show_normalized.then(|| {
+                    coloring::calculate_normalized_channel_values(&pixel_value, &coloring_factors)
+                });
+                let pixel_text = pixel_value
+                    .format_with(&pixel_value_format, normalized_values.as_deref())
+                    .join("\n");
 
                 // The actual pixel color might be different from the pixel value, depending on drawing options
                 let text_color = match drawing_options.coloring {
@@ -554,15 +758,16 @@ impl ImageRenderer {
                             }
                             _ => unreachable!(),
                         };
-                        let colormap = rendering_context
-                            .get_color_map(name)
-                            .expect("Could not get color map");
-                        let pixel_color = coloring::calculate_pixel_color_from_colormap(
-                            &pixel_value,
-                            &coloring_factors,
-                            colormap.as_ref(),
-                            drawing_options,
-                        );
+                        // a colormap that can't be used is reported when the image is drawn
+                        let pixel_color = match rendering_context.get_color_map(name) {
+                            Ok(colormap) => coloring::calculate_pixel_color_from_colormap(
+                                &pixel_value,
+                                &coloring_factors,
+                                colormap.as_ref(),
+                                drawing_options,
+                            ),
+                            Err(_) => Vec4::new(0.0, 0.0, 0.0, 1.0),
+                        };
 
                         text_color(pixel_color, &DrawingOptions::default())
                     }
@@ -578,10 +783,10 @@ impl ImageRenderer {
 
                 rendering_data.text_renderer.render(PixelTextRenderingData {
                     pixel_text_cache,
-                    pixel_loc: &pixel,
-                    pixel_value: &pixel_value,
+                    pixel_loc: &orientation.displayed_pixel(pixel, &image_size),
+                    pixel_text: &pixel_text,
                     image_coords_to_view_coord_mat: &image_pixels_to_view,
-                    view_projection: &view_projection,
+                    view_projection: &text_projection,
                     text_color: &text_color,
                 });
             }
@@ -602,24 +807,12 @@ impl ImageRenderer {
         let (drawing_options, global_drawing_options) =
             rendering_context.drawing_options(&overlay_item.id, &DrawingContext::Overlay);
 
-        let colormap_texture = if Coloring::Heatmap == drawing_options.coloring {
-            let color_map_texture = rendering_context
-                .get_color_map_texture(&global_drawing_options.heatmap_colormap_name)
-                .expect("Could not get color map texture");
-
-            Some(color_map_texture.obj.clone())
-        } else if matches!(
-            drawing_options.coloring,
-            Coloring::Segmentation | Coloring::Edges
-        ) {
-            let color_map_texture = rendering_context
-                .get_color_map_texture(&global_drawing_options.segmentation_colormap_name)
-                .expect("Could not get color map texture");
-
-            Some(color_map_texture.obj.clone())
-        } else {
-            None
-        };
+        let colormap_texture = colormap_texture(
+            rendering_context,
+            &overlay_item.id,
+            &drawing_options,
+            &global_drawing_options,
+        );
 
         let mut uniform_values = HashMap::new();
 
@@ -629,7 +822,11 @@ impl ImageRenderer {
             texture,
             colormap_texture.as_ref(),
             batch_item,
-            image_view_data,
+            &Size {
+                width: image_view_data.html_element.client_width() as f32,
+                height: image_view_data.html_element.client_height() as f32,
+            },
+            &image_view_data.camera,
             &DrawingContext::Overlay,
             &mut uniform_values,
         );
@@ -701,26 +898,12 @@ impl ImageRenderer {
         let (drawing_options, global_drawing_options) =
             rendering_context.drawing_options(cv_id, &DrawingContext::BaseImage);
 
-        let colormap_texture = if Coloring::Heatmap == drawing_options.coloring {
-            let color_map_texture = rendering_context
-                .get_color_map_texture(&global_drawing_options.heatmap_colormap_name)
-                .expect("Could not get color map texture");
-
-            let tex = color_map_texture.obj.clone();
-            Some(tex)
-        } else if matches!(
-            drawing_options.coloring,
-            Coloring::Segmentation | Coloring::Edges
-        ) {
-            let color_map_texture = rendering_context
-                .get_color_map_texture(&global_drawing_options.segmentation_colormap_name)
-                .expect("Could not get color map texture");
-
-            let tex = color_map_texture.obj.clone();
-            Some(tex)
-        } else {
-            None
-        };
+        let colormap_texture = colormap_texture(
+            rendering_context,
+            cv_id,
+            &drawing_options,
+            &global_drawing_options,
+        );
 
         let mut uniform_values = HashMap::new();
 
@@ -730,7 +913,11 @@ impl ImageRenderer {
             &texture,
             colormap_texture.as_ref(),
             batch_item,
-            image_view_data,
+            &Size {
+                width: image_view_data.html_element.client_width() as f32,
+                height: image_view_data.html_element.client_height() as f32,
+            },
+            &image_view_data.camera,
             &DrawingContext::BaseImage,
             &mut uniform_values,
         );
@@ -0,0 +1,153 @@
+use anyhow::Result;
+use glam::{Mat3, Vec2, Vec4};
+use std::collections::HashMap;
+use std::rc::Rc;
+use web_sys::WebGl2RenderingContext;
+use web_sys::WebGl2RenderingContext as GL;
+
+use crate::common::camera;
+use crate::common::Size;
+use crate::webgl_utils::attributes::{create_buffer_info_from_arrays, Arrays};
+use crate::webgl_utils::draw::draw_buffer_info;
+use crate::webgl_utils::program::{set_buffers_and_attributes, set_uniforms};
+use crate::webgl_utils::{
+    self, ArraySpec, BindingPoint, BufferInfo, DrawMode, ProgramBundle, UniformValue,
+};
+
+use super::constants::VIEW_SIZE;
+use super::rendering_context::{LineProfileData, RenderingContext};
+use super::utils::scissor_view;
+
+const LINE_COLOR: Vec4 = Vec4::new(1.0, 0.85, 0.0, 1.0);
+const CURSOR_OUTLINE_COLOR: Vec4 = Vec4::new(0.0, 0.0, 0.0, 1.0);
+const CURSOR_SIZE: f32 = 6.0;
+
+pub(super) fn create_line_attributes(gl: &GL) -> Result<BufferInfo> {
+    // the position along the line is interpolated in the vertex shader
+    #[rustfmt::skip]
+    let a_line_position = ArraySpec {
+        name: "vin_position".to_string(),
+        data: (&[
+            0.0_f32, 0.0, // start
+            1.0, 0.0, // end
+        ] as &[f32]),
+        num_components: 2,
+        normalized: true,
+        stride: None,
+        target: BindingPoint::ArrayBuffer,
+    };
+
+    create_buffer_info_from_arrays(
+        gl,
+        Arrays {
+            f32_arrays: vec![a_line_position],
+            u8_arrays: vec![] as Vec<ArraySpec<Vec<u8>>>,
+        },
+        None,
+    )
+}
+
+struct Programs {
+    line: ProgramBundle,
+}
+
+struct RenderingData {
+    programs: Programs,
+    buffer: BufferInfo,
+}
+
+pub(crate) struct LineProfileRenderer {}
+
+impl LineProfileRenderer {
+    pub(crate) fn setup_rendering_callback(
+        rendering_context: Rc<dyn RenderingContext>,
+    ) -> Result<Box<dyn FnMut()>> {
+        let gl = rendering_context.gl().clone();
+
+        let programs = Self::create_programs(&gl)?;
+        let buffer = create_line_attributes(&gl)?;
+
+        let mut rendering_data = RenderingData { programs, buffer };
+
+        Ok(Box::new(move || {
+            LineProfileRenderer::render(&gl, &mut rendering_data, rendering_context.as_ref());
+        }))
+    }
+
+    fn create_programs(gl: &WebGl2RenderingContext) -> Result<Programs> {
+        let line = webgl_utils::program::GLProgramBuilder::create(gl)
+            .vertex_shader(include_str!("../shaders/line.vert"))
+            .fragment_shader(include_str!("../shaders/line.frag"))
+            .attribute("vin_position")
+            .build()?;
+        Ok(Programs { line })
+    }
+
+    fn render(
+        gl: &WebGl2RenderingContext,
+        rendering_data: &mut RenderingData,
+        rendering_context: &dyn RenderingContext,
+    ) {
+        for view_id in rendering_context.visible_nodes() {
+            if let Some(ref data) = rendering_context.get_line_profile_data(view_id) {
+                Self::render_line(gl, rendering_data, data);
+            }
+        }
+    }
+
+    fn render_line(
+        gl: &WebGl2RenderingContext,
+        rendering_data: &mut RenderingData,
+        data: &LineProfileData,
+    ) {
+        scissor_view(gl, &data.html_element);
+
+        let html_element_size = Size {
+            width: data.html_element.client_width() as f32,
+            height: data.html_element.client_height() as f32,
+        };
+        let aspect_ratio = data.image_size.width / data.image_size.height;
+        let view_projection = camera::calculate_view_projection(
+            &html_element_size,
+            &VIEW_SIZE,
+            &data.camera,
+            aspect_ratio,
+        );
+        let image_pixels_to_view = Mat3::from_scale(Vec2::new(
+            VIEW_SIZE.width / data.image_size.width,
+            VIEW_SIZE.height / data.image_size.height,
+        ));
+
+        let program = &rendering_data.programs.line;
+        let buffer = &rendering_data.buffer;
+        gl.use_program(Some(&program.program));
+        set_buffers_and_attributes(program, buffer);
+
+        let mut uniform_values = HashMap::from([
+            (
+                "u_projectionMatrix",
+                UniformValue::Mat3Owned(view_projection * image_pixels_to_view),
+            ),
+            ("u_start", UniformValue::Vec2Owned(data.line.start)),
+            ("u_end", UniformValue::Vec2Owned(data.line.end)),
+            ("u_point_size", UniformValue::FloatOwned(1.0)),
+            ("u_color", UniformValue::Vec4Owned(LINE_COLOR)),
+        ]);
+        set_uniforms(program, &uniform_values);
+        draw_buffer_info(gl, buffer, DrawMode::Lines);
+
+        if let Some(cursor) = data.cursor {
+            uniform_values.insert("u_start", UniformValue::Vec2Owned(cursor));
+            uniform_values.insert("u_end", UniformValue::Vec2Owned(cursor));
+            for (size, color) in [
+                (CURSOR_SIZE + 2.0, CURSOR_OUTLINE_COLOR),
+                (CURSOR_SIZE, LINE_COLOR),
+            ] {
+                uniform_values.insert("u_point_size", UniformValue::FloatOwned(size));
+                uniform_values.insert("u_color", UniformValue::Vec4Owned(color));
+                set_uniforms(program, &uniform_values);
+                draw_buffer_info(gl, buffer, DrawMode::Points);
+            }
+        }
+    }
+}
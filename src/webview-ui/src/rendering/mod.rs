@@ -5,3 +5,6 @@ pub mod rendering_context;
 pub mod colorbar_renderer;
 mod utils;
 pub mod image_renderer;
+pub mod line_profile_renderer;
+pub mod annotation_renderer;
+pub mod software_renderer;
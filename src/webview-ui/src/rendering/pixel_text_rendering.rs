@@ -9,7 +9,7 @@ use glyph_brush_layout::{GlyphPositioner, Layout, SectionGeometry, SectionText};
 use web_sys::{WebGl2RenderingContext, WebGlTexture};
 
 use crate::{
-    common::{Datatype, Size},
+    common::Size,
     webgl_utils::{
         self,
         draw::draw_buffer_info,
@@ -20,8 +20,6 @@ use crate::{
     },
 };
 
-use crate::common::pixel_value::PixelValue;
-
 pub(crate) struct PixelTextRenderer {
     gl: WebGl2RenderingContext,
     font: FontArc,
@@ -43,35 +41,9 @@ fn rect_to_positions(rect: Rect) -> [f32; 12] {
     ]
 }
 
-#[rustfmt::skip]
-fn format_pixel_value(pixel_value: &PixelValue) -> String {
-    let bytes_per_element = pixel_value.datatype.num_bytes();
-    (0..pixel_value.num_channels.into())
-        .map(|c| {
-            let start = c as usize * bytes_per_element;
-            let end = start + bytes_per_element;
-            let bytes = &pixel_value.bytes[start..end];
-            match pixel_value.datatype {
-                Datatype::Uint8 => format!("{}", u8::from_ne_bytes([bytes[0]])),
-                Datatype::Int8 => format!("{}", i8::from_ne_bytes([bytes[0]])),
-                Datatype::Float32 => {
-                    let value = f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
-                    format!("{:.7}", float_pretty_print::PrettyPrintFloat(value as f64))
-                }
-                Datatype::Bool => format!("{}", (bytes[0] != 0) as u8),
-                Datatype::Uint16 => format!("{}", u16::from_ne_bytes([bytes[0], bytes[1]])),
-                Datatype::Uint32 => format!("{}", u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
-                Datatype::Int16 => format!("{}", i16::from_ne_bytes([bytes[0], bytes[1]])),
-                Datatype::Int32 => format!("{}", i32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
-            }
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
-}
-
 struct PixelTextData {
     buffer_info: BufferInfo<ReusableBuffer>,
-    pixel_value: PixelValue,
+    pixel_text: String,
 }
 
 fn calculate_text_to_image(font_scale: f32, max_rows_cols: f32) -> Mat3 {
@@ -82,7 +54,7 @@ fn calculate_text_to_image(font_scale: f32, max_rows_cols: f32) -> Mat3 {
 }
 
 impl PixelTextData {
-    fn try_new(gl: &WebGl2RenderingContext, pixel_value: &PixelValue) -> Result<Self> {
+    fn try_new(gl: &WebGl2RenderingContext, pixel_text: &str) -> Result<Self> {
         let buffer_info = BufferInfo {
             num_elements: 6,
             attribs: vec![
@@ -112,7 +84,7 @@ impl PixelTextData {
 
         Ok(Self {
             buffer_info,
-            pixel_value: *pixel_value,
+            pixel_text: pixel_text.to_owned(),
         })
     }
 
@@ -204,7 +176,7 @@ impl GlyphTexture {
 
     fn prepare_glyphs(&mut self, font: &FontArc) {
         let scale = 100.0;
-        let required_letters = "0123456789., -+enNaif";
+        let required_letters = "0123456789., -+enNaifbcdx()";
         let glyphs = Layout::default().calculate_glyphs(
             &[&font],
             &SectionGeometry {
@@ -283,7 +255,8 @@ pub(crate) struct PixelTextCache(HashMap<UVec2, PixelTextData>);
 pub(crate) struct PixelTextRenderingData<'a> {
     pub pixel_text_cache: &'a mut PixelTextCache,
     pub pixel_loc: &'a UVec2,
-    pub pixel_value: &'a PixelValue,
+    /// The formatted pixel value, one line per channel.
+    pub pixel_text: &'a str,
     pub image_coords_to_view_coord_mat: &'a Mat3,
     pub view_projection: &'a Mat3,
     pub text_color: &'a Vec4,
@@ -311,9 +284,9 @@ impl PixelTextRenderer {
         })
     }
 
-    fn pixel_value_into_buffers(
+    fn pixel_text_into_buffers(
         pixel_loc: &UVec2,
-        pixel_value: &PixelValue,
+        pixel_text: &str,
         glyph_texture: &GlyphTexture,
         font: &FontArc,
         pixel_data: &mut PixelTextData,
@@ -329,7 +302,6 @@ impl PixelTextRenderer {
         let px = 0.0;
         let py = 0.0;
 
-        let pixel_text = format_pixel_value(pixel_value);
         // log::debug!("Pixel text: {}", pixel_text);
 
         let glyphs = Layout::default()
@@ -345,7 +317,7 @@ impl PixelTextRenderer {
                     ..SectionGeometry::default()
                 },
                 &[SectionText {
-                    text: &pixel_text,
+                    text: pixel_text,
                     scale: PxScale::from(font_scale),
                     font_id: glyph_brush_layout::FontId(0),
                 }],
@@ -381,23 +353,23 @@ impl PixelTextRenderer {
 
     fn get_cache_pixel<'a>(&self, data: PixelTextRenderingData<'a>) -> Result<&'a PixelTextData> {
         if let Some(pixel_data) = data.pixel_text_cache.0.get_mut(data.pixel_loc) {
-            if pixel_data.pixel_value != *data.pixel_value {
-                Self::pixel_value_into_buffers(
+            if pixel_data.pixel_text != data.pixel_text {
+                Self::pixel_text_into_buffers(
                     data.pixel_loc,
-                    data.pixel_value,
+                    data.pixel_text,
                     &self.glyph_texture,
                     &self.font,
                     pixel_data,
                 );
 
-                pixel_data.pixel_value = *data.pixel_value;
+                pixel_data.pixel_text = data.pixel_text.to_owned();
             }
         } else {
             // log::debug!("Creating new pixel text cache");
-            let mut pixel_data = PixelTextData::try_new(&self.gl, data.pixel_value)?;
-            Self::pixel_value_into_buffers(
+            let mut pixel_data = PixelTextData::try_new(&self.gl, data.pixel_text)?;
+            Self::pixel_text_into_buffers(
                 data.pixel_loc,
-                data.pixel_value,
+                data.pixel_text,
                 &self.glyph_texture,
                 &self.font,
                 &mut pixel_data,
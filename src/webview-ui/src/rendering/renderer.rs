@@ -1,18 +1,55 @@
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
 
 use wasm_bindgen::prelude::*;
-use web_sys::WebGl2RenderingContext;
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, WebGl2RenderingContext};
 
+use super::annotation_renderer::AnnotationRenderer;
 use super::colorbar_renderer::ColorBarRenderer;
 use super::image_renderer::ImageRenderer;
+use super::line_profile_renderer::LineProfileRenderer;
 use super::rendering_context::RenderingContext;
+use super::software_renderer::SoftwareRenderer;
 use super::utils::gl_canvas;
 
-pub(crate) struct Renderer {}
+thread_local! {
+    /// Renders a frame, set once the rendering is set up.
+    static RENDER_FRAME: RefCell<Option<Closure<dyn FnMut()>>> = const { RefCell::new(None) };
+    /// Counts the render frames set, a replaced one does not render anymore.
+    static RENDER_FRAME_GENERATION: Cell<u32> = const { Cell::new(0) };
+    /// Replaced render frames that a requested frame still calls.
+    static RETIRED_RENDER_FRAMES: RefCell<Vec<Closure<dyn FnMut()>>> =
+        const { RefCell::new(Vec::new()) };
+    static FRAME_REQUESTED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Renders the views on the next animation frame. Frames are only rendered when something
+/// shown changes, so an idle webview doesn't keep the GPU busy. Changes made while a frame is
+/// rendered request the next one, so frames run continuously while something is animated.
+pub(crate) fn request_render() {
+    if FRAME_REQUESTED.get() {
+        return;
+    }
+    RENDER_FRAME.with_borrow(|render_frame| {
+        if let Some(render_frame) = render_frame {
+            Renderer::request_animation_frame(render_frame);
+            FRAME_REQUESTED.set(true);
+        }
+    });
+}
+
+pub(crate) struct Renderer {
+    /// Requests a frame when the canvas or a view is resized.
+    resize_observer: Option<(web_sys::ResizeObserver, Closure<dyn FnMut()>)>,
+}
 
 impl Renderer {
     pub(crate) fn new() -> Self {
-        Self {}
+        Self {
+            resize_observer: None,
+        }
     }
 
     fn request_animation_frame(f: &Closure<dyn FnMut()>) {
@@ -22,12 +59,83 @@ impl Renderer {
             .expect("should register `requestAnimationFrame` OK");
     }
 
+    /// Observes the canvas, replacing the observer of a previous setup.
+    fn observe_resizes(&mut self, canvas: &HtmlCanvasElement) -> web_sys::ResizeObserver {
+        let on_resize = Closure::wrap(Box::new(request_render) as Box<dyn FnMut()>);
+        let resize_observer = web_sys::ResizeObserver::new(on_resize.as_ref().unchecked_ref())
+            .expect("Could not create ResizeObserver");
+        resize_observer.observe(canvas);
+
+        if let Some((previous, _)) = self
+            .resize_observer
+            .replace((resize_observer.clone(), on_resize))
+        {
+            previous.disconnect();
+        }
+        resize_observer
+    }
+
+    /// Renders the next frames with `render_frame`. A frame requested before still calls the
+    /// replaced one, which is kept until the new one first runs: frames run in the order they
+    /// were requested.
+    fn set_render_frame(mut render_frame: Box<dyn FnMut()>) {
+        let generation = RENDER_FRAME_GENERATION.get().wrapping_add(1);
+        RENDER_FRAME_GENERATION.set(generation);
+        let render_frame = Closure::wrap(Box::new(move || {
+            if RENDER_FRAME_GENERATION.get() != generation {
+                return;
+            }
+            RETIRED_RENDER_FRAMES.take();
+            render_frame();
+        }) as Box<dyn FnMut()>);
+
+        if let Some(previous) = RENDER_FRAME.replace(Some(render_frame)) {
+            if FRAME_REQUESTED.replace(false) {
+                RETIRED_RENDER_FRAMES.with_borrow_mut(|retired| retired.push(previous));
+            }
+        }
+    }
+
     pub(crate) fn setup_rendering(&mut self, rendering_context: Rc<dyn RenderingContext>) {
         log::debug!("Renderer::set_rendering_context");
-        Renderer::setup_rendering_callback_if_ready(rendering_context);
+        let resize_observer = self.observe_resizes(&gl_canvas(&rendering_context.gl()));
+        Renderer::setup_rendering_callback_if_ready(rendering_context, resize_observer);
+        request_render();
+    }
+
+    /// Draws the images on the CPU, for when WebGL2 is unavailable.
+    pub(crate) fn setup_software_rendering(
+        &mut self,
+        rendering_context: Rc<dyn RenderingContext>,
+        context: CanvasRenderingContext2d,
+    ) {
+        log::debug!("Renderer::setup_software_rendering");
+        let canvas = context.canvas().expect("2D context without a canvas");
+        let resize_observer = self.observe_resizes(&canvas);
+
+        let mut render_images =
+            SoftwareRenderer::setup_rendering_callback(Rc::clone(&rendering_context), context);
+
+        Renderer::set_render_frame(Box::new(move || {
+            FRAME_REQUESTED.set(false);
+
+            for view_id in rendering_context.visible_nodes() {
+                resize_observer.observe(&rendering_context.view_data(view_id).html_element);
+            }
+
+            // setting the size also clears the canvas
+            canvas.set_width(canvas.client_width() as _);
+            canvas.set_height(canvas.client_height() as _);
+
+            render_images();
+        }));
+        request_render();
     }
 
-    fn setup_rendering_callback_if_ready(rendering_context: Rc<dyn RenderingContext>) {
+    fn setup_rendering_callback_if_ready(
+        rendering_context: Rc<dyn RenderingContext>,
+        resize_observer: web_sys::ResizeObserver,
+    ) {
         let gl = rendering_context.gl().clone();
 
         gl.enable(WebGl2RenderingContext::SCISSOR_TEST);
@@ -35,6 +143,12 @@ impl Renderer {
         let mut render_image =
             ImageRenderer::setup_rendering_callback(Rc::clone(&rendering_context))
                 .expect("Could not setup rendering callback");
+        let mut render_annotations =
+            AnnotationRenderer::setup_rendering_callback(Rc::clone(&rendering_context))
+                .expect("Could not setup rendering callback");
+        let mut render_line_profile =
+            LineProfileRenderer::setup_rendering_callback(Rc::clone(&rendering_context))
+                .expect("Could not setup rendering callback");
         let mut render_colorbar =
             ColorBarRenderer::setup_rendering_callback(Rc::clone(&rendering_context))
                 .expect("Could not setup rendering callback");
@@ -42,37 +156,39 @@ impl Renderer {
         // Gloo-render's request_animation_frame has this extra closure
         // wrapping logic running every frame, unnecessary cost.
         // Here constructing the wrapped closure just once.
-        let cb = Rc::new(RefCell::new(None));
-
-        *cb.borrow_mut() = Some(Closure::wrap(Box::new({
-            let cb = Rc::clone(&cb);
-            move || {
-                if gl.is_context_lost() {
-                    // Drop our handle to this closure so that it will get cleaned
-                    // up once we return.
-                    let _ = cb.borrow_mut().take();
-                } else {
-                    // Clean the canvas
-                    gl.clear_color(0.0, 0.0, 0.0, 0.0);
-                    gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
-
-                    let canvas = gl_canvas(&gl);
-
-                    // The following two lines set the size (in CSS pixels) of
-                    // the drawing buffer to be identical to the size of the
-                    // canvas HTML element, as determined by CSS.
-                    canvas.set_width(canvas.client_width() as _);
-                    canvas.set_height(canvas.client_height() as _);
-
-                    render_image();
-
-                    render_colorbar();
-
-                    Renderer::request_animation_frame(cb.borrow().as_ref().unwrap());
-                }
+        let render_frame = Box::new(move || {
+            FRAME_REQUESTED.set(false);
+            if gl.is_context_lost() {
+                return;
+            }
+
+            // views can be resized without a change of the canvas size, observing the same
+            // element again does nothing
+            for view_id in rendering_context.visible_nodes() {
+                resize_observer.observe(&rendering_context.view_data(view_id).html_element);
             }
-        }) as Box<dyn FnMut()>));
 
-        Renderer::request_animation_frame(cb.borrow().as_ref().unwrap());
+            // Clean the canvas
+            gl.clear_color(0.0, 0.0, 0.0, 0.0);
+            gl.clear(WebGl2RenderingContext::COLOR_BUFFER_BIT);
+
+            let canvas = gl_canvas(&gl);
+
+            // The following two lines set the size (in CSS pixels) of
+            // the drawing buffer to be identical to the size of the
+            // canvas HTML element, as determined by CSS.
+            canvas.set_width(canvas.client_width() as _);
+            canvas.set_height(canvas.client_height() as _);
+
+            render_image();
+
+            render_annotations();
+
+            render_line_profile();
+
+            render_colorbar();
+        });
+
+        Renderer::set_render_frame(render_frame);
     }
 }
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use glam::Vec2;
 use std::rc::Rc;
 use yewdux::mrc::Mrc;
 
@@ -6,8 +7,12 @@ use web_sys::{HtmlElement, WebGl2RenderingContext};
 
 use crate::{
     application_state::{
+        annotations::Annotations,
         app_state::GlobalDrawingOptions,
+        image_errors::ImageError,
         images::{DrawingContext, ImageAvailability},
+        line_profile::Line,
+        thumbnails::{Thumbnail, ThumbnailKey},
         views::OverlayItem,
     },
     coloring::DrawingOptions,
@@ -33,6 +38,22 @@ pub(crate) struct ColorBarData {
     pub texture_image: Mrc<TextureImage>,
 }
 
+pub(crate) struct LineProfileData {
+    pub html_element: HtmlElement,
+    pub camera: camera::Camera,
+    pub image_size: Size,
+    pub line: Line,
+    pub cursor: Option<Vec2>,
+}
+
+pub(crate) struct AnnotationData {
+    pub html_element: HtmlElement,
+    pub camera: camera::Camera,
+    pub image_size: Size,
+    pub image_id: ViewableObjectId,
+    pub annotations: Mrc<Annotations>,
+}
+
 pub(crate) trait RenderingContext {
     fn gl(&self) -> WebGl2RenderingContext;
     fn visible_nodes(&self) -> Vec<ViewId>;
@@ -50,6 +71,13 @@ pub(crate) trait RenderingContext {
         colormap_name: &str,
     ) -> Result<Rc<webgl_utils::GLGuard<web_sys::WebGlTexture>>>;
     fn get_colorbar_data(&self, view_id: ViewId) -> Option<ColorBarData>;
+    fn get_line_profile_data(&self, view_id: ViewId) -> Option<LineProfileData>;
+    fn get_annotation_data(&self, view_id: ViewId) -> Option<AnnotationData>;
+    /// Images shown in the image list whose thumbnail is missing or outdated.
+    fn thumbnails_to_render(&self) -> Vec<(ViewableObjectId, ThumbnailKey)>;
+    fn set_thumbnail(&self, image_id: ViewableObjectId, thumbnail: Thumbnail);
+    /// Shows the error on the image, the state is only updated when the error is new.
+    fn report_image_error(&self, image_id: &ViewableObjectId, error: ImageError);
 }
 
 pub(crate) trait ViewContext {
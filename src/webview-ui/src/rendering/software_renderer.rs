@@ -0,0 +1,506 @@
+use anyhow::{anyhow, Result};
+use glam::{Mat3, UVec2, Vec2, Vec3, Vec4, Vec4Swizzles};
+use std::rc::Rc;
+use wasm_bindgen::Clamped;
+use web_sys::{CanvasRenderingContext2d, ImageData};
+
+use crate::application_state::app_state::GlobalDrawingOptions;
+use crate::application_state::image_errors::ImageError;
+use crate::application_state::images::{DrawingContext, ImageAvailability};
+use crate::application_state::thumbnails::{Thumbnail, ThumbnailKey, THUMBNAIL_SIZE};
+use crate::coloring::{calculate_color_matrix, Coloring, ColoringFactors, DrawingOptions};
+use crate::colormap::{ColorMap, ColorMapKind};
+use crate::common::camera;
+use crate::common::pixel_value::PixelValue;
+use crate::common::texture_image::TextureImage;
+use crate::common::{Channels, CurrentlyViewing, Size, ViewableObjectId};
+use crate::math_utils::image_calculations::calculate_pixels_information;
+
+use super::constants::VIEW_SIZE;
+use super::rendering_context::{ImageViewData, RenderingContext};
+
+// same as the image shaders
+const CHECKER_SIZE: f32 = 10.0;
+const WHITE_CHECKER: f32 = 0.9;
+const BLACK_CHECKER: f32 = 0.6;
+/// Thickness of the edge as a fraction of the pixel size.
+const EDGE_THICKNESS: f32 = 0.2;
+
+fn ndc_to_pixels(view_projection: &Mat3, image_size: &Size) -> Mat3 {
+    let image_pixels_to_view = Mat3::from_scale(Vec2::new(
+        VIEW_SIZE.width / image_size.width,
+        VIEW_SIZE.height / image_size.height,
+    ));
+    (*view_projection * image_pixels_to_view).inverse()
+}
+
+/// Change of the position in image pixels for one pixel of the target, along x and y.
+fn pixel_step(ndc_to_pixels: &Mat3, target_size: &Size) -> (Vec2, Vec2) {
+    // the y axis of the target points down, the one of normalized device coordinates up
+    (
+        ndc_to_pixels.transform_vector2(Vec2::new(2.0 / target_size.width, 0.0)),
+        ndc_to_pixels.transform_vector2(Vec2::new(0.0, -2.0 / target_size.height)),
+    )
+}
+
+/// Size of an image pixel on the target, in pixels of the target. The steps lie on either axis
+/// of the image, depending on the orientation of the view.
+fn image_pixel_size(pixel_step: (Vec2, Vec2)) -> f32 {
+    1.0 / pixel_step.0.length()
+}
+
+/// An image as drawn on a target, with what the image shaders get as uniforms.
+struct Layer<'a> {
+    texture: &'a TextureImage,
+    bytes: &'a [u8],
+    drawing_options: DrawingOptions,
+    coloring_factors: ColoringFactors,
+    colormap: Option<Rc<ColorMap>>,
+    /// Maps normalized device coordinates of the target to image pixels.
+    ndc_to_pixels: Mat3,
+    /// Change of the position in image pixels for one pixel of the target, along x and y.
+    pixel_step: (Vec2, Vec2),
+    enable_borders: bool,
+}
+
+impl<'a> Layer<'a> {
+    /// Returns `None` if the batch item is not loaded.
+    #[allow(clippy::too_many_arguments)]
+    fn new(
+        rendering_context: &dyn RenderingContext,
+        image_id: &ViewableObjectId,
+        texture: &'a TextureImage,
+        batch_item: Option<u32>,
+        drawing_options: DrawingOptions,
+        global_drawing_options: &GlobalDrawingOptions,
+        target_size: &Size,
+        camera: &camera::Camera,
+    ) -> Option<Self> {
+        let bytes = texture.bytes.get(&batch_item.unwrap_or(0))?;
+
+        let image_size = texture.image_size();
+        let aspect_ratio = image_size.width / image_size.height;
+        let view_projection =
+            camera::calculate_view_projection(target_size, &VIEW_SIZE, camera, aspect_ratio);
+        let ndc_to_pixels = ndc_to_pixels(&view_projection, &image_size);
+        let pixel_step = pixel_step(&ndc_to_pixels, target_size);
+
+        let config = rendering_context.rendering_configuration();
+        let pixels_info = calculate_pixels_information(&image_size, &view_projection, target_size);
+        let enable_borders =
+            pixels_info.image_pixel_size_device > config.minimum_size_to_render_pixel_border as _;
+
+        let coloring_factors =
+            calculate_color_matrix(&texture.info, &texture.computed_info, &drawing_options);
+        let colormap = colormap(
+            rendering_context,
+            image_id,
+            &drawing_options,
+            global_drawing_options,
+        );
+
+        Some(Self {
+            texture,
+            bytes,
+            drawing_options,
+            coloring_factors,
+            colormap,
+            ndc_to_pixels,
+            pixel_step,
+            enable_borders,
+        })
+    }
+
+    /// The position in image pixels drawn at `ndc`, if it is on the image.
+    fn position(&self, ndc: Vec2) -> Option<Vec2> {
+        let position = self.ndc_to_pixels.transform_point2(ndc);
+        let size = self.texture.image_size();
+        let on_image = position.x >= 0.0
+            && position.y >= 0.0
+            && position.x < size.width
+            && position.y < size.height;
+        on_image.then_some(position)
+    }
+
+    /// The channels of the pixel as the textures return them: missing channels are 0, and a
+    /// missing alpha is 1. Pixels outside the image are those of the closest edge.
+    fn sample(&self, x: i64, y: i64) -> Vec4 {
+        let info = &self.texture.info;
+        let pixel = UVec2::new(
+            x.clamp(0, info.width as i64 - 1) as u32,
+            y.clamp(0, info.height as i64 - 1) as u32,
+        );
+        let pixel_value = PixelValue::from_image_info(info, self.bytes, &pixel);
+        let mut sampled = Vec4::from(pixel_value.as_rgba_f32());
+        if info.channels != Channels::Four {
+            sampled.w = 1.0;
+        }
+        sampled
+    }
+
+    /// The color of the image at `position`, before the checkerboard is mixed in.
+    fn color(&self, position: Vec2) -> Vec4 {
+        let options = &self.drawing_options;
+        let factors = &self.coloring_factors;
+
+        let sampled = self.sample(position.x.floor() as i64, position.y.floor() as i64);
+        let is_nan = sampled.is_nan();
+        let mut color = if is_nan {
+            Vec4::new(0.0, 0.0, 0.0, 1.0)
+        } else {
+            let mut sampled = sampled;
+            // the clip values are only set for images with one channel
+            if self.texture.info.channels == Channels::One {
+                if let Some(min) = options.clip.min {
+                    sampled = sampled.xyz().max(Vec3::splat(min)).extend(sampled.w);
+                }
+                if let Some(max) = options.clip.max {
+                    sampled = sampled.xyz().min(Vec3::splat(max)).extend(sampled.w);
+                }
+            }
+            (factors.color_multiplier * (sampled / factors.normalization_factor)
+                + factors.color_addition)
+                .clamp(Vec4::ZERO, Vec4::ONE)
+        };
+
+        if options.invert {
+            color = (Vec3::ONE - color.xyz()).extend(color.w);
+        }
+        if let (false, Some(colormap)) = (is_nan, &self.colormap) {
+            color = sample_colormap(colormap, color.x).extend(color.w);
+        }
+        if options.coloring == Coloring::Edges && !self.is_edge(position) {
+            color = Vec4::new(0.0, 0.0, 0.0, 1.0);
+        }
+        if options.zeros_as_transparent && color.xyz() == Vec3::ZERO {
+            color.w = 0.0;
+        }
+        color
+    }
+
+    /// Whether `position` is close to a neighbor pixel with another value.
+    fn is_edge(&self, position: Vec2) -> bool {
+        let (x, y) = (position.x.floor() as i64, position.y.floor() as i64);
+        let current = self.sample(x, y).x;
+        let differs = |dx: i64, dy: i64| self.sample(x + dx, y + dy).x != current;
+
+        let fraction = position - position.floor();
+        let pixel_size = image_pixel_size(self.pixel_step);
+        let thickness = (pixel_size * EDGE_THICKNESS).max(1.0);
+        let near_left = fraction.x * pixel_size < thickness;
+        let near_right = (1.0 - fraction.x) * pixel_size < thickness;
+        let near_top = fraction.y * pixel_size < thickness;
+        let near_bottom = (1.0 - fraction.y) * pixel_size < thickness;
+
+        (near_top && differs(0, -1))
+            || (near_bottom && differs(0, 1))
+            || (near_left && differs(-1, 0))
+            || (near_right && differs(1, 0))
+            || (near_left && near_top && differs(-1, -1))
+            || (near_right && near_top && differs(1, -1))
+            || (near_left && near_bottom && differs(-1, 1))
+            || (near_right && near_bottom && differs(1, 1))
+    }
+
+    /// Brightness added on the borders of the pixels, once they are large enough.
+    fn border(&self, position: Vec2) -> f32 {
+        if !self.enable_borders {
+            return 0.0;
+        }
+        let step = self.pixel_step.0.x.abs().max(self.pixel_step.0.y.abs());
+        let border = |fraction: f32| {
+            ((-fraction / step + 0.5 / step).abs() - (0.5 / step - 1.0)).clamp(0.0, 1.0)
+        };
+        let fraction = position - position.floor();
+        border(fraction.x) + border(fraction.y)
+    }
+}
+
+/// The colormap of the coloring. A colormap that can't be used is reported on the image.
+fn colormap(
+    rendering_context: &dyn RenderingContext,
+    image_id: &ViewableObjectId,
+    drawing_options: &DrawingOptions,
+    global_drawing_options: &GlobalDrawingOptions,
+) -> Option<Rc<ColorMap>> {
+    let colormap_name = match drawing_options.coloring {
+        Coloring::Heatmap => &global_drawing_options.heatmap_colormap_name,
+        Coloring::Segmentation | Coloring::Edges => {
+            &global_drawing_options.segmentation_colormap_name
+        }
+        _ => return None,
+    };
+    match rendering_context.get_color_map(colormap_name) {
+        Ok(colormap) => Some(colormap),
+        Err(e) => {
+            rendering_context
+                .report_image_error(image_id, ImageError::Colormap(format!("{:#}", e)));
+            None
+        }
+    }
+}
+
+/// The color at `value` of the colormap texture, filtered like the texture.
+fn sample_colormap(colormap: &ColorMap, value: f32) -> Vec3 {
+    let map = &colormap.map;
+    let last = map.len() - 1;
+    let texel = value * map.len() as f32;
+    match colormap.kind {
+        ColorMapKind::Categorical => Vec3::from(map[(texel as usize).min(last)]),
+        ColorMapKind::Linear | ColorMapKind::Diverging => {
+            // texel centers are at half pixels
+            let position = (texel - 0.5).clamp(0.0, last as f32);
+            let index = position as usize;
+            let next = (index + 1).min(last);
+            Vec3::from(map[index]).lerp(Vec3::from(map[next]), position.fract())
+        }
+    }
+}
+
+fn checkerboard(x: f32, y: f32) -> f32 {
+    let is_white = ((x / CHECKER_SIZE).floor() + (y / CHECKER_SIZE).floor()).rem_euclid(2.0);
+    BLACK_CHECKER + (WHITE_CHECKER - BLACK_CHECKER) * is_white
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Draws the image, and the overlay over it, into RGBA pixels of the target, top row first.
+/// Pixels outside the image are transparent.
+fn draw(base: &Layer, overlay: Option<&Layer>, width: u32, height: u32) -> Vec<u8> {
+    let mut pixels = vec![0_u8; (width * height * 4) as usize];
+    for (index, pixel) in pixels.chunks_exact_mut(4).enumerate() {
+        let x = (index as u32 % width) as f32 + 0.5;
+        let y = (index as u32 / width) as f32 + 0.5;
+        let ndc = Vec2::new(2.0 * x / width as f32 - 1.0, 1.0 - 2.0 * y / height as f32);
+        let Some(position) = base.position(ndc) else {
+            continue;
+        };
+
+        let color = base.color(position);
+        let checker = Vec3::splat(checkerboard(x, y));
+        let mut rgb = checker.lerp(color.xyz(), color.w) + Vec3::splat(base.border(position));
+
+        if let Some((overlay, position)) =
+            overlay.and_then(|overlay| Some((overlay, overlay.position(ndc)?)))
+        {
+            let color = overlay.color(position);
+            let alpha = overlay.drawing_options.global_alpha * color.w;
+            rgb = rgb.clamp(Vec3::ZERO, Vec3::ONE).lerp(color.xyz(), alpha);
+        }
+
+        pixel.copy_from_slice(&[to_u8(rgb.x), to_u8(rgb.y), to_u8(rgb.z), 255]);
+    }
+    pixels
+}
+
+/// Draws the images on the CPU, into a 2D canvas, when WebGL2 is unavailable. The coloring
+/// follows the image shaders. The pixel values, annotations, line profiles and the colorbar are
+/// not drawn.
+pub(crate) struct SoftwareRenderer {}
+
+impl SoftwareRenderer {
+    pub(crate) fn setup_rendering_callback(
+        rendering_context: Rc<dyn RenderingContext>,
+        context: CanvasRenderingContext2d,
+    ) -> Box<dyn FnMut()> {
+        Box::new(move || {
+            SoftwareRenderer::render(&context, rendering_context.as_ref());
+        })
+    }
+
+    fn render(context: &CanvasRenderingContext2d, rendering_context: &dyn RenderingContext) {
+        for view_id in rendering_context.visible_nodes() {
+            let view_data = rendering_context.view_data(view_id);
+            if let Err(e) = SoftwareRenderer::render_view(context, rendering_context, &view_data) {
+                log::error!("SoftwareRenderer::render: {}", e);
+            }
+        }
+
+        for (image_id, key) in rendering_context.thumbnails_to_render() {
+            if let Some(pixels) =
+                SoftwareRenderer::render_thumbnail(rendering_context, &image_id, &key)
+            {
+                rendering_context.set_thumbnail(
+                    image_id,
+                    Thumbnail {
+                        key,
+                        pixels: Rc::new(pixels),
+                    },
+                );
+            }
+        }
+    }
+
+    /// Renders the image fitted in a thumbnail, and returns its RGBA pixels, top row first.
+    /// Returns `None` if the data is not available.
+    fn render_thumbnail(
+        rendering_context: &dyn RenderingContext,
+        image_id: &ViewableObjectId,
+        key: &ThumbnailKey,
+    ) -> Option<Vec<u8>> {
+        let ImageAvailability::Available(texture) = rendering_context.texture_by_id(image_id)
+        else {
+            return None;
+        };
+        let texture = texture.borrow();
+        let (_, global_drawing_options) =
+            rendering_context.drawing_options(image_id, &DrawingContext::BaseImage);
+        let layer = Layer::new(
+            rendering_context,
+            image_id,
+            &texture,
+            key.drawing_options.batch_item,
+            key.drawing_options.clone(),
+            &global_drawing_options,
+            &Size {
+                width: THUMBNAIL_SIZE as f32,
+                height: THUMBNAIL_SIZE as f32,
+            },
+            &camera::Camera::default(),
+        )?;
+        Some(draw(&layer, None, THUMBNAIL_SIZE, THUMBNAIL_SIZE))
+    }
+
+    fn render_view(
+        context: &CanvasRenderingContext2d,
+        rendering_context: &dyn RenderingContext,
+        image_view_data: &ImageViewData,
+    ) -> Result<()> {
+        let Some(cv) = &image_view_data.currently_viewing else {
+            return Ok(());
+        };
+        let image_id = cv.id();
+        let ImageAvailability::Available(texture) = rendering_context.texture_by_id(image_id)
+        else {
+            return Ok(());
+        };
+        let texture = texture.borrow();
+
+        let (drawing_options, global_drawing_options) =
+            rendering_context.drawing_options(image_id, &DrawingContext::BaseImage);
+        let batch_item = if matches!(cv, CurrentlyViewing::BatchItem(_)) {
+            let Some(batch_item) = drawing_options
+                .batch_item
+                .filter(|batch_item| texture.has_batch_item(*batch_item))
+            else {
+                return Ok(());
+            };
+            Some(batch_item)
+        } else {
+            None
+        };
+
+        let element = &image_view_data.html_element;
+        let (width, height) = (element.client_width(), element.client_height());
+        if width <= 0 || height <= 0 {
+            return Ok(());
+        }
+        let size = Size {
+            width: width as f32,
+            height: height as f32,
+        };
+        let camera = &image_view_data.camera;
+
+        let Some(base) = Layer::new(
+            rendering_context,
+            image_id,
+            &texture,
+            batch_item,
+            drawing_options,
+            &global_drawing_options,
+            &size,
+            camera,
+        ) else {
+            return Ok(());
+        };
+
+        let overlay = image_view_data
+            .overlay
+            .as_ref()
+            .filter(|overlay| !overlay.hidden)
+            .and_then(
+                |overlay| match rendering_context.texture_by_id(&overlay.id) {
+                    ImageAvailability::Available(texture) => Some((overlay, texture)),
+                    _ => None,
+                },
+            );
+        let overlay_texture = overlay.as_ref().map(|(_, texture)| texture.borrow());
+        let overlay = match (&overlay, &overlay_texture) {
+            (Some((overlay, _)), Some(texture)) => {
+                let (drawing_options, global_drawing_options) =
+                    rendering_context.drawing_options(&overlay.id, &DrawingContext::Overlay);
+                Layer::new(
+                    rendering_context,
+                    &overlay.id,
+                    texture,
+                    batch_item,
+                    drawing_options,
+                    &global_drawing_options,
+                    &size,
+                    camera,
+                )
+            }
+            _ => None,
+        };
+
+        let pixels = draw(&base, overlay.as_ref(), width as u32, height as u32);
+        let image_data = ImageData::new_with_u8_clamped_array_and_sh(
+            Clamped(&pixels),
+            width as u32,
+            height as u32,
+        )
+        .map_err(|e| anyhow!("Could not create the image data: {:?}", e))?;
+        let rect = element.get_bounding_client_rect();
+        context
+            .put_image_data(
+                &image_data,
+                rect.left() + element.client_left() as f64,
+                rect.top() + element.client_top() as f64,
+            )
+            .map_err(|e| anyhow!("Could not draw the image data: {:?}", e))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::camera::{Camera, Orientation};
+
+    fn pixel_size_with(camera: &Camera) -> f32 {
+        let target_size = Size {
+            width: 400.0,
+            height: 400.0,
+        };
+        let image_size = Size {
+            width: 20.0,
+            height: 20.0,
+        };
+        let view_projection =
+            camera::calculate_view_projection(&target_size, &VIEW_SIZE, camera, 1.0);
+        let ndc_to_pixels = ndc_to_pixels(&view_projection, &image_size);
+        image_pixel_size(pixel_step(&ndc_to_pixels, &target_size))
+    }
+
+    #[test]
+    fn pixel_size_does_not_depend_on_the_rotation() {
+        let camera = Camera::default();
+        let pixel_size = pixel_size_with(&camera);
+        assert!(pixel_size.is_finite() && pixel_size > 0.0);
+
+        for quarter_turns in 1..4 {
+            let rotated =
+                camera.with_orientation(Orientation::default().rotated_clockwise(quarter_turns));
+            let rotated_pixel_size = pixel_size_with(&rotated);
+            assert!(
+                (rotated_pixel_size - pixel_size).abs() < 1e-3,
+                "{} quarter turns: {} instead of {}",
+                quarter_turns,
+                rotated_pixel_size,
+                pixel_size
+            );
+        }
+    }
+}
@@ -0,0 +1,10 @@
+#version 300 es
+precision mediump float;
+
+layout(location = 0) out vec4 fout_color;
+
+uniform vec4 u_color;
+
+void main() {
+  fout_color = u_color;
+}
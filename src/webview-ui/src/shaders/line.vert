@@ -0,0 +1,17 @@
+#version 300 es
+precision mediump float;
+
+// x is the position along the line, in [0, 1]
+in vec2 vin_position;
+
+uniform mat3 u_projectionMatrix;
+uniform vec2 u_start;
+uniform vec2 u_end;
+uniform float u_point_size;
+
+void main() {
+  vec2 position = mix(u_start, u_end, vin_position.x);
+  vec3 p = u_projectionMatrix * vec3(position, 1);
+  gl_Position = vec4(p.xy, 0, 1);
+  gl_PointSize = u_point_size;
+}
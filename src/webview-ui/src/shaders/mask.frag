@@ -0,0 +1,31 @@
+#version 300 es
+precision mediump float;
+precision mediump usampler2D;
+
+in vec2 vout_uv;
+
+layout(location = 0) out vec4 fout_color;
+
+uniform usampler2D u_mask;
+uniform float u_opacity;
+
+// label 0 is the background, labels from 1 cycle through these colors.
+// Keep in sync with LABEL_COLORS in application_state/annotations.rs
+const vec3 LABEL_COLORS[8] = vec3[8](
+  vec3(230, 25, 75) / 255.0,
+  vec3(60, 180, 75) / 255.0,
+  vec3(255, 225, 25) / 255.0,
+  vec3(0, 130, 200) / 255.0,
+  vec3(245, 130, 48) / 255.0,
+  vec3(145, 30, 180) / 255.0,
+  vec3(70, 240, 240) / 255.0,
+  vec3(240, 50, 230) / 255.0
+);
+
+void main() {
+  uint label = texture(u_mask, vout_uv).r;
+  if (label == 0u) {
+    discard;
+  }
+  fout_color = vec4(LABEL_COLORS[(label - 1u) % 8u], u_opacity);
+}
@@ -1,7 +1,8 @@
 use std::collections::HashMap;
 
 use crate::common::{
-    AppMode, Channels, DataOrdering, Datatype, SessionId, ValueVariableKind, ViewableObjectId,
+    drawing_preset::DrawingPreset, AppMode, Channels, DataOrdering, Datatype, SessionId,
+    ValueVariableKind, ViewableObjectId,
 };
 
 #[derive(tsify::Tsify, serde::Serialize, serde::Deserialize, Debug, Clone)]
@@ -52,6 +53,7 @@ pub(crate) struct ImagePlaceholders(pub Vec<ImagePlaceholderMessage>);
 #[derive(tsify::Tsify, serde::Deserialize, Debug)]
 pub(crate) struct ReplaceData {
     pub session_id: Option<SessionId>,
+    pub frame_id: Option<u32>,
     pub replacement_images: ImagePlaceholders,
 }
 
@@ -59,6 +61,17 @@ pub(crate) struct ReplaceData {
 pub(crate) struct Configuration {
     pub invert_scroll_direction: Option<bool>,
     pub auto_update_images: Option<String>,
+    pub float_notation: Option<String>,
+    pub float_precision: Option<u32>,
+    pub integer_base: Option<String>,
+    pub show_normalized_value: Option<bool>,
+    /// Key bindings by command name, overriding the default ones.
+    pub key_bindings: Option<HashMap<String, String>>,
+    pub remember_drawing_options: Option<String>,
+    pub drawing_presets: Option<Vec<DrawingPreset>>,
+    pub memory_budget_mb: Option<u32>,
+    pub prefetch_images: Option<u32>,
+    pub prefetch_batch_items: Option<u32>,
 }
 
 #[derive(tsify::Tsify, serde::Deserialize, Debug)]
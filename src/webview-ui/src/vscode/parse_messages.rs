@@ -1,13 +1,130 @@
 use std::convert::{TryFrom, TryInto};
 
+use itertools::Itertools;
+use thiserror::Error;
+
 use crate::{
     application_state::app_state::ImageObject,
-    common::{pixel_value::PixelValue, ComputedInfo, ImageData, ImageInfo, ImagePlaceholder},
-    math_utils::image_calculations::image_minmax_on_bytes,
+    common::{
+        pixel_value::PixelValue, ComputedInfo, Datatype, ImageData, ImageInfo, ImagePlaceholder,
+    },
 };
 
 use super::messages::{ImageMessage, ImagePlaceholderMessage};
 
+/// Why the bytes of an [`ImageMessage`] are not the image it describes.
+#[derive(Error, Debug, PartialEq)]
+pub(crate) enum InvalidImageMessage {
+    #[error("image size {width}×{height} is empty")]
+    EmptyImage { width: u32, height: u32 },
+    #[error("batched image without batch_size and batch_items_range")]
+    MissingBatchInfo,
+    #[error("batch items range {start}..{end} is empty")]
+    EmptyBatchItemsRange { start: u32, end: u32 },
+    #[error("batch items range {start}..{end} is out of a batch of size {batch_size}")]
+    BatchItemsOutOfRange {
+        start: u32,
+        end: u32,
+        batch_size: u32,
+    },
+    #[error("image of {shape} bytes is too large")]
+    TooLarge { shape: String },
+    #[error("byte length {length} is not a multiple of the {datatype:?} size {size}")]
+    Misaligned {
+        length: usize,
+        datatype: Datatype,
+        size: usize,
+    },
+    #[error("byte length {length} != {shape}")]
+    ByteLength { length: usize, shape: String },
+    #[error("{name} has {length} values, the image has {channels} channels")]
+    RangeChannels {
+        name: &'static str,
+        length: usize,
+        channels: u32,
+    },
+}
+
+/// Checks that the bytes of the message hold the image it describes, so that they can be sliced
+/// by its shape.
+pub(crate) fn validate_image_message(message: &ImageMessage) -> Result<(), InvalidImageMessage> {
+    let ImageMessage {
+        width,
+        height,
+        channels,
+        datatype,
+        bytes,
+        ..
+    } = message;
+    if *width == 0 || *height == 0 {
+        return Err(InvalidImageMessage::EmptyImage {
+            width: *width,
+            height: *height,
+        });
+    }
+
+    let num_items = match (message.batch_size, message.batch_items_range) {
+        (Some(batch_size), Some((start, end))) => {
+            if start >= end {
+                return Err(InvalidImageMessage::EmptyBatchItemsRange { start, end });
+            }
+            if end > batch_size {
+                return Err(InvalidImageMessage::BatchItemsOutOfRange {
+                    start,
+                    end,
+                    batch_size,
+                });
+            }
+            Some(end - start)
+        }
+        _ if message.is_batched => return Err(InvalidImageMessage::MissingBatchInfo),
+        _ => None,
+    };
+
+    let shape = num_items
+        .into_iter()
+        .chain([
+            *height,
+            *width,
+            *channels as u32,
+            datatype.num_bytes() as u32,
+        ])
+        .collect::<Vec<_>>();
+    let shape_string = || shape.iter().join("×");
+    let expected = shape
+        .iter()
+        .try_fold(1_u64, |size, dim| size.checked_mul(*dim as u64))
+        .ok_or_else(|| InvalidImageMessage::TooLarge {
+            shape: shape_string(),
+        })?;
+
+    if bytes.len() % datatype.num_bytes() != 0 {
+        return Err(InvalidImageMessage::Misaligned {
+            length: bytes.len(),
+            datatype: *datatype,
+            size: datatype.num_bytes(),
+        });
+    }
+    if bytes.len() as u64 != expected {
+        return Err(InvalidImageMessage::ByteLength {
+            length: bytes.len(),
+            shape: shape_string(),
+        });
+    }
+
+    for (name, values) in [("min", &message.min), ("max", &message.max)] {
+        if let Some(values) = values.as_ref().filter(|v| v.len() != *channels as usize) {
+            return Err(InvalidImageMessage::RangeChannels {
+                name,
+                length: values.len(),
+                channels: *channels as u32,
+            });
+        }
+    }
+
+    Ok(())
+}
+
 impl From<ImageMessage> for ImageInfo {
     fn from(image_message: ImageMessage) -> Self {
         Self {
@@ -34,6 +151,7 @@ impl TryFrom<ImageMessage> for ImageData {
     type Error = anyhow::Error;
 
     fn try_from(image_message: ImageMessage) -> Result<Self, Self::Error> {
+        validate_image_message(&image_message)?;
         let ImageMessage { bytes, .. } = image_message;
         let info = ImageInfo {
             image_id: image_message.image_id,
@@ -54,18 +172,19 @@ impl TryFrom<ImageMessage> for ImageData {
             additional_info: image_message.additional_info,
         };
 
-        let (min, max) = if image_message.min.is_some() && image_message.max.is_some() {
-            (
+        // without min and max, they are computed with the other statistics off the UI thread
+        let computed_info = if image_message.min.is_some() && image_message.max.is_some() {
+            ComputedInfo::new(
                 TryInto::<PixelValue>::try_into(image_message.min.unwrap())?,
                 TryInto::<PixelValue>::try_into(image_message.max.unwrap())?,
             )
         } else {
-            image_minmax_on_bytes(&bytes, info.datatype, info.channels)
+            ComputedInfo::pending(info.datatype, info.channels)
         };
 
         Ok(Self {
             info,
-            computed_info: ComputedInfo { min, max },
+            computed_info,
             bytes,
         })
     }
@@ -100,3 +219,246 @@ impl TryFrom<ImagePlaceholderMessage> for ImageObject {
         )))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use glam::UVec2;
+
+    use super::*;
+    use crate::common::{Channels, DataOrdering, SessionId, ValueVariableKind, ViewableObjectId};
+
+    const DATATYPES: [Datatype; 8] = [
+        Datatype::Uint8,
+        Datatype::Uint16,
+        Datatype::Uint32,
+        Datatype::Float32,
+        Datatype::Int8,
+        Datatype::Int16,
+        Datatype::Int32,
+        Datatype::Bool,
+    ];
+    const CHANNELS: [Channels; 4] = [
+        Channels::One,
+        Channels::Two,
+        Channels::Three,
+        Channels::Four,
+    ];
+
+    fn message(width: u32, height: u32, channels: Channels, datatype: Datatype) -> ImageMessage {
+        let length = (width * height * channels as u32) as usize * datatype.num_bytes();
+        ImageMessage {
+            image_id: ViewableObjectId::new(&SessionId("session".to_string()), "image"),
+            value_variable_kind: ValueVariableKind::Variable,
+            expression: "image".to_string(),
+            width,
+            height,
+            channels,
+            datatype,
+            data_ordering: DataOrdering::HWC,
+            is_batched: false,
+            batch_size: None,
+            batch_items_range: None,
+            min: None,
+            max: None,
+            additional_info: HashMap::new(),
+            bytes: vec![0; length],
+        }
+    }
+
+    fn batched(mut message: ImageMessage, batch_size: u32, range: (u32, u32)) -> ImageMessage {
+        let num_items = range.1.saturating_sub(range.0) as usize;
+        message.bytes = message.bytes.repeat(num_items);
+        message.is_batched = true;
+        message.batch_size = Some(batch_size);
+        message.batch_items_range = Some(range);
+        message
+    }
+
+    /// xorshift, so that the malformed messages are the same on every run.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next_u64(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, n: u64) -> u32 {
+            (self.next_u64() % n) as u32
+        }
+
+        fn pick<T: Copy>(&mut self, values: &[T]) -> T {
+            values[self.below(values.len() as u64) as usize]
+        }
+    }
+
+    /// Reads every pixel of every batch item, as the renderer and the pixel inspector do.
+    fn read_all_pixels(image_data: &ImageData) {
+        let info = &image_data.info;
+        let num_items = info.batch_info.as_ref().map_or(1, |batch_info| {
+            let (start, end) = batch_info.batch_items_range;
+            end - start
+        });
+        let item_size = image_data.bytes.len() / num_items as usize;
+        for item_bytes in image_data.bytes.chunks(item_size) {
+            for y in 0..info.height {
+                for x in 0..info.width {
+                    PixelValue::from_image_info(info, item_bytes, &UVec2::new(x, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn accepts_consistent_messages() {
+        for (datatype, channels) in DATATYPES.into_iter().zip(CHANNELS.into_iter().cycle()) {
+            let image = message(5, 3, channels, datatype);
+            assert_eq!(validate_image_message(&image), Ok(()));
+            assert_eq!(validate_image_message(&batched(image, 4, (1, 3))), Ok(()));
+        }
+    }
+
+    #[test]
+    fn describes_byte_length_mismatch() {
+        let mut image = message(64, 64, Channels::Three, Datatype::Float32);
+        image.bytes.truncate(1000);
+        let error = validate_image_message(&image).unwrap_err();
+        assert_eq!(error.to_string(), "byte length 1000 != 64×64×3×4");
+
+        let mut image = batched(message(2, 2, Channels::One, Datatype::Uint8), 8, (0, 3));
+        image.bytes.push(0);
+        let error = validate_image_message(&image).unwrap_err();
+        assert_eq!(error.to_string(), "byte length 13 != 3×2×2×1×1");
+    }
+
+    #[test]
+    fn rejects_misaligned_bytes() {
+        let mut image = message(4, 4, Channels::One, Datatype::Uint16);
+        image.bytes.pop();
+        assert_eq!(
+            validate_image_message(&image),
+            Err(InvalidImageMessage::Misaligned {
+                length: 31,
+                datatype: Datatype::Uint16,
+                size: 2,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_batch_items_range() {
+        let image = batched(message(2, 2, Channels::One, Datatype::Uint8), 4, (3, 6));
+        assert_eq!(
+            validate_image_message(&image),
+            Err(InvalidImageMessage::BatchItemsOutOfRange {
+                start: 3,
+                end: 6,
+                batch_size: 4,
+            })
+        );
+
+        let image = batched(message(2, 2, Channels::One, Datatype::Uint8), 4, (2, 2));
+        assert_eq!(
+            validate_image_message(&image),
+            Err(InvalidImageMessage::EmptyBatchItemsRange { start: 2, end: 2 })
+        );
+
+        let mut image = message(2, 2, Channels::One, Datatype::Uint8);
+        image.is_batched = true;
+        image.batch_size = Some(4);
+        assert_eq!(
+            validate_image_message(&image),
+            Err(InvalidImageMessage::MissingBatchInfo)
+        );
+    }
+
+    #[test]
+    fn rejects_empty_and_huge_images() {
+        let image = message(0, 3, Channels::One, Datatype::Uint8);
+        assert_eq!(
+            validate_image_message(&image),
+            Err(InvalidImageMessage::EmptyImage {
+                width: 0,
+                height: 3,
+            })
+        );
+
+        let mut image = message(1, 1, Channels::Four, Datatype::Float32);
+        image.width = u32::MAX;
+        image.height = u32::MAX;
+        assert!(matches!(
+            validate_image_message(&image),
+            Err(InvalidImageMessage::TooLarge { .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_range_with_wrong_number_of_channels() {
+        let mut image = message(2, 2, Channels::Three, Datatype::Float32);
+        image.min = Some(vec![0.0; 3]);
+        image.max = Some(vec![1.0; 2]);
+        assert_eq!(
+            validate_image_message(&image),
+            Err(InvalidImageMessage::RangeChannels {
+                name: "max",
+                length: 2,
+                channels: 3,
+            })
+        );
+    }
+
+    #[test]
+    fn malformed_messages_do_not_panic() {
+        let mut rng = Rng(0x5eed_1234_abcd_9876);
+        let mut accepted = 0;
+        for _ in 0..5000 {
+            let width = rng.below(6);
+            let height = rng.below(6);
+            let channels = rng.pick(&CHANNELS);
+            let datatype = rng.pick(&DATATYPES);
+            let mut image = message(width, height, channels, datatype);
+            image.data_ordering = rng.pick(&[DataOrdering::HWC, DataOrdering::CHW]);
+
+            if rng.below(2) == 0 {
+                let batch_size = rng.below(5);
+                let start = rng.below(6);
+                let end = rng.below(6);
+                image = batched(image, batch_size, (start, end));
+                match rng.below(4) {
+                    0 => image.batch_size = None,
+                    1 => image.batch_items_range = None,
+                    _ => {}
+                }
+            }
+            match rng.below(4) {
+                // shape and bytes disagree
+                0 => image.width += 1,
+                1 => image.channels = rng.pick(&CHANNELS),
+                2 => {
+                    let length = image.bytes.len() as i64 + rng.below(9) as i64 - 4;
+                    image.bytes.resize(length.max(0) as usize, 0);
+                }
+                _ => {}
+            }
+            if rng.below(4) == 0 {
+                image.min = Some(vec![0.0; 1 + rng.below(4) as usize]);
+                image.max = Some(vec![1.0; 1 + rng.below(4) as usize]);
+            }
+
+            let valid = validate_image_message(&image).is_ok();
+            match ImageData::try_from(image) {
+                Ok(image_data) => {
+                    assert!(valid);
+                    read_all_pixels(&image_data);
+                    accepted += 1;
+                }
+                Err(_) => assert!(!valid),
+            }
+        }
+        assert!(accepted > 0);
+    }
+}
@@ -1,4 +1,9 @@
-use crate::{coloring::DrawingOptions, common::ViewableObjectId};
+use std::collections::HashMap;
+
+use crate::{
+    coloring::DrawingOptions,
+    common::{constants::MAX_REMEMBERED_DRAWING_OPTIONS, ViewableObjectId},
+};
 
 #[derive(Builder, Debug, Clone, PartialEq, Default, serde::Serialize, serde::Deserialize)]
 #[builder(setter(into), name = "HostExtensionStateUpdate")]
@@ -6,6 +11,58 @@ pub(crate) struct HostExtensionState {
     pub(crate) current_image_id: Option<ViewableObjectId>,
     pub(crate) current_image_expression: Option<String>,
     pub(crate) current_image_drawing_options: Option<DrawingOptions>,
+    /// Drawing options that differ from the defaults, by the key of their image, see
+    /// [`crate::configurations::RememberDrawingOptions::key`].
+    #[serde(default)]
+    pub(crate) remembered_drawing_options: HashMap<String, DrawingOptions>,
+    /// Keys of the remembered drawing options, least recently used first.
+    #[serde(default)]
+    pub(crate) remembered_drawing_options_order: Vec<String>,
+}
+
+impl HostExtensionState {
+    /// Remembers the drawing options of the key, or forgets them with `None`. Only the
+    /// `MAX_REMEMBERED_DRAWING_OPTIONS` most recently used keys are kept.
+    pub(crate) fn set_remembered_drawing_options(
+        &mut self,
+        key: String,
+        drawing_options: Option<DrawingOptions>,
+    ) {
+        match drawing_options {
+            Some(drawing_options) => {
+                self.remembered_drawing_options
+                    .insert(key.clone(), drawing_options);
+                self.touch_remembered_drawing_options(&key);
+            }
+            None => {
+                self.remembered_drawing_options.remove(&key);
+                self.remembered_drawing_options_order.retain(|k| *k != key);
+            }
+        }
+    }
+
+    /// Marks the drawing options of the key as used, so they are forgotten after the others.
+    pub(crate) fn touch_remembered_drawing_options(&mut self, key: &str) {
+        let remembered = &self.remembered_drawing_options;
+        let order = &mut self.remembered_drawing_options_order;
+        order.retain(|k| k != key && remembered.contains_key(k));
+        // keys remembered before the order was kept are the least recently used
+        let mut unordered = remembered
+            .keys()
+            .filter(|k| *k != key && !order.contains(*k))
+            .cloned()
+            .collect::<Vec<_>>();
+        unordered.sort();
+        order.splice(0..0, unordered);
+        if remembered.contains_key(key) {
+            order.push(key.to_owned());
+        }
+
+        let excess = order.len().saturating_sub(MAX_REMEMBERED_DRAWING_OPTIONS);
+        for forgotten in order.drain(..excess) {
+            self.remembered_drawing_options.remove(&forgotten);
+        }
+    }
 }
 
 pub(crate) fn update_host_extension_state(
@@ -26,5 +83,7 @@ pub(crate) fn update_host_extension_state(
         current_image_id: update_field!(current_image_id),
         current_image_expression: update_field!(current_image_expression),
         current_image_drawing_options: update_field!(current_image_drawing_options),
+        remembered_drawing_options: update_field!(remembered_drawing_options),
+        remembered_drawing_options_order: update_field!(remembered_drawing_options_order),
     }
 }
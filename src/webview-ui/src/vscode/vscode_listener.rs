@@ -1,4 +1,6 @@
-use crate::application_state::app_state::{AppState, ImageObject, StoreAction};
+use crate::application_state::app_state::{AppState, HistoryAction, ImageObject, StoreAction};
+use crate::application_state::image_errors::ImageError;
+use crate::commands::Keymap;
 use crate::common::{AppMode, ImageData, SessionId, ViewId};
 use crate::vscode::messages::*;
 use anyhow::Result;
@@ -68,7 +70,12 @@ impl VSCodeListener {
     fn handle_image_data_response(image_message: ImageMessage) -> Result<()> {
         let image_id = image_message.image_id.clone();
         let dispatch = Dispatch::<AppState>::global();
-        let image_data = ImageData::try_from(image_message)?;
+        let image_data = ImageData::try_from(image_message).inspect_err(|e| {
+            dispatch.apply(StoreAction::RejectImageData(
+                image_id.clone(),
+                ImageError::InvalidData(format!("{:#}", e)),
+            ));
+        })?;
 
         dispatch.apply(StoreAction::AddImageWithData(image_id.clone(), image_data));
         Ok(())
@@ -90,6 +97,7 @@ impl VSCodeListener {
     fn handle_replace_data_request(replace_data: ReplaceData) {
         let ReplaceData {
             session_id,
+            frame_id,
             replacement_images,
         } = replace_data;
         let dispatch = Dispatch::<AppState>::global();
@@ -103,6 +111,13 @@ impl VSCodeListener {
             log::error!("Unable to parse images: {:?}", errors);
         }
 
+        // archive the data of the previous step before it is replaced
+        if let (Some(session_id), Some(frame_id)) = (session_id.clone(), frame_id) {
+            dispatch.apply(HistoryAction::FrameChanged {
+                session_id,
+                frame_id,
+            });
+        }
         dispatch.apply(StoreAction::ReplaceData(images));
         if let Some(session_id) = session_id {
             dispatch.apply(StoreAction::SetActiveSession(session_id));
@@ -118,6 +133,38 @@ impl VSCodeListener {
             if let Some(auto_update_images_str) = configurations.auto_update_images {
                 state.configuration.auto_update_images = (&auto_update_images_str[..]).into();
             }
+            let pixel_value_format = &mut state.configuration.rendering.pixel_value_format;
+            if let Some(float_notation) = configurations.float_notation {
+                pixel_value_format.float_notation = (&float_notation[..]).into();
+            }
+            if let Some(float_precision) = configurations.float_precision {
+                pixel_value_format.precision = float_precision as usize;
+            }
+            if let Some(integer_base) = configurations.integer_base {
+                pixel_value_format.integer_base = (&integer_base[..]).into();
+            }
+            if let Some(show_normalized_value) = configurations.show_normalized_value {
+                pixel_value_format.show_normalized = show_normalized_value;
+            }
+            if let Some(key_bindings) = configurations.key_bindings {
+                state.configuration.keymap = Keymap::with_overrides(&key_bindings);
+            }
+            if let Some(remember_drawing_options) = configurations.remember_drawing_options {
+                state.configuration.remember_drawing_options =
+                    (&remember_drawing_options[..]).into();
+            }
+            if let Some(drawing_presets) = configurations.drawing_presets {
+                state.configuration.drawing_presets = drawing_presets;
+            }
+            if let Some(memory_budget_mb) = configurations.memory_budget_mb {
+                state.configuration.memory_budget_mb = memory_budget_mb;
+            }
+            if let Some(prefetch_images) = configurations.prefetch_images {
+                state.configuration.prefetch.images = prefetch_images;
+            }
+            if let Some(prefetch_batch_items) = configurations.prefetch_batch_items {
+                state.configuration.prefetch.batch_items = prefetch_batch_items;
+            }
         });
         Ok(())
     }
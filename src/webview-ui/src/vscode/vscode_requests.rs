@@ -7,6 +7,7 @@ use wasm_bindgen::JsValue;
 
 use yewdux::prelude::*;
 
+use crate::common::drawing_preset::DrawingPreset;
 use crate::common::ViewableObjectId;
 use crate::vscode::WebviewApi;
 
@@ -28,6 +29,9 @@ struct RequestBatchItemData {
     expression: String,
     batch_item: u32,
     currently_holding: Option<Vec<u32>>,
+    prefetch_ahead: Option<u32>,
+    /// Bounds the prefetched items.
+    batch_size: Option<u32>,
 }
 
 #[derive(tsify::Tsify, serde::Serialize, serde::Deserialize)]
@@ -47,6 +51,59 @@ struct SaveImage {
     expression: String,
 }
 
+/// A shape in image pixel coordinates.
+#[derive(tsify::Tsify, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "shape", rename_all = "lowercase")]
+pub(crate) enum AnnotationShape {
+    Polygon {
+        label: u8,
+        points: Vec<[f32; 2]>,
+    },
+    Box {
+        label: u8,
+        x: f32,
+        y: f32,
+        width: f32,
+        height: f32,
+    },
+}
+
+#[derive(tsify::Tsify, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "kind")]
+pub(crate) enum AnnotationContent {
+    /// A label per pixel, in row-major order.
+    Mask {
+        width: u32,
+        height: u32,
+        data: Vec<u8>,
+    },
+    Shapes {
+        width: u32,
+        height: u32,
+        shapes: Vec<AnnotationShape>,
+    },
+}
+
+#[derive(tsify::Tsify, serde::Serialize, serde::Deserialize)]
+struct SaveAnnotations {
+    image_id: ViewableObjectId,
+    expression: String,
+    content: AnnotationContent,
+}
+
+/// Asks the extension for a name for the preset, the name of `preset` is only a suggestion.
+#[derive(tsify::Tsify, serde::Serialize, serde::Deserialize)]
+struct SaveDrawingPreset {
+    preset: DrawingPreset,
+}
+
+/// An error of an image, written to the extension's log.
+#[derive(tsify::Tsify, serde::Serialize, serde::Deserialize)]
+struct ReportError {
+    image_id: ViewableObjectId,
+    message: String,
+}
+
 #[derive(tsify::Tsify, serde::Serialize, serde::Deserialize)]
 #[serde(tag = "type")]
 #[tsify(into_wasm_abi, from_wasm_abi)]
@@ -58,6 +115,9 @@ enum FromWebviewMessage {
     AddExpression(AddExpression),
     EditExpression(EditExpression),
     SaveImage(SaveImage),
+    SaveAnnotations(SaveAnnotations),
+    SaveDrawingPreset(SaveDrawingPreset),
+    ReportError(ReportError),
 }
 
 #[derive(tsify::Tsify, serde::Serialize, serde::Deserialize)]
@@ -154,6 +214,8 @@ impl VSCodeRequests {
         expression: String,
         batch_item: u32,
         currently_holding: Option<Vec<u32>>,
+        prefetch_ahead: Option<u32>,
+        batch_size: Option<u32>,
     ) -> MessageId {
         log::debug!("VSCodeRequests::request_batch_item_data: {:?}", image_id);
         Self::send_message(FromWebviewMessage::RequestBatchItemData(
@@ -162,6 +224,8 @@ impl VSCodeRequests {
                 expression,
                 batch_item,
                 currently_holding,
+                prefetch_ahead,
+                batch_size,
             },
         ))
     }
@@ -190,4 +254,31 @@ impl VSCodeRequests {
             expression,
         }))
     }
+
+    pub(crate) fn save_annotations(
+        image_id: ViewableObjectId,
+        expression: String,
+        content: AnnotationContent,
+    ) -> MessageId {
+        log::debug!("VSCodeRequests::save_annotations: {:?}", image_id);
+        Self::send_message(FromWebviewMessage::SaveAnnotations(SaveAnnotations {
+            image_id,
+            expression,
+            content,
+        }))
+    }
+
+    pub(crate) fn save_drawing_preset(preset: DrawingPreset) -> MessageId {
+        log::debug!("VSCodeRequests::save_drawing_preset: {:?}", preset.name);
+        Self::send_message(FromWebviewMessage::SaveDrawingPreset(SaveDrawingPreset {
+            preset,
+        }))
+    }
+
+    pub(crate) fn report_error(image_id: ViewableObjectId, message: String) -> MessageId {
+        Self::send_message(FromWebviewMessage::ReportError(ReportError {
+            image_id,
+            message,
+        }))
+    }
 }
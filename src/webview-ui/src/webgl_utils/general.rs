@@ -20,3 +20,8 @@ pub(crate) fn enable_extension(
         .get_extension(name)
         .map_err(|js_value| WebGlError::from_js_value(&js_value, "get_extension"))?)
 }
+
+/// Discards the errors recorded so far, like those of deleting the objects of a lost context.
+pub(crate) fn clear_errors(gl: &WebGl2RenderingContext) {
+    while gl.get_error() != WebGl2RenderingContext::NO_ERROR {}
+}
@@ -218,6 +218,12 @@ impl GLDrop for WebGlTexture {
     }
 }
 
+impl GLDrop for WebGlFramebuffer {
+    fn drop(&self, gl: &GL) {
+        gl.delete_framebuffer(Some(self));
+    }
+}
+
 pub(crate) struct GLGuard<T: GLDrop> {
     pub gl: GL,
     pub obj: T,
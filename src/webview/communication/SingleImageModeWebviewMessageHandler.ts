@@ -3,14 +3,19 @@ import type {
   FromWebviewMessageWithId,
   ImageMessage,
   MessageId,
+  ReportError,
   RequestImageData,
+  SaveAnnotations,
+  SaveDrawingPreset,
 } from '../webview';
 import type { WebviewCommunication } from './WebviewClient';
 import * as vscode from 'vscode';
-import { logTrace } from '../../Logging';
+import { logError, logTrace } from '../../Logging';
 import { errorMessage } from '../../utils/Result';
 import { disposeAll } from '../../utils/VSCodeUtils';
 import { WebviewRequests, WebviewResponses } from './createMessages';
+import { saveAnnotations } from './saveAnnotations';
+import { saveDrawingPreset } from './saveDrawingPreset';
 
 export class SingleImageModeWebviewMessageHandler implements vscode.Disposable {
   private _disposables: vscode.Disposable[] = [];
@@ -55,6 +60,18 @@ export class SingleImageModeWebviewMessageHandler implements vscode.Disposable {
     }
   }
 
+  async handleSaveAnnotations(_id: MessageId, args: SaveAnnotations) {
+    return saveAnnotations(args);
+  }
+
+  async handleSaveDrawingPreset(_id: MessageId, { preset }: SaveDrawingPreset) {
+    return saveDrawingPreset(preset);
+  }
+
+  async handleReportError(_id: MessageId, { image_id, message }: ReportError) {
+    logError(`Webview error for ${image_id[1]}:`, message);
+  }
+
   private async onWebviewMessage(messageWithId: FromWebviewMessageWithId) {
     logTrace('Received message from webview', messageWithId);
 
@@ -67,6 +84,12 @@ export class SingleImageModeWebviewMessageHandler implements vscode.Disposable {
         return this.handleWebviewReady(id);
       case 'RequestImageData':
         return this.handleImageDataRequest(id, message);
+      case 'SaveAnnotations':
+        return this.handleSaveAnnotations(id, message);
+      case 'SaveDrawingPreset':
+        return this.handleSaveDrawingPreset(id, message);
+      case 'ReportError':
+        return this.handleReportError(id, message);
       // not need to handle these messages in single image mode
       case 'RequestImages':
       case 'RequestBatchItemData':
@@ -4,7 +4,10 @@ import type {
   FromWebviewMessageWithId,
   MessageId,
   RequestBatchItemData,
+  ReportError,
   RequestImageData,
+  SaveAnnotations,
+  SaveDrawingPreset,
   SaveImage,
 } from '../webview';
 import type { WebviewCommunication } from './WebviewClient';
@@ -28,6 +31,8 @@ import { Option } from '../../utils/Option';
 import { errorMessage } from '../../utils/Result';
 import { disposeAll } from '../../utils/VSCodeUtils';
 import { WebviewRequests, WebviewResponses } from './createMessages';
+import { saveAnnotations } from './saveAnnotations';
+import { saveDrawingPreset } from './saveDrawingPreset';
 
 export class WebviewMessageHandler implements vscode.Disposable {
   private _disposables: vscode.Disposable[] = [];
@@ -128,7 +133,10 @@ export class WebviewMessageHandler implements vscode.Disposable {
     }
 
     const start = Math.max(args.batch_item - 1, 0);
-    const stop = Math.max(args.batch_item + 1, 0);
+    const stop = Math.min(
+      Math.max(args.batch_item + 1 + (args.prefetch_ahead ?? 0), 0),
+      args.batch_size ?? Number.POSITIVE_INFINITY,
+    );
 
     const response = await serializeImageUsingSocketServer(
       objectItemKind === 'variable'
@@ -260,6 +268,18 @@ export class WebviewMessageHandler implements vscode.Disposable {
     }
   }
 
+  async handleSaveAnnotations(_id: MessageId, args: SaveAnnotations) {
+    return saveAnnotations(args);
+  }
+
+  async handleSaveDrawingPreset(_id: MessageId, { preset }: SaveDrawingPreset) {
+    return saveDrawingPreset(preset);
+  }
+
+  async handleReportError(_id: MessageId, { image_id, message }: ReportError) {
+    logError(`Webview error for ${image_id[1]}:`, message);
+  }
+
   async onWebviewMessage(messageWithId: FromWebviewMessageWithId) {
     logTrace('Received message from webview', messageWithId);
 
@@ -282,6 +302,12 @@ export class WebviewMessageHandler implements vscode.Disposable {
         return this.handleEditExpression(id, message);
       case 'SaveImage':
         return this.handleSaveImage(id, message);
+      case 'SaveAnnotations':
+        return this.handleSaveAnnotations(id, message);
+      case 'SaveDrawingPreset':
+        return this.handleSaveDrawingPreset(id, message);
+      case 'ReportError':
+        return this.handleReportError(id, message);
 
       default:
         ((_: never) => {
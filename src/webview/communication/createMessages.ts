@@ -11,7 +11,7 @@ import type {
   ValueVariableKind,
 } from '../webview';
 import { getConfiguration } from '../../config';
-import { sessionToId } from '../../session/Session';
+import { isDebugSession, sessionToId } from '../../session/Session';
 import { getSessionData } from '../../session/SessionData';
 import { hasValue, valueOrEval } from '../../utils/Utils';
 
@@ -79,6 +79,13 @@ function imageObjects(session: Option<Session>): ImagePlaceholderMessage[] {
   return objects;
 }
 
+function currentFrameId(session: Option<Session>): number | null {
+  if (session.none || !isDebugSession(session.val)) {
+    return null;
+  }
+  return getSessionData(session.val).debugVariablesTracker.frameId ?? null;
+}
+
 export class WebviewRequests {
   static setSessionNames(names: {
     [k: SessionId]: string;
@@ -98,6 +105,7 @@ export class WebviewRequests {
     return {
       type: 'ReplaceData',
       session_id: session.map(sessionToId).unwrapOr(null),
+      frame_id: currentFrameId(session),
       replacement_images: replacementImages,
     };
   }
@@ -117,6 +125,22 @@ export class WebviewRequests {
       invert_scroll_direction:
         getConfiguration('viewerUi.invertMouseWheelZoom') ?? null,
       auto_update_images: autoUpdateImages ?? 'true',
+      float_notation:
+        getConfiguration('viewerUi.pixelValues.floatNotation') ?? null,
+      float_precision:
+        getConfiguration('viewerUi.pixelValues.precision') ?? null,
+      integer_base:
+        getConfiguration('viewerUi.pixelValues.integerBase') ?? null,
+      show_normalized_value:
+        getConfiguration('viewerUi.pixelValues.showNormalized') ?? null,
+      key_bindings: getConfiguration('viewerUi.keyBindings') ?? null,
+      remember_drawing_options:
+        getConfiguration('viewerUi.rememberDrawingOptions') ?? null,
+      drawing_presets: getConfiguration('viewerUi.drawingPresets') ?? null,
+      memory_budget_mb: getConfiguration('viewerUi.memoryBudgetMB') ?? null,
+      prefetch_images: getConfiguration('viewerUi.prefetch.images') ?? null,
+      prefetch_batch_items:
+        getConfiguration('viewerUi.prefetch.batchItems') ?? null,
     };
   }
 
@@ -148,6 +172,7 @@ export class WebviewResponses {
     return {
       type: 'ReplaceData',
       session_id: session.map(sessionToId).unwrapOr(null),
+      frame_id: currentFrameId(session),
       replacement_images: replacementImages,
     };
   }
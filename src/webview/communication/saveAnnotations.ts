@@ -0,0 +1,67 @@
+import type { SaveAnnotations } from '../webview';
+import * as path from 'node:path';
+import Container from 'typedi';
+import * as vscode from 'vscode';
+import { ExtensionPersistentState } from '../../ExtensionPersistentState';
+import { logDebug } from '../../Logging';
+import { encodeUint8Npy } from '../../utils/Npy';
+
+function encodeAnnotations(content: SaveAnnotations['content']): {
+  bytes: Uint8Array;
+  suffix: string;
+  filters: Record<string, string[]>;
+} {
+  switch (content.kind) {
+    case 'Mask':
+      return {
+        bytes: encodeUint8Npy(content.width, content.height, content.data),
+        suffix: '_mask.npy',
+        filters: { 'NumPy Array': ['npy'] },
+      };
+    case 'Shapes':
+      return {
+        bytes: new TextEncoder().encode(JSON.stringify(content, null, 2)),
+        suffix: '_shapes.json',
+        filters: { JSON: ['json'] },
+      };
+  }
+}
+
+/**
+ * Saves annotations drawn in the webview: a label mask as a .npy file, or a list of shapes as JSON.
+ */
+export async function saveAnnotations({ expression, content }: SaveAnnotations) {
+  const { bytes, suffix, filters } = encodeAnnotations(content);
+
+  // Get last save directory from persistent state
+  const persistentState = Container.get(ExtensionPersistentState);
+  const lastSaveDir
+    = persistentState.workspace.get<string>('lastSaveAnnotationsDir')
+      ?? vscode.workspace.workspaceFolders?.[0]?.uri.fsPath
+      ?? '';
+
+  // Sanitize the expression to create a valid filename
+  const sanitizedName = expression.replace(/[<>:"/\\|?*]/g, '_');
+  const defaultPath = path.join(lastSaveDir, `${sanitizedName}${suffix}`);
+
+  const saveUri = await vscode.window.showSaveDialog({
+    defaultUri: vscode.Uri.file(defaultPath),
+    filters,
+    title: 'Save Annotations',
+  });
+
+  if (saveUri === undefined) {
+    return; // User cancelled
+  }
+
+  await persistentState.workspace.update(
+    'lastSaveAnnotationsDir',
+    path.dirname(saveUri.fsPath),
+  );
+
+  await vscode.workspace.fs.writeFile(saveUri, bytes);
+  logDebug(`Annotations saved to ${saveUri.fsPath}`);
+  vscode.window.showInformationMessage(
+    `Annotations saved: ${path.basename(saveUri.fsPath)}`,
+  );
+}
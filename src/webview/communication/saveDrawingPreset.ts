@@ -0,0 +1,49 @@
+import type { DrawingPreset } from '../webview';
+import * as vscode from 'vscode';
+import { EXTENSION_CONFIG_SECTION, getConfiguration } from '../../config';
+import { logDebug } from '../../Logging';
+
+/**
+ * Adds the preset to the list, replacing the preset with the same name. Options that are not
+ * set are left out, so the setting only lists what the preset changes.
+ */
+export function upsertDrawingPreset(
+  presets: readonly DrawingPreset[],
+  preset: DrawingPreset,
+): DrawingPreset[] {
+  const cleaned = Object.fromEntries(
+    Object.entries(preset).filter(([, value]) => value !== null && value !== undefined),
+  ) as DrawingPreset;
+  const index = presets.findIndex(p => p.name === preset.name);
+  if (index === -1) {
+    return [...presets, cleaned];
+  }
+  return presets.map((p, i) => (i === index ? cleaned : p));
+}
+
+/**
+ * Saves drawing options of the webview as a named preset in the `viewerUi.drawingPresets`
+ * setting, in the workspace settings when a workspace is open.
+ */
+export async function saveDrawingPreset(preset: DrawingPreset) {
+  const presets = getConfiguration('viewerUi.drawingPresets') ?? [];
+  const name = await vscode.window.showInputBox({
+    title: 'Save Drawing Preset',
+    prompt: 'Name of the preset, an existing preset with the same name is replaced',
+    value: preset.name,
+    validateInput: value => (value.trim() === '' ? 'The name cannot be empty' : undefined),
+  });
+  if (name === undefined) {
+    return; // User cancelled
+  }
+
+  const target = vscode.workspace.workspaceFolders === undefined
+    ? vscode.ConfigurationTarget.Global
+    : vscode.ConfigurationTarget.Workspace;
+  await vscode.workspace.getConfiguration(EXTENSION_CONFIG_SECTION).update(
+    'viewerUi.drawingPresets',
+    upsertDrawingPreset(presets, { ...preset, name: name.trim() }),
+    target,
+  );
+  logDebug(`Drawing preset saved: ${name}`);
+}
@@ -0,0 +1,41 @@
+import type { DrawingPreset } from '../../../src/webview/webview';
+import { describe, expect, it } from 'vitest';
+import { upsertDrawingPreset } from '../../../src/webview/communication/saveDrawingPreset';
+
+function preset(fields: Partial<DrawingPreset> & { name: string }): DrawingPreset {
+  return fields as DrawingPreset;
+}
+
+describe('upsertDrawingPreset', () => {
+  it('appends a preset with a new name', () => {
+    const presets = [preset({ name: 'depth', coloring: 'Heatmap' })];
+    const result = upsertDrawingPreset(presets, preset({ name: 'mask', coloring: 'Segmentation' }));
+    expect(result.map(p => p.name)).toEqual(['depth', 'mask']);
+  });
+
+  it('replaces the preset with the same name in place', () => {
+    const presets = [
+      preset({ name: 'depth', coloring: 'Heatmap' }),
+      preset({ name: 'mask', coloring: 'Segmentation' }),
+    ];
+    const result = upsertDrawingPreset(presets, preset({ name: 'depth', coloring: 'Grayscale' }));
+    expect(result).toEqual([
+      { name: 'depth', coloring: 'Grayscale' },
+      { name: 'mask', coloring: 'Segmentation' },
+    ]);
+  });
+
+  it('leaves out options that are not set', () => {
+    const result = upsertDrawingPreset(
+      [],
+      { name: 'depth', clipMin: 0, clipMax: null, heatmapColormap: undefined } as unknown as DrawingPreset,
+    );
+    expect(result).toEqual([{ name: 'depth', clipMin: 0 }]);
+  });
+
+  it('does not modify the given list', () => {
+    const presets = [preset({ name: 'depth' })];
+    upsertDrawingPreset(presets, preset({ name: 'mask' }));
+    expect(presets).toHaveLength(1);
+  });
+});
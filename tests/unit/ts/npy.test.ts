@@ -0,0 +1,35 @@
+import { describe, expect, it } from 'vitest';
+import { encodeUint8Npy } from '../../../src/utils/Npy';
+
+function decodeHeader(bytes: Uint8Array): { headerLength: number; header: string } {
+  const headerLength = new DataView(bytes.buffer).getUint16(8, true);
+  const header = String.fromCharCode(...bytes.slice(10, 10 + headerLength));
+  return { headerLength, header };
+}
+
+describe('encodeUint8Npy', () => {
+  it('starts with the magic string and version 1.0', () => {
+    const bytes = encodeUint8Npy(1, 1, [0]);
+    expect(Array.from(bytes.slice(0, 8))).toEqual([0x93, 78, 85, 77, 80, 89, 1, 0]);
+  });
+
+  it('describes a uint8 array of shape (height, width)', () => {
+    const { header } = decodeHeader(encodeUint8Npy(3, 2, [0, 1, 2, 3, 4, 5]));
+    expect(header).toContain('\'descr\': \'|u1\'');
+    expect(header).toContain('\'fortran_order\': False');
+    expect(header).toContain('\'shape\': (2, 3)');
+    expect(header.endsWith('\n')).toBe(true);
+  });
+
+  it('aligns the data to 64 bytes', () => {
+    const { headerLength } = decodeHeader(encodeUint8Npy(3, 2, [0, 1, 2, 3, 4, 5]));
+    expect((10 + headerLength) % 64).toBe(0);
+  });
+
+  it('writes the data after the header', () => {
+    const data = [0, 1, 2, 3, 4, 5];
+    const bytes = encodeUint8Npy(3, 2, data);
+    expect(Array.from(bytes.slice(bytes.length - data.length))).toEqual(data);
+    expect(bytes.length % 64).toBe(data.length);
+  });
+});
@@ -6,6 +6,6 @@ import webpackExtensionConfig from './webpack.extension.config.mjs';
 import webpackWebviewConfig from './webpack.webview.config.mjs';
 
 const [extensionConfig] = webpackExtensionConfig;
-const [webviewConfig, webview3rdParty] = webpackWebviewConfig;
+const [webviewConfig, webview3rdParty, computeWorker] = webpackWebviewConfig;
 
-export default [webviewConfig, extensionConfig, webview3rdParty];
+export default [webviewConfig, extensionConfig, webview3rdParty, computeWorker];
@@ -1,6 +1,11 @@
 import devConfigs from './webpack.config.mjs';
 
-const [WebviewConfigDev, extensionConfigDev, Webview3rdPartyDev] = devConfigs;
+const [
+  WebviewConfigDev,
+  extensionConfigDev,
+  Webview3rdPartyDev,
+  ComputeWorkerDev,
+] = devConfigs;
 
 /** @type {import('webpack').Configuration} */
 const prodConfig = {
@@ -20,4 +25,8 @@ export default [
     ...Webview3rdPartyDev,
     ...prodConfig,
   },
+  {
+    ...ComputeWorkerDev,
+    ...prodConfig,
+  },
 ];
@@ -151,4 +151,26 @@ const WebviewConfig = {
   },
 };
 
-export default [WebviewConfig, webview3rdParty];
+// Config for the worker that runs the pixel computations of the webview, see
+// src/webview-ui/compute_worker.js
+/** @type WebpackConfig */
+const ComputeWorkerConfig = {
+  ...baseConfig,
+  name: 'computeWorker',
+  target: 'webworker',
+  entry: {
+    compute_worker: path.resolve(webviewPath, 'compute_worker.js'),
+  },
+  output: {
+    path: dist,
+    filename: '[name].js',
+  },
+  // the wasm package is built by the webview config
+  dependencies: ['webview'],
+  experiments: {
+    asyncWebAssembly: true,
+    syncWebAssembly: true,
+  },
+};
+
+export default [WebviewConfig, webview3rdParty, ComputeWorkerConfig];